@@ -21,7 +21,36 @@ fn build_coords() -> Vec<Coord> {
         .collect()
 }
 
-fn build_flexpolyline(coords: &[Coord], precision: flexpolyline::Precision) -> flexpolyline::Polyline {
+fn build_route() -> Vec<Coord> {
+    let mut rng = StdRng::seed_from_u64(42);
+    // Steps between a metre and a kilometre or so, like a dense urban route
+    let step_magnitude = Uniform::from(-5.0..-2.0);
+    let step_sign = Uniform::from(0..2);
+    let mut coord = Coord {
+        x: -0.127758,
+        y: 51.507351,
+    };
+    (0..10_000)
+        .map(|_| {
+            let mut step = || {
+                let sign = if step_sign.sample(&mut rng) == 0 {
+                    -1.0
+                } else {
+                    1.0
+                };
+                sign * 10f64.powf(step_magnitude.sample(&mut rng))
+            };
+            coord.x += step();
+            coord.y += step();
+            coord
+        })
+        .collect()
+}
+
+fn build_flexpolyline(
+    coords: &[Coord],
+    precision: flexpolyline::Precision,
+) -> flexpolyline::Polyline {
     let coords = coords.iter().map(|c| (c.x, c.y));
     flexpolyline::Polyline::Data2d {
         coordinates: coords.collect(),
@@ -44,15 +73,25 @@ fn bench_encode(c: &mut Criterion) {
         })
     });
 
-    // This is just to compare us to another popular library. The format isn't identical so we
-    // don't expet performance to be identical, but it's some kind of touchstone.
-    // At time of commit, flexpolyline was ~20% slower at encoding than this crate.
-    c.bench_function("encode 10_000 coordinates at precision 1e-5 (flexpolyline)", |b| {
-        let pl = build_flexpolyline(&coords, flexpolyline::Precision::Digits5);
+    let route = build_route();
+    c.bench_function("encode 10_000 route coordinates at precision 1e-5", |b| {
         b.iter(|| {
-            black_box(pl.encode().unwrap());
+            black_box(encode_coordinates(route.iter().copied(), 5).unwrap());
         })
     });
+
+    // This is just to compare us to another popular library. The format isn't identical so we
+    // don't expet performance to be identical, but it's some kind of touchstone.
+    // At time of commit, flexpolyline was ~20% slower at encoding than this crate.
+    c.bench_function(
+        "encode 10_000 coordinates at precision 1e-5 (flexpolyline)",
+        |b| {
+            let pl = build_flexpolyline(&coords, flexpolyline::Precision::Digits5);
+            b.iter(|| {
+                black_box(pl.encode().unwrap());
+            })
+        },
+    );
 }
 
 #[allow(unused_must_use)]
@@ -75,12 +114,17 @@ fn bench_decode(c: &mut Criterion) {
     // This is just to compare us to another popular library. The format isn't identical so we
     // don't expet performance to be identical, but it's some kind of touchstone.
     // At time of commit, flexpolyline was ~12% slower at decoding than this crate.
-    c.bench_function("decode 10_000 coordinates at precision 1e-5 (flexpolyline)", |b| {
-        let encoded = build_flexpolyline(&coords, flexpolyline::Precision::Digits5).encode().unwrap();
-        b.iter(|| {
-            black_box(flexpolyline::Polyline::decode(&encoded).unwrap());
-        })
-    });
+    c.bench_function(
+        "decode 10_000 coordinates at precision 1e-5 (flexpolyline)",
+        |b| {
+            let encoded = build_flexpolyline(&coords, flexpolyline::Precision::Digits5)
+                .encode()
+                .unwrap();
+            b.iter(|| {
+                black_box(flexpolyline::Polyline::decode(&encoded).unwrap());
+            })
+        },
+    );
 }
 
 criterion_group!(benches, bench_encode, bench_decode,);
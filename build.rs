@@ -0,0 +1,77 @@
+//! Generates the lookup tables used by the encoder and decoder.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// Each character encodes a 6-bit chunk, offset into the printable ASCII range
+const OFFSET: u8 = 63;
+/// Marks bytes which can't appear in a polyline
+const INVALID_CHUNK: u8 = 0xff;
+
+fn main() {
+    let mut tables = String::new();
+
+    let encoding: Vec<u8> = (0..64).map(|chunk| chunk + OFFSET).collect();
+    writeln!(
+        tables,
+        "/// The character encoding each 6-bit chunk: five bits of the value, plus a continuation bit\n\
+         const ENCODING_TABLE: [u8; 64] = {encoding:?};"
+    )
+    .unwrap();
+
+    // Bytes above the printable range are accepted, matching the arithmetic decoder
+    let decoding: Vec<u8> = (0..=255u8)
+        .map(|byte| byte.checked_sub(OFFSET).unwrap_or(INVALID_CHUNK))
+        .collect();
+    writeln!(
+        tables,
+        "/// Marks bytes which can't appear in a polyline in [`DECODING_TABLE`]\n\
+         const INVALID_CHUNK: u8 = {INVALID_CHUNK};\n\
+         /// The chunk encoded by each byte: five bits of the value, plus a continuation bit\n\
+         const DECODING_TABLE: [u8; 256] = {decoding:?};"
+    )
+    .unwrap();
+
+    // The URL- and filename-safe base64 alphabet of RFC 4648, as used by Flexible Polyline
+    let base64: Vec<u8> = (b'A'..=b'Z')
+        .chain(b'a'..=b'z')
+        .chain(b'0'..=b'9')
+        .chain(*b"-_")
+        .collect();
+    let mut base64_decoding = [INVALID_CHUNK; 256];
+    for (chunk, &byte) in base64.iter().enumerate() {
+        base64_decoding[byte as usize] = chunk as u8;
+    }
+    writeln!(
+        tables,
+        "/// The base64 character encoding each 6-bit chunk\n\
+         const BASE64_ENCODING_TABLE: [u8; 64] = {base64:?};\n\
+         /// The chunk encoded by each base64 character, or [`INVALID_CHUNK`]\n\
+         const BASE64_DECODING_TABLE: [u8; 256] = {base64_decoding:?};"
+    )
+    .unwrap();
+
+    writeln!(
+        tables,
+        "/// The largest shift at which a 5-bit chunk still fits in a 64-bit value\n\
+         const MAX_SHIFT: u32 = {};",
+        u64::BITS - 5
+    )
+    .unwrap();
+
+    // Every power of ten up to 1e22 is exactly representable as an f64
+    let powers: Vec<f64> = (0..=22).map(|exponent| 10f64.powi(exponent)).collect();
+    writeln!(
+        tables,
+        "/// The scale factor for each precision\n\
+         const POWERS_OF_TEN: [f64; {}] = {powers:?};",
+        powers.len()
+    )
+    .unwrap();
+
+    let out_dir = env::var_os("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("tables.rs"), tables).unwrap();
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,145 @@
+/* Generated by cbindgen from the `capi` feature of the polyline crate. Do not edit. */
+
+#ifndef POLYLINE_H
+#define POLYLINE_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// A required pointer argument was null
+#define POLYLINE_ERR_NULL_POINTER -1
+
+// The input polyline was not valid UTF-8
+#define POLYLINE_ERR_INVALID_UTF8 -2
+
+// The input could not be decoded or encoded
+#define POLYLINE_ERR_CODEC -3
+
+// The output buffer is too small to hold the result
+#define POLYLINE_ERR_BUFFER_TOO_SMALL -4
+
+// A coordinate pair, laid out as two consecutive `double`s
+typedef struct PolylineCoord {
+  // The longitude
+  double x;
+  // The latitude
+  double y;
+} PolylineCoord;
+
+// A coordinate pair, laid out as two consecutive `float`s
+typedef struct PolylineCoordF32 {
+  // The longitude
+  float x;
+  // The latitude
+  float y;
+} PolylineCoordF32;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Returns a description of the most recent error on the calling thread, or null if no call
+// has failed yet.
+//
+// The returned string is owned by this crate and remains valid until the next failing call
+// on the same thread. It must not be freed by the caller.
+const char *polyline_last_error_message(void);
+
+// Decodes the `len` bytes of polyline starting at `ptr` into `out_ptr`, which has room for
+// `out_cap` coordinate pairs.
+//
+// Returns the number of coordinate pairs written. If `out_ptr` is null, nothing is written and
+// the number of coordinate pairs required is returned instead, so callers can size their buffer.
+//
+// # Safety
+//
+// `ptr` must be valid for reads of `len` bytes, and `out_ptr` must either be null or valid for
+// writes of `out_cap` coordinate pairs.
+ptrdiff_t decode_polyline_into_buffer(const char *ptr,
+                                      size_t len,
+                                      uint32_t precision,
+                                      struct PolylineCoord *out_ptr,
+                                      size_t out_cap);
+
+// Decodes `count` NUL-terminated polylines in a single call.
+//
+// The coordinate pairs of every polyline are written back to back into `out_ptr`, which has
+// room for `out_cap` pairs, and the number of pairs belonging to polyline `i` is written to
+// `out_lens[i]`. Returns the total number of coordinate pairs written. If `out_ptr` is null,
+// only `out_lens` is filled in and the total number of pairs required is returned.
+//
+// # Safety
+//
+// `polylines` must be valid for reads of `count` pointers, each of which must point to a
+// NUL-terminated string. `out_lens` must be valid for writes of `count` elements, and
+// `out_ptr` must either be null or valid for writes of `out_cap` coordinate pairs.
+ptrdiff_t decode_polylines_into_buffer(const char *const *polylines,
+                                       size_t count,
+                                       uint32_t precision,
+                                       struct PolylineCoord *out_ptr,
+                                       size_t out_cap,
+                                       size_t *out_lens);
+
+// Decodes the `len` bytes of polyline starting at `ptr`, invoking `cb` with the latitude,
+// longitude and `ctx` of every coordinate as soon as it has been decoded.
+//
+// Returns the number of coordinates passed to `cb`. If decoding fails part-way through, `cb`
+// will already have been invoked for the coordinates preceding the error.
+//
+// # Safety
+//
+// `ptr` must be valid for reads of `len` bytes, and `cb` must be safe to call with `ctx`.
+ptrdiff_t decode_polyline_with_callback(const char *ptr,
+                                        size_t len,
+                                        uint32_t precision,
+                                        void (*cb)(double lat, double lon, void *ctx),
+                                        void *ctx);
+
+// Encodes the `len` coordinate pairs starting at `ptr` into `out_ptr`, which has room for
+// `out_cap` bytes.
+//
+// Returns the number of bytes written. The output is not NUL-terminated. If `out_ptr` is null,
+// nothing is written and the number of bytes required is returned instead.
+//
+// # Safety
+//
+// `ptr` must be valid for reads of `len` coordinate pairs, and `out_ptr` must either be null or
+// valid for writes of `out_cap` bytes.
+ptrdiff_t encode_coordinates_into_buffer(const struct PolylineCoord *ptr,
+                                         size_t len,
+                                         uint32_t precision,
+                                         char *out_ptr,
+                                         size_t out_cap);
+
+// Like [`decode_polyline_into_buffer`], but writes `f32` coordinate pairs.
+//
+// # Safety
+//
+// `ptr` must be valid for reads of `len` bytes, and `out_ptr` must either be null or valid for
+// writes of `out_cap` coordinate pairs.
+ptrdiff_t decode_polyline_ffi_f32(const char *ptr,
+                                  size_t len,
+                                  uint32_t precision,
+                                  struct PolylineCoordF32 *out_ptr,
+                                  size_t out_cap);
+
+// Like [`encode_coordinates_into_buffer`], but reads `f32` coordinate pairs.
+//
+// # Safety
+//
+// `ptr` must be valid for reads of `len` coordinate pairs, and `out_ptr` must either be null or
+// valid for writes of `out_cap` bytes.
+ptrdiff_t encode_coordinates_ffi_f32(const struct PolylineCoordF32 *ptr,
+                                     size_t len,
+                                     uint32_t precision,
+                                     char *out_ptr,
+                                     size_t out_cap);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* POLYLINE_H */
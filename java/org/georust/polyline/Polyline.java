@@ -0,0 +1,26 @@
+package org.georust.polyline;
+
+/**
+ * Google Encoded Polyline encoding and decoding, backed by the polyline crate's `jni` feature.
+ */
+public final class Polyline {
+    static {
+        System.loadLibrary("polyline");
+    }
+
+    private Polyline() {}
+
+    /**
+     * Encodes parallel arrays of latitudes and longitudes as a polyline.
+     *
+     * @throws IllegalArgumentException if the arrays differ in length or a coordinate is out of bounds
+     */
+    public static native String encode(double[] lats, double[] lons, int precision);
+
+    /**
+     * Decodes a polyline into a two-element array holding the latitudes and the longitudes.
+     *
+     * @throws IllegalArgumentException if the polyline is invalid
+     */
+    public static native double[][] decode(String polyline, int precision);
+}
@@ -0,0 +1,214 @@
+//! Encoding and decoding combined with algorithms from the `geo` crate, enabled with the `geo`
+//! feature
+
+use crate::errors::PolylineError;
+use crate::{decode_polyline_map, encode_coordinates};
+use geo::{AffineTransform, Densify, Euclidean, Haversine, Simplify};
+use geo_types::{Coord, LineString};
+
+/// Decodes a Google Encoded Polyline, applying an affine transform to each coordinate in the
+/// same pass, for example to project it into a map tile's pixel space.
+///
+/// Coordinates are checked for errors before they're transformed.
+///
+/// # Examples
+///
+/// ```
+/// use geo::{AffineTransform, Densify, Euclidean, Haversine, Simplify};
+/// use geo_types::coord;
+/// use polyline;
+///
+/// let transform = AffineTransform::translate(1.0, -1.0);
+/// let decoded = polyline::decode_polyline_transformed("_ibE_seK", 5, &transform).unwrap();
+/// assert_eq!(decoded.0[0], coord! { x: 3.0, y: 0.0 });
+/// ```
+pub fn decode_polyline_transformed(
+    polyline: &str,
+    precision: u32,
+    transform: &AffineTransform<f64>,
+) -> Result<LineString<f64>, PolylineError> {
+    decode_polyline_map(polyline, precision, |coord| transform.apply(coord))
+}
+
+/// Encodes a Google Encoded Polyline, applying an affine transform to each coordinate in the
+/// same pass.
+///
+/// The transformed coordinates are checked for errors, just as [`encode_coordinates`] checks
+/// its input.
+///
+/// # Examples
+///
+/// ```
+/// use geo::{AffineTransform, Densify, Euclidean, Haversine, Simplify};
+/// use geo_types::line_string;
+/// use polyline;
+///
+/// // from tenths of a degree
+/// let transform = AffineTransform::scale(0.1, 0.1, (0.0, 0.0));
+/// let coords = line_string![(x: -1202.0, y: 385.0)];
+/// let encoded = polyline::encode_coordinates_transformed(coords, 5, &transform).unwrap();
+/// assert_eq!(encoded, "_p~iF~ps|U");
+/// ```
+pub fn encode_coordinates_transformed<C>(
+    coordinates: C,
+    precision: u32,
+    transform: &AffineTransform<f64>,
+) -> Result<String, PolylineError>
+where
+    C: IntoIterator<Item = Coord<f64>>,
+{
+    encode_coordinates(
+        coordinates.into_iter().map(|coord| transform.apply(coord)),
+        precision,
+    )
+}
+
+/// Simplifies a line with the Ramer–Douglas–Peucker algorithm, then encodes it as a Google
+/// Encoded Polyline.
+///
+/// `epsilon` is the furthest, in degrees, any coordinate may be from the simplified line, so
+/// polylines can be generated at a density suitable for the map they're displayed on.
+///
+/// # Examples
+///
+/// ```
+/// use geo_types::line_string;
+/// use polyline;
+///
+/// let coords = line_string![(x: 2.0, y: 1.0), (x: 3.0, y: 1.5), (x: 4.0, y: 2.0)];
+/// let simplified = polyline::encode_simplified(coords, 5, 0.01).unwrap();
+/// let ends = line_string![(x: 2.0, y: 1.0), (x: 4.0, y: 2.0)];
+/// assert_eq!(simplified, polyline::encode_coordinates(ends, 5).unwrap());
+/// ```
+pub fn encode_simplified<C>(
+    coordinates: C,
+    precision: u32,
+    epsilon: f64,
+) -> Result<String, PolylineError>
+where
+    C: IntoIterator<Item = Coord<f64>>,
+{
+    let line: LineString<f64> = coordinates.into_iter().collect();
+    encode_coordinates(line.simplify(epsilon), precision)
+}
+
+/// The greatest distance allowed between consecutive coordinates by [`encode_densified`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Spacing {
+    /// A distance in degrees, treating longitude and latitude as Cartesian coordinates
+    Degrees(f64),
+    /// A distance in meters, along great circles of a spherical Earth
+    Meters(f64),
+}
+
+/// Inserts coordinates into a line wherever consecutive coordinates are further apart than
+/// `spacing`, then encodes it as a Google Encoded Polyline.
+///
+/// This is for consumers which need a maximum distance between vertices, such as animations
+/// and snapping services. The indices of encoding errors refer to the densified coordinates.
+///
+/// # Panics
+///
+/// Panics if the spacing isn't positive.
+///
+/// # Examples
+///
+/// ```
+/// use geo_types::line_string;
+/// use polyline::{self, Spacing};
+///
+/// let coords = line_string![(x: 0.0, y: 0.0), (x: 0.0, y: 1.0)];
+/// let densified = polyline::encode_densified(coords, 5, Spacing::Degrees(0.25)).unwrap();
+/// assert_eq!(polyline::decode_polyline(&densified, 5).unwrap().0.len(), 5);
+/// ```
+pub fn encode_densified<C>(
+    coordinates: C,
+    precision: u32,
+    spacing: Spacing,
+) -> Result<String, PolylineError>
+where
+    C: IntoIterator<Item = Coord<f64>>,
+{
+    let line: LineString<f64> = coordinates.into_iter().collect();
+    let densified = match spacing {
+        Spacing::Degrees(degrees) => Euclidean.densify(&line, degrees),
+        Spacing::Meters(meters) => Haversine.densify(&line, meters),
+    };
+    encode_coordinates(densified, precision)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_polyline;
+    use geo::AffineOps;
+
+    #[test]
+    fn transforms() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let transform = AffineTransform::translate(1.0, -1.0);
+        let decoded = decode_polyline(polyline, 5).unwrap();
+        let transformed = decode_polyline_transformed(polyline, 5, &transform).unwrap();
+        assert_eq!(transformed, decoded.affine_transform(&transform));
+
+        let inverse = transform.inverse().unwrap();
+        let encoded = encode_coordinates_transformed(transformed, 5, &inverse).unwrap();
+        assert_eq!(encoded, polyline);
+
+        // transformed coordinates are checked
+        let transform = AffineTransform::translate(-100.0, 0.0);
+        assert!(matches!(
+            encode_coordinates_transformed(decoded, 5, &transform),
+            Err(PolylineError::LongitudeCoordError { .. })
+        ));
+    }
+
+    #[test]
+    fn simplifies() {
+        let coords: LineString<f64> = vec![
+            [0.0, 0.0],
+            [5.0, 4.0],
+            [11.0, 5.5],
+            [17.3, 3.2],
+            [27.8, 0.1],
+        ]
+        .into();
+        let encoded = encode_simplified(coords.clone(), 5, 1.0).unwrap();
+        assert_eq!(
+            encoded,
+            encode_coordinates(coords.simplify(1.0), 5).unwrap()
+        );
+        assert_eq!(decode_polyline(&encoded, 5).unwrap().0.len(), 4);
+
+        assert_eq!(
+            encode_simplified(LineString::new(vec![]), 5, 1.0).unwrap(),
+            ""
+        );
+        assert!(encode_simplified(vec![Coord { x: 181.0, y: 0.0 }], 5, 1.0).is_err());
+    }
+
+    #[test]
+    fn densifies() {
+        let coords: LineString<f64> = vec![[0.0, 0.0], [0.0, 1.0], [0.1, 1.0]].into();
+        let densified = encode_densified(coords.clone(), 5, Spacing::Degrees(0.3)).unwrap();
+        let expected: LineString<f64> = vec![
+            [0.0, 0.0],
+            [0.0, 0.25],
+            [0.0, 0.5],
+            [0.0, 0.75],
+            [0.0, 1.0],
+            [0.1, 1.0],
+        ]
+        .into();
+        assert_eq!(decode_polyline(&densified, 5).unwrap(), expected);
+
+        // a degree of latitude is about 111km, so it's split into three
+        let densified = encode_densified(coords, 5, Spacing::Meters(50_000.0)).unwrap();
+        assert_eq!(decode_polyline(&densified, 5).unwrap().0.len(), 5);
+
+        assert_eq!(
+            encode_densified(LineString::new(vec![]), 5, Spacing::Meters(1.0)).unwrap(),
+            ""
+        );
+    }
+}
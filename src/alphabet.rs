@@ -0,0 +1,158 @@
+//! Encoding and decoding polylines whose 6-bit chunks are written in another alphabet
+
+use crate::errors::PolylineError;
+use crate::{
+    decode_polyline_bytes, encode_coordinates, BASE64_DECODING_TABLE, BASE64_ENCODING_TABLE,
+    ENCODING_TABLE, INVALID_CHUNK,
+};
+use geo_types::{Coord, LineString};
+
+/// The characters a polyline's 6-bit chunks are written as.
+///
+/// Only the characters differ between alphabets: values are split into chunks and
+/// delta-encoded in the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Alphabet {
+    /// Chunks offset into printable ASCII, as in Google Encoded Polylines
+    #[default]
+    Google,
+    /// The URL- and filename-safe base64 alphabet of RFC 4648, as in HERE Flexible Polylines.
+    ///
+    /// Unlike the Google alphabet, this never needs escaping in URLs, JSON or file names.
+    UrlSafeBase64,
+}
+
+/// Encodes coordinates as a polyline whose chunks are written in `alphabet`.
+///
+/// # Examples
+///
+/// ```
+/// use polyline::{self, Alphabet};
+/// use geo_types::line_string;
+///
+/// let coords = line_string![(x: -120.2, y: 38.5), (x: -120.95, y: 40.7), (x: -126.453, y: 43.252)];
+/// let encoded =
+///     polyline::encode_coordinates_with_alphabet(coords, 5, Alphabet::UrlSafeBase64).unwrap();
+/// assert_eq!(encoded, "gx_qH_x09Wg2tNvvyEguyP35yhB");
+/// ```
+pub fn encode_coordinates_with_alphabet<C>(
+    coordinates: C,
+    precision: u32,
+    alphabet: Alphabet,
+) -> Result<String, PolylineError>
+where
+    C: IntoIterator<Item = Coord<f64>>,
+{
+    let encoded = encode_coordinates(coordinates, precision)?;
+    match alphabet {
+        Alphabet::Google => Ok(encoded),
+        Alphabet::UrlSafeBase64 => {
+            let mut encoded = encoded.into_bytes();
+            to_base64(&mut encoded);
+            // SAFETY: the base64 alphabet is ASCII
+            Ok(unsafe { String::from_utf8_unchecked(encoded) })
+        }
+    }
+}
+
+/// Decodes a polyline whose chunks are written in `alphabet`.
+///
+/// Characters outside the alphabet are reported as a [`PolylineError::DecodeError`] at their
+/// index.
+///
+/// # Examples
+///
+/// ```
+/// use polyline::{self, Alphabet};
+///
+/// let decoded =
+///     polyline::decode_polyline_with_alphabet("gx_qH_x09W", 5, Alphabet::UrlSafeBase64);
+/// assert_eq!(decoded, polyline::decode_polyline("_p~iF~ps|U", 5));
+/// ```
+pub fn decode_polyline_with_alphabet(
+    polyline: &str,
+    precision: u32,
+    alphabet: Alphabet,
+) -> Result<LineString<f64>, PolylineError> {
+    match alphabet {
+        Alphabet::Google => decode_polyline_bytes(polyline.as_bytes(), precision),
+        Alphabet::UrlSafeBase64 => {
+            decode_polyline_bytes(&from_base64(polyline.as_bytes()), precision)
+        }
+    }
+}
+
+/// Rewrites chunks encoded in the Google alphabet in the base64 alphabet.
+pub(crate) fn to_base64(encoded: &mut [u8]) {
+    for byte in encoded {
+        *byte = BASE64_ENCODING_TABLE[(*byte - ENCODING_TABLE[0]) as usize];
+    }
+}
+
+/// Rewrites chunks encoded in the base64 alphabet in the Google alphabet, replacing other
+/// characters with ones the decoder rejects.
+///
+/// Characters map one to one, so error indices carry over.
+pub(crate) fn from_base64(encoded: &[u8]) -> Vec<u8> {
+    encoded
+        .iter()
+        .map(|&byte| match BASE64_DECODING_TABLE[byte as usize] {
+            INVALID_CHUNK => 0,
+            chunk => ENCODING_TABLE[chunk as usize],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_polyline;
+
+    #[test]
+    fn base64_round_trips() {
+        let coords = [
+            Coord {
+                x: -0.00001,
+                y: 0.00001,
+            },
+            Coord { x: 0.0, y: 0.0 },
+        ];
+        assert_eq!(
+            encode_coordinates_with_alphabet(coords, 5, Alphabet::UrlSafeBase64).unwrap(),
+            "CBBC"
+        );
+
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let line = decode_polyline(polyline, 5).unwrap();
+        let encoded =
+            encode_coordinates_with_alphabet(line.clone(), 5, Alphabet::UrlSafeBase64).unwrap();
+        assert!(encoded
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_'));
+        assert_eq!(
+            decode_polyline_with_alphabet(&encoded, 5, Alphabet::UrlSafeBase64).unwrap(),
+            line
+        );
+        assert_eq!(
+            encode_coordinates_with_alphabet(line.clone(), 5, Alphabet::default()).unwrap(),
+            polyline
+        );
+        assert_eq!(
+            decode_polyline_with_alphabet(polyline, 5, Alphabet::Google).unwrap(),
+            line
+        );
+    }
+
+    #[test]
+    fn base64_reports_invalid_characters() {
+        assert_eq!(
+            decode_polyline_with_alphabet("gx_qH~", 5, Alphabet::UrlSafeBase64).unwrap_err(),
+            PolylineError::DecodeError { idx: 5 }
+        );
+        assert_eq!(
+            decode_polyline_with_alphabet("CBC", 5, Alphabet::UrlSafeBase64).unwrap_err(),
+            PolylineError::NoLongError { idx: 2 }
+        );
+    }
+}
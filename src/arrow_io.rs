@@ -0,0 +1,263 @@
+//! Conversion between Arrow arrays of polylines and GeoArrow `LineString` arrays, enabled with
+//! the `arrow` feature
+
+use crate::errors::PolylineError;
+use crate::{encode_coord, factor, Decoder};
+use arrow_array::cast::AsArray;
+use arrow_array::types::Float64Type;
+use arrow_array::{
+    Array, FixedSizeListArray, Float64Array, GenericListArray, GenericStringArray, OffsetSizeTrait,
+};
+use arrow_buffer::{Buffer, OffsetBuffer};
+use arrow_schema::{DataType, Field};
+use geo_types::Coord;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Returns the field of a GeoArrow `LineString` column named `name`, with interleaved
+/// coordinates, as produced by [`decode_polyline_array`].
+///
+/// The field carries the `geoarrow.linestring` extension name, so readers such as GeoParquet
+/// writers recognise the column as geometry.
+pub fn line_string_field(name: &str, nullable: bool) -> Field {
+    let data_type = DataType::List(Arc::new(vertices_field()));
+    Field::new(name, data_type, nullable).with_metadata(HashMap::from([(
+        "ARROW:extension:name".to_string(),
+        "geoarrow.linestring".to_string(),
+    )]))
+}
+
+fn vertices_field() -> Field {
+    let xy = Field::new("xy", DataType::Float64, false);
+    Field::new("vertices", DataType::FixedSizeList(Arc::new(xy), 2), false)
+}
+
+/// Decodes an Arrow array of Google Encoded Polylines as a GeoArrow `LineString` array.
+///
+/// Coordinates are interleaved, as `[x, y]` lists, and null polylines decode to null lines.
+/// The whole batch is decoded into a single coordinate buffer, so the first polyline which
+/// fails to decode is reported as an error; its index refers to that polyline.
+///
+/// # Examples
+///
+/// ```
+/// use arrow_array::{Array, StringArray};
+/// use polyline;
+///
+/// let polylines = StringArray::from(vec![Some("_p~iF~ps|U_ulLnnqC"), None]);
+/// let lines = polyline::decode_polyline_array(&polylines, 5).unwrap();
+/// assert_eq!(lines.value_length(0), 2);
+/// assert!(lines.is_null(1));
+/// ```
+pub fn decode_polyline_array<O: OffsetSizeTrait>(
+    polylines: &GenericStringArray<O>,
+    precision: u32,
+) -> Result<GenericListArray<O>, PolylineError> {
+    // each coordinate takes at least two bytes, so this holds every value
+    let mut coords = Vec::with_capacity(polylines.values().len());
+    let mut offsets = Vec::with_capacity(polylines.len() + 1);
+    offsets.push(O::usize_as(0));
+    for polyline in polylines.iter() {
+        if let Some(polyline) = polyline {
+            for coord in Decoder::new(polyline.as_bytes(), precision) {
+                let coord = coord?;
+                coords.push(coord.x);
+                coords.push(coord.y);
+            }
+        }
+        offsets.push(offset(coords.len() / 2)?);
+    }
+
+    let xy = Arc::new(Field::new("xy", DataType::Float64, false));
+    let vertices = FixedSizeListArray::new(xy, 2, Arc::new(Float64Array::from(coords)), None);
+    Ok(GenericListArray::new(
+        Arc::new(vertices_field()),
+        OffsetBuffer::new(offsets.into()),
+        Arc::new(vertices),
+        polylines.nulls().cloned(),
+    ))
+}
+
+/// Encodes a GeoArrow `LineString` array as an Arrow array of Google Encoded Polylines.
+///
+/// Both interleaved (`[x, y]` lists) and separated (`{x, y}` structs) coordinates are
+/// accepted, and null lines encode to null polylines. Any other layout, such as coordinates
+/// with a `z` dimension, is reported as a [`PolylineError::ArrowError`]. The whole batch is
+/// encoded into a single buffer, so the first line which fails to encode is reported as an
+/// error; its index refers to the coordinate within that line.
+///
+/// # Examples
+///
+/// ```
+/// use arrow_array::StringArray;
+/// use polyline;
+///
+/// let polylines = StringArray::from(vec!["_p~iF~ps|U_ulLnnqC"]);
+/// let lines = polyline::decode_polyline_array(&polylines, 5).unwrap();
+/// assert_eq!(polyline::encode_line_string_array(&lines, 5).unwrap(), polylines);
+/// ```
+pub fn encode_line_string_array<O: OffsetSizeTrait>(
+    lines: &GenericListArray<O>,
+    precision: u32,
+) -> Result<GenericStringArray<O>, PolylineError> {
+    let (xs, ys, stride) = coordinates(lines.values())?;
+    let factor = factor(precision);
+    let mut output = Vec::with_capacity(xs.len() * 4);
+    let mut offsets = Vec::with_capacity(lines.len() + 1);
+    offsets.push(O::usize_as(0));
+    for (i, range) in lines.offsets().windows(2).enumerate() {
+        if lines.is_valid(i) {
+            let mut previous = Coord { x: 0, y: 0 };
+            for (idx, position) in (range[0].as_usize()..range[1].as_usize()).enumerate() {
+                let coord = Coord {
+                    x: xs[position * stride],
+                    y: ys[position * stride],
+                };
+                encode_coord(idx, coord, factor, &mut previous, &mut output)?;
+            }
+        }
+        offsets.push(offset(output.len())?);
+    }
+
+    // SAFETY: the offsets are increasing, and encoded values are ASCII
+    Ok(unsafe {
+        GenericStringArray::new_unchecked(
+            OffsetBuffer::new_unchecked(offsets.into()),
+            Buffer::from_vec(output),
+            lines.nulls().cloned(),
+        )
+    })
+}
+
+/// Returns the x and y values of GeoArrow coordinates, along with the stride between them.
+fn coordinates(values: &dyn Array) -> Result<(&[f64], &[f64], usize), PolylineError> {
+    if let Some(interleaved) = values.as_fixed_size_list_opt() {
+        if interleaved.value_length() == 2 {
+            if let Some(xy) = interleaved.values().as_primitive_opt::<Float64Type>() {
+                let xy = xy.values();
+                return Ok((xy, xy.get(1..).unwrap_or_default(), 2));
+            }
+        }
+    } else if let Some(separated) = values.as_struct_opt() {
+        let column = |name| {
+            separated
+                .column_by_name(name)
+                .and_then(|column| column.as_primitive_opt::<Float64Type>())
+        };
+        if let (Some(x), Some(y), 2) = (column("x"), column("y"), separated.num_columns()) {
+            return Ok((x.values(), y.values(), 1));
+        }
+    }
+    Err(PolylineError::ArrowError {
+        message: format!(
+            "expected 2D GeoArrow coordinates of Float64, found {}",
+            values.data_type()
+        ),
+    })
+}
+
+fn offset<O: OffsetSizeTrait>(len: usize) -> Result<O, PolylineError> {
+    O::from_usize(len).ok_or_else(|| PolylineError::ArrowError {
+        message: format!("{} values overflow the array's offsets", len),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{ArrayRef, LargeStringArray, ListArray, StringArray, StructArray};
+    use arrow_buffer::NullBuffer;
+
+    #[test]
+    fn round_trips() {
+        let polylines = StringArray::from(vec![
+            Some("_p~iF~ps|U_ulLnnqC_mqNvxq`@"),
+            None,
+            Some(""),
+            Some("_ibE_seK"),
+        ]);
+        let lines = decode_polyline_array(&polylines, 5).unwrap();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines.value_offsets(), [0, 3, 3, 3, 4]);
+        assert_eq!(lines.null_count(), 1);
+        assert_eq!(
+            lines.data_type(),
+            line_string_field("geometry", true).data_type()
+        );
+        let last = lines.value(3);
+        let xy = last
+            .as_fixed_size_list()
+            .values()
+            .as_primitive::<Float64Type>();
+        assert_eq!(xy.values()[..], [2.0, 1.0]);
+
+        assert_eq!(encode_line_string_array(&lines, 5).unwrap(), polylines);
+        // sliced arrays
+        let sliced = lines.slice(2, 2);
+        assert_eq!(
+            encode_line_string_array(&sliced, 5).unwrap(),
+            StringArray::from(vec!["", "_ibE_seK"])
+        );
+
+        let large = LargeStringArray::from(vec!["_ibE_seK_seK_seK"]);
+        let lines = decode_polyline_array(&large, 5).unwrap();
+        assert_eq!(encode_line_string_array(&lines, 5).unwrap(), large);
+    }
+
+    #[test]
+    fn encodes_separated_coordinates() {
+        let x: ArrayRef = Arc::new(Float64Array::from(vec![-120.2, -120.95]));
+        let y: ArrayRef = Arc::new(Float64Array::from(vec![38.5, 40.7]));
+        let coords = StructArray::from(vec![
+            (Arc::new(Field::new("x", DataType::Float64, false)), x),
+            (Arc::new(Field::new("y", DataType::Float64, false)), y),
+        ]);
+        let field = Field::new("vertices", coords.data_type().clone(), false);
+        let lines = ListArray::new(
+            Arc::new(field),
+            OffsetBuffer::from_lengths([2, 0]),
+            Arc::new(coords),
+            Some(NullBuffer::from(vec![true, false])),
+        );
+        assert_eq!(
+            encode_line_string_array(&lines, 5).unwrap(),
+            StringArray::from(vec![Some("_p~iF~ps|U_ulLnnqC"), None])
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        let polylines = StringArray::from(vec!["_ibE_seK", "_ibE_seK_seK"]);
+        assert_eq!(
+            decode_polyline_array(&polylines, 5).unwrap_err(),
+            PolylineError::NoLongError { idx: 8 }
+        );
+
+        assert!(matches!(
+            encode_line_string_array(&interleaved(3, vec![1.0, 2.0, 3.0]), 5),
+            Err(PolylineError::ArrowError { .. })
+        ));
+        assert_eq!(
+            encode_line_string_array(&interleaved(2, vec![2.0, 1.0, 2.0, 91.0]), 5).unwrap_err(),
+            PolylineError::LatitudeCoordError {
+                coord: 91.0,
+                idx: 1
+            }
+        );
+    }
+
+    /// A single line of coordinates with `size` dimensions
+    fn interleaved(size: i32, values: Vec<f64>) -> ListArray {
+        let len = values.len() / size as usize;
+        let dimension = Arc::new(Field::new("xy", DataType::Float64, false));
+        let coords =
+            FixedSizeListArray::new(dimension, size, Arc::new(Float64Array::from(values)), None);
+        let field = Field::new("vertices", coords.data_type().clone(), false);
+        ListArray::new(
+            Arc::new(field),
+            OffsetBuffer::from_lengths([len]),
+            Arc::new(coords),
+            None,
+        )
+    }
+}
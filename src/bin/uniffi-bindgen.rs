@@ -0,0 +1,7 @@
+//! Generates Swift and Kotlin bindings for the `uniffi` feature, e.g.
+//! `cargo run --features uniffi-bindgen --bin uniffi-bindgen generate --library
+//! target/release/libpolyline.so --language swift --out-dir bindings`
+
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
@@ -0,0 +1,492 @@
+//! Stable C ABI for this crate, enabled with the `capi` feature
+//!
+//! The functions and types in this module are laid out for consumption by
+//! [cbindgen](https://github.com/mozilla/cbindgen); the generated header lives in
+//! `include/polyline.h` and can be regenerated with
+//! `cbindgen --config cbindgen.toml --output include/polyline.h`.
+//!
+//! # Ownership
+//!
+//! None of these functions allocate memory that has to be freed by the caller: the host
+//! language owns every buffer that crosses the boundary, and no pointer passed in is retained
+//! after a call returns. The only pointer handed out by this module is the one returned by
+//! [`polyline_last_error_message`], which is owned by this crate.
+//!
+//! Coordinates are passed as [`PolylineCoord`] structs in `(x, y)` / `(longitude, latitude)`
+//! order, matching the rest of this crate.
+//!
+//! Every function returns the number of elements written, or one of the negative
+//! `POLYLINE_ERR_*` codes on failure. A human-readable description of the most recent failure
+//! on the calling thread is available from [`polyline_last_error_message`].
+
+use crate::{decode_polyline, encode_coordinates, Decoder};
+use geo_types::Coord;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::fmt::Display;
+use std::os::raw::{c_char, c_void};
+
+/// A coordinate pair, laid out as two consecutive `double`s
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PolylineCoord {
+    /// The longitude
+    pub x: f64,
+    /// The latitude
+    pub y: f64,
+}
+
+/// A coordinate pair, laid out as two consecutive `float`s
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PolylineCoordF32 {
+    /// The longitude
+    pub x: f32,
+    /// The latitude
+    pub y: f32,
+}
+
+/// A required pointer argument was null
+pub const POLYLINE_ERR_NULL_POINTER: isize = -1;
+/// The input polyline was not valid UTF-8
+pub const POLYLINE_ERR_INVALID_UTF8: isize = -2;
+/// The input could not be decoded or encoded
+pub const POLYLINE_ERR_CODEC: isize = -3;
+/// The output buffer is too small to hold the result
+pub const POLYLINE_ERR_BUFFER_TOO_SMALL: isize = -4;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Returns a description of the most recent error on the calling thread, or null if no call
+/// has failed yet.
+///
+/// The returned string is owned by this crate and remains valid until the next failing call
+/// on the same thread. It must not be freed by the caller.
+#[no_mangle]
+pub extern "C" fn polyline_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+fn set_last_error(code: isize, message: impl Display) -> isize {
+    let message = CString::new(message.to_string()).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+    code
+}
+
+/// Decodes the `len` bytes of polyline starting at `ptr` into `out_ptr`, which has room for
+/// `out_cap` coordinate pairs.
+///
+/// Returns the number of coordinate pairs written. If `out_ptr` is null, nothing is written and
+/// the number of coordinate pairs required is returned instead, so callers can size their buffer.
+///
+/// # Safety
+///
+/// `ptr` must be valid for reads of `len` bytes, and `out_ptr` must either be null or valid for
+/// writes of `out_cap` coordinate pairs.
+#[no_mangle]
+pub unsafe extern "C" fn decode_polyline_into_buffer(
+    ptr: *const c_char,
+    len: usize,
+    precision: u32,
+    out_ptr: *mut PolylineCoord,
+    out_cap: usize,
+) -> isize {
+    if ptr.is_null() {
+        return set_last_error(POLYLINE_ERR_NULL_POINTER, "null pointer argument");
+    }
+    let bytes = std::slice::from_raw_parts(ptr as *const u8, len);
+    let coords = match decode_bytes(bytes, precision) {
+        Ok(coords) => coords,
+        Err(code) => return code,
+    };
+    if out_ptr.is_null() {
+        return coords.len() as isize;
+    }
+    if coords.len() > out_cap {
+        return set_last_error(POLYLINE_ERR_BUFFER_TOO_SMALL, "output buffer is too small");
+    }
+    write_coords(&coords, out_ptr);
+    coords.len() as isize
+}
+
+/// Decodes `count` NUL-terminated polylines in a single call.
+///
+/// The coordinate pairs of every polyline are written back to back into `out_ptr`, which has
+/// room for `out_cap` pairs, and the number of pairs belonging to polyline `i` is written to
+/// `out_lens[i]`. Returns the total number of coordinate pairs written. If `out_ptr` is null,
+/// only `out_lens` is filled in and the total number of pairs required is returned.
+///
+/// # Safety
+///
+/// `polylines` must be valid for reads of `count` pointers, each of which must point to a
+/// NUL-terminated string. `out_lens` must be valid for writes of `count` elements, and
+/// `out_ptr` must either be null or valid for writes of `out_cap` coordinate pairs.
+#[no_mangle]
+pub unsafe extern "C" fn decode_polylines_into_buffer(
+    polylines: *const *const c_char,
+    count: usize,
+    precision: u32,
+    out_ptr: *mut PolylineCoord,
+    out_cap: usize,
+    out_lens: *mut usize,
+) -> isize {
+    if polylines.is_null() || out_lens.is_null() {
+        return set_last_error(POLYLINE_ERR_NULL_POINTER, "null pointer argument");
+    }
+    let polylines = std::slice::from_raw_parts(polylines, count);
+    let out_lens = std::slice::from_raw_parts_mut(out_lens, count);
+    let mut written = 0;
+    for (&polyline, out_len) in polylines.iter().zip(out_lens) {
+        if polyline.is_null() {
+            return set_last_error(POLYLINE_ERR_NULL_POINTER, "null pointer argument");
+        }
+        let coords = match decode_bytes(CStr::from_ptr(polyline).to_bytes(), precision) {
+            Ok(coords) => coords,
+            Err(code) => return code,
+        };
+        *out_len = coords.len();
+        if !out_ptr.is_null() {
+            if written + coords.len() > out_cap {
+                return set_last_error(POLYLINE_ERR_BUFFER_TOO_SMALL, "output buffer is too small");
+            }
+            write_coords(&coords, out_ptr.add(written));
+        }
+        written += coords.len();
+    }
+    written as isize
+}
+
+/// Decodes the `len` bytes of polyline starting at `ptr`, invoking `cb` with the latitude,
+/// longitude and `ctx` of every coordinate as soon as it has been decoded.
+///
+/// Returns the number of coordinates passed to `cb`. If decoding fails part-way through, `cb`
+/// will already have been invoked for the coordinates preceding the error.
+///
+/// # Safety
+///
+/// `ptr` must be valid for reads of `len` bytes, and `cb` must be safe to call with `ctx`.
+#[no_mangle]
+pub unsafe extern "C" fn decode_polyline_with_callback(
+    ptr: *const c_char,
+    len: usize,
+    precision: u32,
+    cb: Option<unsafe extern "C" fn(lat: f64, lon: f64, ctx: *mut c_void)>,
+    ctx: *mut c_void,
+) -> isize {
+    let Some(cb) = cb else {
+        return set_last_error(POLYLINE_ERR_NULL_POINTER, "null pointer argument");
+    };
+    if ptr.is_null() {
+        return set_last_error(POLYLINE_ERR_NULL_POINTER, "null pointer argument");
+    }
+    let bytes = std::slice::from_raw_parts(ptr as *const u8, len);
+    let polyline = match std::str::from_utf8(bytes) {
+        Ok(polyline) => polyline,
+        Err(e) => return set_last_error(POLYLINE_ERR_INVALID_UTF8, e),
+    };
+    let mut count = 0;
+    for coord in Decoder::new(polyline.as_bytes(), precision) {
+        match coord {
+            Ok(coord) => cb(coord.y, coord.x, ctx),
+            Err(e) => return set_last_error(POLYLINE_ERR_CODEC, e),
+        }
+        count += 1;
+    }
+    count
+}
+
+/// Encodes the `len` coordinate pairs starting at `ptr` into `out_ptr`, which has room for
+/// `out_cap` bytes.
+///
+/// Returns the number of bytes written. The output is not NUL-terminated. If `out_ptr` is null,
+/// nothing is written and the number of bytes required is returned instead.
+///
+/// # Safety
+///
+/// `ptr` must be valid for reads of `len` coordinate pairs, and `out_ptr` must either be null or
+/// valid for writes of `out_cap` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn encode_coordinates_into_buffer(
+    ptr: *const PolylineCoord,
+    len: usize,
+    precision: u32,
+    out_ptr: *mut c_char,
+    out_cap: usize,
+) -> isize {
+    if ptr.is_null() {
+        return set_last_error(POLYLINE_ERR_NULL_POINTER, "null pointer argument");
+    }
+    let coords = std::slice::from_raw_parts(ptr, len);
+    let coords = coords.iter().map(|c| Coord { x: c.x, y: c.y });
+    let encoded = match encode_coordinates(coords, precision) {
+        Ok(encoded) => encoded,
+        Err(e) => return set_last_error(POLYLINE_ERR_CODEC, e),
+    };
+    write_encoded(&encoded, out_ptr, out_cap)
+}
+
+/// Like [`decode_polyline_into_buffer`], but writes `f32` coordinate pairs.
+///
+/// # Safety
+///
+/// `ptr` must be valid for reads of `len` bytes, and `out_ptr` must either be null or valid for
+/// writes of `out_cap` coordinate pairs.
+#[no_mangle]
+pub unsafe extern "C" fn decode_polyline_ffi_f32(
+    ptr: *const c_char,
+    len: usize,
+    precision: u32,
+    out_ptr: *mut PolylineCoordF32,
+    out_cap: usize,
+) -> isize {
+    if ptr.is_null() {
+        return set_last_error(POLYLINE_ERR_NULL_POINTER, "null pointer argument");
+    }
+    let bytes = std::slice::from_raw_parts(ptr as *const u8, len);
+    let coords = match decode_bytes(bytes, precision) {
+        Ok(coords) => coords,
+        Err(code) => return code,
+    };
+    if out_ptr.is_null() {
+        return coords.len() as isize;
+    }
+    if coords.len() > out_cap {
+        return set_last_error(POLYLINE_ERR_BUFFER_TOO_SMALL, "output buffer is too small");
+    }
+    let out = std::slice::from_raw_parts_mut(out_ptr, coords.len());
+    for (slot, coord) in out.iter_mut().zip(&coords) {
+        *slot = PolylineCoordF32 {
+            x: coord.x as f32,
+            y: coord.y as f32,
+        };
+    }
+    coords.len() as isize
+}
+
+/// Like [`encode_coordinates_into_buffer`], but reads `f32` coordinate pairs.
+///
+/// # Safety
+///
+/// `ptr` must be valid for reads of `len` coordinate pairs, and `out_ptr` must either be null or
+/// valid for writes of `out_cap` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn encode_coordinates_ffi_f32(
+    ptr: *const PolylineCoordF32,
+    len: usize,
+    precision: u32,
+    out_ptr: *mut c_char,
+    out_cap: usize,
+) -> isize {
+    if ptr.is_null() {
+        return set_last_error(POLYLINE_ERR_NULL_POINTER, "null pointer argument");
+    }
+    let coords = std::slice::from_raw_parts(ptr, len);
+    let coords = coords.iter().map(|c| Coord {
+        x: f64::from(c.x),
+        y: f64::from(c.y),
+    });
+    let encoded = match encode_coordinates(coords, precision) {
+        Ok(encoded) => encoded,
+        Err(e) => return set_last_error(POLYLINE_ERR_CODEC, e),
+    };
+    write_encoded(&encoded, out_ptr, out_cap)
+}
+
+fn decode_bytes(bytes: &[u8], precision: u32) -> Result<Vec<Coord<f64>>, isize> {
+    let polyline =
+        std::str::from_utf8(bytes).map_err(|e| set_last_error(POLYLINE_ERR_INVALID_UTF8, e))?;
+    let line_string =
+        decode_polyline(polyline, precision).map_err(|e| set_last_error(POLYLINE_ERR_CODEC, e))?;
+    Ok(line_string.0)
+}
+
+/// # Safety
+///
+/// `out_ptr` must be valid for writes of `coords.len()` coordinate pairs.
+unsafe fn write_coords(coords: &[Coord<f64>], out_ptr: *mut PolylineCoord) {
+    let out = std::slice::from_raw_parts_mut(out_ptr, coords.len());
+    for (slot, coord) in out.iter_mut().zip(coords) {
+        *slot = PolylineCoord {
+            x: coord.x,
+            y: coord.y,
+        };
+    }
+}
+
+/// # Safety
+///
+/// `out_ptr` must either be null or valid for writes of `out_cap` bytes.
+unsafe fn write_encoded(encoded: &str, out_ptr: *mut c_char, out_cap: usize) -> isize {
+    if out_ptr.is_null() {
+        return encoded.len() as isize;
+    }
+    if encoded.len() > out_cap {
+        return set_last_error(POLYLINE_ERR_BUFFER_TOO_SMALL, "output buffer is too small");
+    }
+    std::ptr::copy_nonoverlapping(encoded.as_ptr(), out_ptr as *mut u8, encoded.len());
+    encoded.len() as isize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coords(pairs: &[[f64; 2]]) -> Vec<PolylineCoord> {
+        pairs.iter().map(|&[x, y]| PolylineCoord { x, y }).collect()
+    }
+
+    #[test]
+    fn decode_into_buffer() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let ptr = polyline.as_ptr() as *const c_char;
+        unsafe {
+            let required =
+                decode_polyline_into_buffer(ptr, polyline.len(), 5, std::ptr::null_mut(), 0);
+            assert_eq!(required, 3);
+
+            let mut small = [PolylineCoord::default(); 2];
+            let err = decode_polyline_into_buffer(ptr, polyline.len(), 5, small.as_mut_ptr(), 2);
+            assert_eq!(err, POLYLINE_ERR_BUFFER_TOO_SMALL);
+
+            let mut out = [PolylineCoord::default(); 3];
+            let written = decode_polyline_into_buffer(ptr, polyline.len(), 5, out.as_mut_ptr(), 3);
+            assert_eq!(written, 3);
+            assert_eq!(
+                out.to_vec(),
+                coords(&[[-120.2, 38.5], [-120.95, 40.7], [-126.453, 43.252]])
+            );
+        }
+    }
+
+    #[test]
+    fn encode_into_buffer() {
+        let coords = coords(&[[-120.2, 38.5], [-120.95, 40.7], [-126.453, 43.252]]);
+        let expected = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        unsafe {
+            let required =
+                encode_coordinates_into_buffer(coords.as_ptr(), 3, 5, std::ptr::null_mut(), 0);
+            assert_eq!(required, expected.len() as isize);
+
+            let mut out = vec![0u8; expected.len()];
+            let written = encode_coordinates_into_buffer(
+                coords.as_ptr(),
+                3,
+                5,
+                out.as_mut_ptr() as *mut c_char,
+                out.len(),
+            );
+            assert_eq!(written, expected.len() as isize);
+            assert_eq!(std::str::from_utf8(&out).unwrap(), expected);
+
+            let bad = PolylineCoord { x: 0.0, y: 100.0 };
+            let err = encode_coordinates_into_buffer(&bad, 1, 5, std::ptr::null_mut(), 0);
+            assert_eq!(err, POLYLINE_ERR_CODEC);
+        }
+    }
+
+    #[test]
+    fn decode_many_into_buffer() {
+        let polylines = [c"_ibE_seK_seK_seK", c"_p~iF~ps|U_ulLnnqC_mqNvxq`@"];
+        let ptrs = polylines.map(|p| p.as_ptr());
+        let mut lens = [0; 2];
+        unsafe {
+            let required = decode_polylines_into_buffer(
+                ptrs.as_ptr(),
+                2,
+                5,
+                std::ptr::null_mut(),
+                0,
+                lens.as_mut_ptr(),
+            );
+            assert_eq!(required, 5);
+            assert_eq!(lens, [2, 3]);
+
+            let mut out = [PolylineCoord::default(); 5];
+            let written = decode_polylines_into_buffer(
+                ptrs.as_ptr(),
+                2,
+                5,
+                out.as_mut_ptr(),
+                5,
+                lens.as_mut_ptr(),
+            );
+            assert_eq!(written, 5);
+            assert_eq!(out[1], PolylineCoord { x: 4.0, y: 3.0 });
+            assert_eq!(
+                out[4],
+                PolylineCoord {
+                    x: -126.453,
+                    y: 43.252
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn f32_roundtrip() {
+        let coords = [
+            PolylineCoordF32 { x: 2.0, y: 1.0 },
+            PolylineCoordF32 { x: 4.0, y: 3.0 },
+        ];
+        let mut encoded = [0 as c_char; 16];
+        unsafe {
+            let len = encode_coordinates_ffi_f32(coords.as_ptr(), 2, 5, encoded.as_mut_ptr(), 16);
+            assert_eq!(len, 16);
+
+            let mut out = [PolylineCoordF32::default(); 2];
+            let written = decode_polyline_ffi_f32(encoded.as_ptr(), 16, 5, out.as_mut_ptr(), 2);
+            assert_eq!(written, 2);
+            assert_eq!(out, coords);
+        }
+    }
+
+    #[test]
+    fn last_error_message() {
+        let polyline = "ugh_ugh";
+        let written = unsafe {
+            decode_polyline_into_buffer(
+                polyline.as_ptr() as *const c_char,
+                polyline.len(),
+                5,
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        assert_eq!(written, POLYLINE_ERR_CODEC);
+        let message = unsafe { CStr::from_ptr(polyline_last_error_message()) };
+        assert_eq!(
+            message.to_str().unwrap(),
+            "latitude out of bounds: 49775.95019 at position 0"
+        );
+    }
+
+    #[test]
+    fn decode_with_callback() {
+        unsafe extern "C" fn collect(lat: f64, lon: f64, ctx: *mut c_void) {
+            (*(ctx as *mut Vec<[f64; 2]>)).push([lat, lon]);
+        }
+
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let mut coords: Vec<[f64; 2]> = vec![];
+        let count = unsafe {
+            decode_polyline_with_callback(
+                polyline.as_ptr() as *const c_char,
+                polyline.len(),
+                5,
+                Some(collect),
+                &mut coords as *mut _ as *mut c_void,
+            )
+        };
+        assert_eq!(count, 3);
+        assert_eq!(
+            coords,
+            [[38.5, -120.2], [40.7, -120.95], [43.252, -126.453]]
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! A container packing several polylines into a single string
+
+use crate::encoded::Polyline;
+use crate::errors::PolylineError;
+use crate::{decode_next, emit, encode_coordinates, zigzag};
+use geo_types::{Coord, MultiLineString};
+
+/// The version of the container format written by [`encode_collection`]
+const CONTAINER_VERSION: u64 = 1;
+
+/// Encodes several lines at `precision` into a single string, such as the routes of a
+/// `FeatureCollection` to store in one database cell.
+///
+/// The container starts with a format version and the precision, followed by each polyline
+/// prefixed with its length. These values are written in the polyline alphabet, so containers
+/// are printable ASCII like the polylines they hold.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+/// use geo_types::{line_string, MultiLineString};
+///
+/// let lines = MultiLineString::new(vec![
+///     line_string![(x: -120.2, y: 38.5), (x: -120.95, y: 40.7)],
+///     line_string![(x: 2.0, y: 1.0)],
+/// ]);
+/// let container = polyline::encode_collection(lines.clone(), 5).unwrap();
+/// assert_eq!(container, "@DQ_p~iF~ps|U_ulLnnqCG_ibE_seK");
+/// assert_eq!(polyline::decode_collection(&container).unwrap(), lines);
+/// ```
+pub fn encode_collection<I, L>(lines: I, precision: u32) -> Result<String, PolylineError>
+where
+    I: IntoIterator<Item = L>,
+    L: IntoIterator<Item = Coord<f64>>,
+{
+    let mut output = vec![];
+    emit(CONTAINER_VERSION, &mut output);
+    emit(u64::from(precision), &mut output);
+    for line in lines {
+        let encoded = encode_coordinates(line, precision)?;
+        emit(encoded.len() as u64, &mut output);
+        output.extend_from_slice(encoded.as_bytes());
+    }
+    // SAFETY: encoded values are ASCII
+    Ok(unsafe { String::from_utf8_unchecked(output) })
+}
+
+/// Decodes every line in a container written by [`encode_collection`].
+///
+/// Containers which can't be read are reported as a [`PolylineError::ContainerError`], while
+/// errors in the polylines they hold are reported at their index in the container.
+pub fn decode_collection(container: &str) -> Result<MultiLineString<f64>, PolylineError> {
+    let mut lines = vec![];
+    for (polyline, offset) in read_collection(container)? {
+        lines.push(
+            polyline
+                .decode()
+                .map_err(|err| err.map_string_idx(|idx| idx + offset))?,
+        );
+    }
+    Ok(MultiLineString::new(lines))
+}
+
+/// Splits a container written by [`encode_collection`] into its polylines, without decoding
+/// them.
+///
+/// Containers which can't be read are reported as a [`PolylineError::ContainerError`].
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let polylines = polyline::split_collection("@DQ_p~iF~ps|U_ulLnnqCG_ibE_seK").unwrap();
+/// assert_eq!(polylines[1].as_str(), "_ibE_seK");
+/// assert_eq!(polylines[1].precision(), 5);
+/// ```
+pub fn split_collection(container: &str) -> Result<Vec<Polyline>, PolylineError> {
+    Ok(read_collection(container)?
+        .into_iter()
+        .map(|(polyline, _)| polyline)
+        .collect())
+}
+
+/// Reads a container, returning its polylines along with where each starts.
+fn read_collection(container: &str) -> Result<Vec<(Polyline, usize)>, PolylineError> {
+    let bytes = container.as_bytes();
+    let mut pos = 0;
+    let version = read_value(bytes, &mut pos)?;
+    if version != CONTAINER_VERSION {
+        return Err(container_error(format!(
+            "unsupported container version {}",
+            version
+        )));
+    }
+    let precision = u32::try_from(read_value(bytes, &mut pos)?)
+        .map_err(|_| container_error("invalid precision".to_string()))?;
+
+    let mut polylines = vec![];
+    while pos < bytes.len() {
+        let len = read_value(bytes, &mut pos)?;
+        let start = pos;
+        let end = usize::try_from(len)
+            .ok()
+            .and_then(|len| start.checked_add(len))
+            .filter(|&end| end <= bytes.len())
+            .ok_or_else(|| {
+                container_error(format!(
+                    "the polyline at index {} runs past the end of the container",
+                    start
+                ))
+            })?;
+        let polyline = container.get(start..end).ok_or_else(|| {
+            container_error(format!(
+                "the polyline at index {} ends inside a character",
+                start
+            ))
+        })?;
+        polylines.push((Polyline::new(polyline, precision), start));
+        pos = end;
+    }
+    Ok(polylines)
+}
+
+/// Reads an unsigned value, which isn't zigzag-encoded.
+fn read_value(bytes: &[u8], pos: &mut usize) -> Result<u64, PolylineError> {
+    if *pos == bytes.len() {
+        return Err(container_error("the container is truncated".to_string()));
+    }
+    let value = decode_next(bytes, pos).map_err(|err| match err {
+        PolylineError::DecodeError { idx } => {
+            container_error(format!("invalid value at index {}", idx))
+        }
+        err => err,
+    })?;
+    Ok(zigzag(value))
+}
+
+fn container_error(message: String) -> PolylineError {
+    PolylineError::ContainerError { message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo_types::line_string;
+
+    #[test]
+    fn round_trips() {
+        let lines = MultiLineString::new(vec![
+            line_string![(x: -120.2, y: 38.5), (x: -120.95, y: 40.7), (x: -126.453, y: 43.252)],
+            line_string![],
+            line_string![(x: 2.0, y: 1.0)],
+        ]);
+        let container = encode_collection(lines.clone(), 5).unwrap();
+        assert_eq!(container, "@DZ_p~iF~ps|U_ulLnnqC_mqNvxq`@?G_ibE_seK");
+        assert_eq!(decode_collection(&container).unwrap(), lines);
+        let polylines = split_collection(&container).unwrap();
+        assert_eq!(polylines.len(), 3);
+        assert_eq!(polylines[0].as_str(), "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+        assert_eq!(polylines[1].as_str(), "");
+
+        let empty = encode_collection(Vec::<Vec<Coord<f64>>>::new(), 6).unwrap();
+        assert_eq!(empty, "@E");
+        assert_eq!(
+            decode_collection(&empty).unwrap(),
+            MultiLineString::new(vec![])
+        );
+
+        // long polylines take several characters to prefix
+        let line = (0..20).map(|i| Coord {
+            x: i as f64,
+            y: i as f64,
+        });
+        let container = encode_collection([line.clone(), line], 5).unwrap();
+        let decoded = decode_collection(&container).unwrap();
+        assert_eq!(decoded.0.len(), 2);
+        assert_eq!(decoded.0[1].0.len(), 20);
+    }
+
+    #[test]
+    fn reports_errors() {
+        for invalid in ["", "@", "A", "@DQ_p~iF", "@D!", "@D@é"] {
+            assert!(
+                matches!(
+                    decode_collection(invalid),
+                    Err(PolylineError::ContainerError { .. })
+                ),
+                "{}",
+                invalid
+            );
+            assert!(split_collection(invalid).is_err(), "{}", invalid);
+        }
+        assert_eq!(
+            decode_collection("@D?G_ibE_seKG_ibE!seK").unwrap_err(),
+            PolylineError::DecodeError { idx: 17 }
+        );
+        assert_eq!(
+            encode_collection(
+                [
+                    line_string![(x: 0.0, y: 0.0)],
+                    line_string![(x: 0.0, y: 91.0)]
+                ],
+                5
+            )
+            .unwrap_err(),
+            PolylineError::LatitudeCoordError {
+                coord: 91.0,
+                idx: 0
+            }
+        );
+    }
+}
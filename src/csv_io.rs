@@ -0,0 +1,159 @@
+//! Conversion between polylines and CSV, enabled with the `csv` feature
+
+use crate::errors::PolylineError;
+use crate::{encode_coord, factor, Decoder};
+use geo_types::Coord;
+use std::io;
+
+/// Encodes the coordinates in the `lat_column` and `lon_column` columns of CSV records as a
+/// Google Encoded Polyline.
+///
+/// Each record is one coordinate, in the order they're read. CSV which can't be read, lacks
+/// either column or holds values which aren't numbers is reported as a
+/// [`PolylineError::CsvError`], while out-of-bounds coordinates are reported at the index of
+/// their record.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let csv = "name,lat,lon\nstart,38.5,-120.2\nend,40.7,-120.95\n";
+/// let mut reader = csv::Reader::from_reader(csv.as_bytes());
+/// let encoded = polyline::encode_csv(&mut reader, 5, "lat", "lon").unwrap();
+/// assert_eq!(encoded, "_p~iF~ps|U_ulLnnqC");
+/// ```
+pub fn encode_csv<R: io::Read>(
+    reader: &mut csv::Reader<R>,
+    precision: u32,
+    lat_column: &str,
+    lon_column: &str,
+) -> Result<String, PolylineError> {
+    let headers = reader.headers().map_err(csv_error)?;
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header == name)
+            .ok_or_else(|| PolylineError::CsvError {
+                message: format!("no `{}` column", name),
+            })
+    };
+    let (lat_column, lon_column) = (column(lat_column)?, column(lon_column)?);
+
+    let factor = factor(precision);
+    let mut previous = Coord { x: 0, y: 0 };
+    let mut output = vec![];
+    for (i, record) in reader.records().enumerate() {
+        let record = record.map_err(csv_error)?;
+        let value = |column: usize| {
+            let field = record.get(column).unwrap_or_default().trim();
+            field.parse::<f64>().map_err(|_| PolylineError::CsvError {
+                message: format!("`{}` in record {} isn't a number", field, i + 1),
+            })
+        };
+        let coord = Coord {
+            x: value(lon_column)?,
+            y: value(lat_column)?,
+        };
+        encode_coord(i, coord, factor, &mut previous, &mut output)?;
+    }
+    // SAFETY: encoded values are ASCII
+    Ok(unsafe { String::from_utf8_unchecked(output) })
+}
+
+/// Decodes a Google Encoded Polyline as CSV, writing a header row naming the `lat_column` and
+/// `lon_column` columns, then a record for each coordinate.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let mut writer = csv::Writer::from_writer(vec![]);
+/// polyline::decode_to_csv("_p~iF~ps|U_ulLnnqC", 5, &mut writer, "lat", "lon").unwrap();
+/// let csv = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+/// assert_eq!(csv, "lat,lon\n38.5,-120.2\n40.7,-120.95\n");
+/// ```
+pub fn decode_to_csv<W: io::Write>(
+    polyline: &str,
+    precision: u32,
+    writer: &mut csv::Writer<W>,
+    lat_column: &str,
+    lon_column: &str,
+) -> Result<(), PolylineError> {
+    writer
+        .write_record([lat_column, lon_column])
+        .map_err(csv_error)?;
+    for coord in Decoder::new(polyline.as_bytes(), precision) {
+        let coord = coord?;
+        writer
+            .write_record([coord.y.to_string(), coord.x.to_string()])
+            .map_err(csv_error)?;
+    }
+    writer.flush().map_err(|err| csv_error(err.into()))
+}
+
+fn csv_error(err: csv::Error) -> PolylineError {
+    PolylineError::CsvError {
+        message: err.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(csv: &str) -> Result<String, PolylineError> {
+        encode_csv(
+            &mut csv::Reader::from_reader(csv.as_bytes()),
+            5,
+            "lat",
+            "lon",
+        )
+    }
+
+    #[test]
+    fn round_trips() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let mut writer = csv::Writer::from_writer(vec![]);
+        decode_to_csv(polyline, 5, &mut writer, "lat", "lon").unwrap();
+        let csv = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert_eq!(encode(&csv).unwrap(), polyline);
+
+        // columns in any order, among others
+        assert_eq!(
+            encode("lon,id,lat\n-120.2,1, 38.5\n-120.95,2,40.7\n").unwrap(),
+            "_p~iF~ps|U_ulLnnqC"
+        );
+        assert_eq!(encode("lat,lon\n").unwrap(), "");
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert!(matches!(
+            encode("latitude,lon\n1,2\n"),
+            Err(PolylineError::CsvError { .. })
+        ));
+        assert!(matches!(
+            encode("lat,lon\n1,2\n3,east\n"),
+            Err(PolylineError::CsvError { .. })
+        ));
+        assert!(matches!(
+            encode("lat,lon\n1,2\n3\n"),
+            Err(PolylineError::CsvError { .. })
+        ));
+        assert_eq!(
+            encode("lat,lon\n1,2\n91,2\n").unwrap_err(),
+            PolylineError::LatitudeCoordError {
+                coord: 91.0,
+                idx: 1
+            }
+        );
+
+        let mut writer = csv::Writer::from_writer(vec![]);
+        assert_eq!(
+            decode_to_csv("_ibE_seK_seK", 5, &mut writer, "lat", "lon").unwrap_err(),
+            PolylineError::NoLongError { idx: 8 }
+        );
+    }
+}
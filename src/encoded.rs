@@ -0,0 +1,199 @@
+//! An encoded polyline which knows its own precision
+
+use crate::errors::PolylineError;
+use crate::{decode_polyline, encode_coordinates, Decoder};
+use geo_types::{Coord, LineString};
+use std::fmt;
+use std::str::FromStr;
+
+/// A Google Encoded Polyline, along with the precision its coordinates are encoded at.
+///
+/// Keeping the two together means a polyline can't accidentally be decoded at the wrong
+/// precision once it's been passed around.
+///
+/// # Examples
+///
+/// ```
+/// use geo_types::line_string;
+/// use polyline::Polyline;
+///
+/// let coords = line_string![(x: -120.2, y: 38.5), (x: -120.95, y: 40.7)];
+/// let polyline = Polyline::encode(coords.clone(), 5).unwrap();
+/// assert_eq!(polyline.as_str(), "_p~iF~ps|U_ulLnnqC");
+/// assert_eq!(polyline.decode().unwrap(), coords);
+/// ```
+///
+/// Polylines are compared and hashed by their encoded string, then their precision, so they can
+/// be used as map keys or deduplicated in sets. The same coordinates encoded at different
+/// precisions are different polylines.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Polyline {
+    encoded: String,
+    precision: u32,
+}
+
+impl Polyline {
+    /// Wraps a polyline encoded at `precision`.
+    ///
+    /// The polyline isn't checked, so errors are only reported once it's decoded.
+    pub fn new(encoded: impl Into<String>, precision: u32) -> Self {
+        Polyline {
+            encoded: encoded.into(),
+            precision,
+        }
+    }
+
+    /// Encodes coordinates at `precision`, as [`encode_coordinates`] does.
+    pub fn encode<C>(coordinates: C, precision: u32) -> Result<Self, PolylineError>
+    where
+        C: IntoIterator<Item = Coord<f64>>,
+    {
+        Ok(Polyline {
+            encoded: encode_coordinates(coordinates, precision)?,
+            precision,
+        })
+    }
+
+    /// Decodes the polyline's coordinates, as [`decode_polyline`] does.
+    pub fn decode(&self) -> Result<LineString<f64>, PolylineError> {
+        decode_polyline(&self.encoded, self.precision)
+    }
+
+    /// Returns the encoded polyline.
+    pub fn as_str(&self) -> &str {
+        &self.encoded
+    }
+
+    /// Returns the precision the polyline's coordinates are encoded at.
+    pub fn precision(&self) -> u32 {
+        self.precision
+    }
+
+    /// Returns the encoded polyline, discarding its precision.
+    pub fn into_string(self) -> String {
+        self.encoded
+    }
+}
+
+/// Parses a polyline encoded at precision 5, the precision of Google's APIs, checking that it
+/// decodes.
+///
+/// Polylines at other precisions can be wrapped with [`Polyline::new`].
+///
+/// # Examples
+///
+/// ```
+/// use polyline::Polyline;
+///
+/// let polyline: Polyline = "_p~iF~ps|U_ulLnnqC".parse().unwrap();
+/// assert_eq!(polyline.precision(), 5);
+/// assert_eq!(polyline.to_string(), "_p~iF~ps|U_ulLnnqC");
+/// assert!("_p~iF~ps|U_ulL".parse::<Polyline>().is_err());
+/// ```
+impl FromStr for Polyline {
+    type Err = PolylineError;
+
+    fn from_str(encoded: &str) -> Result<Self, Self::Err> {
+        const PRECISION: u32 = 5;
+        Decoder::new(encoded.as_bytes(), PRECISION).try_for_each(|coord| coord.map(drop))?;
+        Ok(Polyline::new(encoded, PRECISION))
+    }
+}
+
+/// Writes the encoded polyline.
+impl fmt::Display for Polyline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.encoded)
+    }
+}
+
+/// Decodes the polyline's coordinates, as [`Polyline::decode`] does.
+impl TryFrom<&Polyline> for LineString<f64> {
+    type Error = PolylineError;
+
+    fn try_from(polyline: &Polyline) -> Result<Self, Self::Error> {
+        polyline.decode()
+    }
+}
+
+/// Encodes a line at a precision, as [`Polyline::encode`] does.
+///
+/// # Examples
+///
+/// ```
+/// use geo_types::{line_string, LineString};
+/// use polyline::Polyline;
+///
+/// let coords = line_string![(x: -120.2, y: 38.5), (x: -120.95, y: 40.7)];
+/// let polyline = Polyline::try_from((&coords, 5)).unwrap();
+/// assert_eq!(polyline.as_str(), "_p~iF~ps|U_ulLnnqC");
+/// assert_eq!(LineString::try_from(&polyline).unwrap(), coords);
+/// ```
+impl TryFrom<(&LineString<f64>, u32)> for Polyline {
+    type Error = PolylineError;
+
+    fn try_from((line, precision): (&LineString<f64>, u32)) -> Result<Self, Self::Error> {
+        Polyline::encode(line.coords().copied(), precision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_displays() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@".parse::<Polyline>().unwrap();
+        assert_eq!(polyline, Polyline::new("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5));
+        assert_eq!(format!("{}", polyline), "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+        assert_eq!("".parse::<Polyline>().unwrap().as_str(), "");
+
+        assert_eq!(
+            "_ibE!".parse::<Polyline>().unwrap_err(),
+            PolylineError::DecodeError { idx: 4 }
+        );
+        assert_eq!(
+            "_ibE_seK_seK".parse::<Polyline>().unwrap_err(),
+            PolylineError::NoLongError { idx: 8 }
+        );
+    }
+
+    #[test]
+    fn converts_line_strings() {
+        let line = LineString::from(vec![(2.0, 1.0), (3.0, 2.0)]);
+        let polyline = Polyline::try_from((&line, 6)).unwrap();
+        assert_eq!(polyline.precision(), 6);
+        assert_eq!(LineString::try_from(&polyline).unwrap(), line);
+
+        let invalid = LineString::from(vec![(0.0, 91.0)]);
+        assert_eq!(
+            Polyline::try_from((&invalid, 5)).unwrap_err(),
+            PolylineError::LatitudeCoordError {
+                coord: 91.0,
+                idx: 0
+            }
+        );
+        assert_eq!(
+            LineString::try_from(&Polyline::new("_ibE_seK_seK", 5)).unwrap_err(),
+            PolylineError::NoLongError { idx: 8 }
+        );
+    }
+
+    #[test]
+    fn compares_and_hashes() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let polylines = [
+            Polyline::new("_ibE_seK", 5),
+            Polyline::new("_p~iF~ps|U", 5),
+            Polyline::new("_ibE_seK", 5),
+            Polyline::new("_ibE_seK", 6),
+        ];
+        assert_eq!(polylines.iter().collect::<HashSet<_>>().len(), 3);
+        let sorted = polylines.iter().collect::<BTreeSet<_>>();
+        assert_eq!(
+            sorted.into_iter().collect::<Vec<_>>(),
+            [&polylines[0], &polylines[3], &polylines[1]]
+        );
+    }
+}
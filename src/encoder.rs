@@ -0,0 +1,227 @@
+//! Encoding coordinates one at a time
+
+use crate::encoded::Polyline;
+use crate::errors::PolylineError;
+use crate::{encode_coord, factor};
+use geo_types::Coord;
+
+/// Encodes a polyline incrementally, as coordinates arrive.
+///
+/// Coordinates can be pushed one at a time, or fed from an iterator with [`Extend`]. Extending
+/// can't report errors, so it stops at the first coordinate which can't be encoded, and
+/// [`Encoder::finish`] returns its error.
+///
+/// # Examples
+///
+/// ```
+/// use polyline::Encoder;
+/// use geo_types::coord;
+///
+/// let mut encoder = Encoder::new(5);
+/// encoder.push(coord! { x: -120.2, y: 38.5 }).unwrap();
+/// encoder.extend([coord! { x: -120.95, y: 40.7 }, coord! { x: -126.453, y: 43.252 }]);
+/// assert_eq!(encoder.finish().unwrap().as_str(), "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+/// ```
+#[derive(Debug)]
+pub struct Encoder {
+    output: Vec<u8>,
+    previous: Coord<i64>,
+    precision: u32,
+    factor: f64,
+    len: usize,
+    error: Option<PolylineError>,
+}
+
+impl Encoder {
+    /// Creates an encoder for a polyline at `precision`.
+    pub fn new(precision: u32) -> Self {
+        Encoder {
+            output: vec![],
+            previous: Coord { x: 0, y: 0 },
+            precision,
+            factor: factor(precision),
+            len: 0,
+            error: None,
+        }
+    }
+
+    /// Encodes coordinates at `precision`, for building a polyline at the end of an iterator
+    /// chain.
+    ///
+    /// Unlike [`Extend`]ing an encoder, this returns the error for the first coordinate which
+    /// can't be encoded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyline::Encoder;
+    /// use geo_types::coord;
+    ///
+    /// let coords = [(-120.2, 38.5), (-120.95, 40.7)];
+    /// let coords = coords.into_iter().map(|(x, y)| coord! { x: x, y: y });
+    /// let polyline = Encoder::from_coords(coords, 5).unwrap();
+    /// assert_eq!(polyline.as_str(), "_p~iF~ps|U_ulLnnqC");
+    /// ```
+    pub fn from_coords<C>(coords: C, precision: u32) -> Result<Polyline, PolylineError>
+    where
+        C: IntoIterator<Item = Coord<f64>>,
+    {
+        let mut encoder = Encoder::new(precision);
+        encoder.extend(coords);
+        encoder.finish()
+    }
+
+    /// Encodes the next coordinate, returning the characters appended to the polyline.
+    ///
+    /// Only the appended characters have to be sent to clients which already hold the rest of
+    /// the polyline, such as those following a live track. Coordinates which can't be encoded
+    /// are reported at their index, leaving the polyline unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyline::Encoder;
+    /// use geo_types::coord;
+    ///
+    /// let mut encoder = Encoder::new(5);
+    /// assert_eq!(encoder.push(coord! { x: -120.2, y: 38.5 }).unwrap(), "_p~iF~ps|U");
+    /// assert_eq!(encoder.push(coord! { x: -120.95, y: 40.7 }).unwrap(), "_ulLnnqC");
+    /// assert_eq!(encoder.as_str(), "_p~iF~ps|U_ulLnnqC");
+    /// ```
+    pub fn push(&mut self, coord: Coord<f64>) -> Result<&str, PolylineError> {
+        let start = self.output.len();
+        encode_coord(
+            self.len,
+            coord,
+            self.factor,
+            &mut self.previous,
+            &mut self.output,
+        )?;
+        self.len += 1;
+        // SAFETY: encoded values are ASCII
+        Ok(unsafe { std::str::from_utf8_unchecked(&self.output[start..]) })
+    }
+
+    /// Returns the polyline encoded so far.
+    pub fn as_str(&self) -> &str {
+        // SAFETY: encoded values are ASCII
+        unsafe { std::str::from_utf8_unchecked(&self.output) }
+    }
+
+    /// Returns the number of coordinates encoded so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no coordinates have been encoded yet.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the encoded polyline, or the error which stopped [`Extend`]ing it.
+    pub fn finish(self) -> Result<Polyline, PolylineError> {
+        match self.error {
+            Some(err) => Err(err),
+            // SAFETY: encoded values are ASCII
+            None => Ok(Polyline::new(
+                unsafe { String::from_utf8_unchecked(self.output) },
+                self.precision,
+            )),
+        }
+    }
+}
+
+impl Extend<Coord<f64>> for Encoder {
+    fn extend<I: IntoIterator<Item = Coord<f64>>>(&mut self, coords: I) {
+        if self.error.is_some() {
+            return;
+        }
+        for coord in coords {
+            if let Err(err) = self.push(coord) {
+                self.error = Some(err);
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode_coordinates;
+    use geo_types::coord;
+
+    #[test]
+    fn encodes_incrementally() {
+        let coords = [
+            coord! { x: -120.2, y: 38.5 },
+            coord! { x: -120.95, y: 40.7 },
+            coord! { x: -126.453, y: 43.252 },
+        ];
+        let mut encoder = Encoder::new(6);
+        assert!(encoder.is_empty());
+        let appended = encoder.push(coords[0]).unwrap().to_string();
+        assert_eq!(encoder.as_str(), appended);
+        assert_eq!(
+            encoder.as_str(),
+            encode_coordinates(coords[..1].to_vec(), 6).unwrap()
+        );
+        encoder.extend(coords[1..].iter().copied());
+        assert_eq!(encoder.len(), 3);
+        let polyline = encoder.finish().unwrap();
+        assert_eq!(polyline.as_str(), encode_coordinates(coords, 6).unwrap());
+        assert_eq!(polyline.precision(), 6);
+
+        // the appended characters make up the polyline
+        let mut encoder = Encoder::new(5);
+        let mut sent = String::new();
+        for coord in coords {
+            sent.push_str(encoder.push(coord).unwrap());
+        }
+        assert_eq!(sent, encoder.as_str());
+    }
+
+    #[test]
+    fn from_coords() {
+        let coords = [coord! { x: 2.0, y: 1.0 }, coord! { x: 3.0, y: 2.0 }];
+        let polyline = Encoder::from_coords(coords, 6).unwrap();
+        assert_eq!(polyline.as_str(), encode_coordinates(coords, 6).unwrap());
+        assert_eq!(polyline.precision(), 6);
+
+        assert_eq!(
+            Encoder::from_coords([coords[0], coord! { x: 2.0, y: 91.0 }], 5).unwrap_err(),
+            PolylineError::LatitudeCoordError {
+                coord: 91.0,
+                idx: 1
+            }
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        let mut encoder = Encoder::new(5);
+        encoder.push(coord! { x: 2.0, y: 1.0 }).unwrap();
+        assert_eq!(
+            encoder.push(coord! { x: 0.0, y: 91.0 }).unwrap_err(),
+            PolylineError::LatitudeCoordError {
+                coord: 91.0,
+                idx: 1
+            }
+        );
+        assert_eq!(encoder.as_str(), "_ibE_seK");
+
+        encoder.extend([
+            coord! { x: 2.0, y: 1.0 },
+            coord! { x: 181.0, y: 1.0 },
+            coord! { x: 2.0, y: 1.0 },
+        ]);
+        assert_eq!(encoder.len(), 2);
+        assert_eq!(
+            encoder.finish().unwrap_err(),
+            PolylineError::LongitudeCoordError {
+                coord: 181.0,
+                idx: 2
+            }
+        );
+    }
+}
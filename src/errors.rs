@@ -31,6 +31,88 @@ pub enum PolylineError {
         /// The array index of the coordinate error
         idx: usize,
     },
+    ChunkLengthError {
+        /// The array index of the coordinate ending the segment which doesn't fit in a chunk
+        idx: usize,
+    },
+    NonCanonicalError {
+        /// The string index of the value which isn't canonically encoded
+        idx: usize,
+    },
+    ContainerError {
+        /// Why the container of polylines couldn't be read
+        message: String,
+    },
+    HeightCountError {
+        /// The number of points the heights are paired with
+        points: usize,
+        /// The number of heights
+        heights: usize,
+    },
+    #[cfg(feature = "arrow")]
+    ArrowError {
+        /// Why the Arrow array couldn't be converted
+        message: String,
+    },
+    #[cfg(feature = "csv")]
+    CsvError {
+        /// Why the CSV couldn't be converted
+        message: String,
+    },
+    #[cfg(feature = "flexible_polyline")]
+    FlexiblePolylineError {
+        /// Why the Flexible Polyline couldn't be converted
+        message: String,
+    },
+    #[cfg(feature = "gpx")]
+    GpxError {
+        /// Why the GPX couldn't be converted
+        message: String,
+    },
+    #[cfg(feature = "proj")]
+    ProjError {
+        /// Why the coordinate couldn't be reprojected
+        message: String,
+    },
+    #[cfg(feature = "serde_json")]
+    JsonError {
+        /// Why the JSON response couldn't be read
+        message: String,
+    },
+    #[cfg(feature = "wkb")]
+    WkbError {
+        /// Why the WKB couldn't be converted
+        message: String,
+    },
+    #[cfg(feature = "wkt")]
+    WktError {
+        /// Why the WKT couldn't be converted to a `LineString`
+        message: String,
+    },
+}
+
+impl PolylineError {
+    /// Maps the string index of a decoding error, for errors found in part of a larger string.
+    pub(crate) fn map_string_idx(self, map: impl FnOnce(usize) -> usize) -> Self {
+        match self {
+            PolylineError::LongitudeCoordError { coord, idx } => {
+                PolylineError::LongitudeCoordError {
+                    coord,
+                    idx: map(idx),
+                }
+            }
+            PolylineError::LatitudeCoordError { coord, idx } => PolylineError::LatitudeCoordError {
+                coord,
+                idx: map(idx),
+            },
+            PolylineError::NoLongError { idx } => PolylineError::NoLongError { idx: map(idx) },
+            PolylineError::DecodeError { idx } => PolylineError::DecodeError { idx: map(idx) },
+            PolylineError::NonCanonicalError { idx } => {
+                PolylineError::NonCanonicalError { idx: map(idx) }
+            }
+            err => err,
+        }
+    }
 }
 
 impl std::error::Error for PolylineError {}
@@ -57,6 +139,46 @@ impl std::fmt::Display for PolylineError {
                     coord, idx
                 )
             }
+            PolylineError::ChunkLengthError { idx } => {
+                write!(
+                    f,
+                    "the segment ending at index {} doesn't fit in a chunk",
+                    idx
+                )
+            }
+            PolylineError::NonCanonicalError { idx } => {
+                write!(f, "value at index {} isn't canonically encoded", idx)
+            }
+            PolylineError::ContainerError { message } => {
+                write!(f, "invalid polyline container: {}", message)
+            }
+            PolylineError::HeightCountError { points, heights } => {
+                write!(
+                    f,
+                    "expected a height for each of {} points, found {}",
+                    points, heights
+                )
+            }
+            #[cfg(feature = "arrow")]
+            PolylineError::ArrowError { message } => write!(f, "invalid Arrow array: {}", message),
+            #[cfg(feature = "csv")]
+            PolylineError::CsvError { message } => write!(f, "invalid CSV: {}", message),
+            #[cfg(feature = "flexible_polyline")]
+            PolylineError::FlexiblePolylineError { message } => {
+                write!(f, "invalid Flexible Polyline: {}", message)
+            }
+            #[cfg(feature = "gpx")]
+            PolylineError::GpxError { message } => write!(f, "invalid GPX: {}", message),
+            #[cfg(feature = "proj")]
+            PolylineError::ProjError { message } => {
+                write!(f, "couldn't reproject coordinate: {}", message)
+            }
+            #[cfg(feature = "serde_json")]
+            PolylineError::JsonError { message } => write!(f, "invalid JSON response: {}", message),
+            #[cfg(feature = "wkb")]
+            PolylineError::WkbError { message } => write!(f, "invalid WKB: {}", message),
+            #[cfg(feature = "wkt")]
+            PolylineError::WktError { message } => write!(f, "invalid WKT: {}", message),
         }
     }
 }
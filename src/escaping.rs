@@ -0,0 +1,249 @@
+//! Encoding and decoding polylines which are escaped or wrapped for embedding in other formats
+
+use crate::errors::PolylineError;
+use crate::{decode_polyline_bytes, encode_coordinates};
+use geo_types::{Coord, LineString};
+
+/// Encodes a Google Encoded Polyline with its backslashes escaped, so it can be embedded in a
+/// JSON string as is.
+///
+/// Backslash is the only character of the polyline alphabet which JSON strings escape.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+/// use geo_types::Coord;
+///
+/// let coords = [Coord { x: -0.00015, y: -0.00015 }];
+/// assert_eq!(polyline::encode_coordinates(coords, 5).unwrap(), r"\\");
+/// assert_eq!(polyline::encode_json_safe(coords, 5).unwrap(), r"\\\\");
+/// ```
+pub fn encode_json_safe<C>(coordinates: C, precision: u32) -> Result<String, PolylineError>
+where
+    C: IntoIterator<Item = Coord<f64>>,
+{
+    Ok(encode_coordinates(coordinates, precision)?.replace('\\', r"\\"))
+}
+
+/// Decodes a Google Encoded Polyline whose backslashes may have been escaped for JSON, as
+/// produced by [`encode_json_safe`] or by serialising a polyline and reading it back raw.
+///
+/// Every `\\` pair is read as a single backslash, while lone backslashes are kept, so both
+/// escaped and most unescaped polylines decode. An unescaped polyline with consecutive
+/// backslashes is ambiguous, and is misread; decode those with
+/// [`decode_polyline`](crate::decode_polyline). Error indices refer to the escaped input.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let escaped = polyline::decode_json_safe(r"\\\\", 5).unwrap();
+/// assert_eq!(escaped, polyline::decode_polyline(r"\\", 5).unwrap());
+/// ```
+pub fn decode_json_safe(polyline: &str, precision: u32) -> Result<LineString<f64>, PolylineError> {
+    let escaped = polyline.as_bytes();
+    let mut pos = 0;
+    let unescaped = std::iter::from_fn(move || {
+        let start = pos;
+        let byte = *escaped.get(pos)?;
+        pos += if escaped[pos..].starts_with(br"\\") {
+            2
+        } else {
+            1
+        };
+        Some((start, byte))
+    });
+    decode_unescaped(escaped.len(), unescaped, precision)
+}
+
+/// Decodes a Google Encoded Polyline which may have been percent-encoded, such as one copied out
+/// of a URL.
+///
+/// `%XX` escapes are decoded, while spaces, `+` and `%20`, which URLs pick up around and within
+/// polylines, are skipped. Error indices refer to the percent-encoded input.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let decoded = polyline::decode_percent_encoded("_p%7EiF%7Eps%7CU+", 5).unwrap();
+/// assert_eq!(decoded, polyline::decode_polyline("_p~iF~ps|U", 5).unwrap());
+/// ```
+pub fn decode_percent_encoded(
+    polyline: &str,
+    precision: u32,
+) -> Result<LineString<f64>, PolylineError> {
+    let escaped = polyline.as_bytes();
+    let mut pos = 0;
+    let unescaped = std::iter::from_fn(move || loop {
+        let start = pos;
+        let mut byte = *escaped.get(pos)?;
+        pos += 1;
+        if byte == b'%' {
+            if let [high, low, ..] = escaped[pos..] {
+                if let (Some(high), Some(low)) = (hex_value(high), hex_value(low)) {
+                    byte = high << 4 | low;
+                    pos += 2;
+                }
+            }
+        } else if byte == b'+' {
+            continue;
+        }
+        if byte != b' ' {
+            return Some((start, byte));
+        }
+    });
+    decode_unescaped(escaped.len(), unescaped, precision)
+}
+
+/// Decodes a Google Encoded Polyline, skipping any ASCII whitespace within it, such as the line
+/// breaks of a polyline wrapped by an email client, a YAML file or a log.
+///
+/// None of the polyline alphabet is whitespace, so this never changes the meaning of a valid
+/// polyline. Error indices refer to the input, whitespace included.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let decoded = polyline::decode_polyline_ignoring_whitespace("_p~iF~ps|U\n  _ulLnnqC\r\n", 5);
+/// assert_eq!(decoded, polyline::decode_polyline("_p~iF~ps|U_ulLnnqC", 5));
+/// ```
+pub fn decode_polyline_ignoring_whitespace(
+    polyline: &str,
+    precision: u32,
+) -> Result<LineString<f64>, PolylineError> {
+    let unwrapped = polyline
+        .bytes()
+        .enumerate()
+        .filter(|(_, byte)| !byte.is_ascii_whitespace());
+    decode_unescaped(polyline.len(), unwrapped, precision)
+}
+
+fn hex_value(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|value| value as u8)
+}
+
+/// Decodes the polyline yielded by `unescaped`, as bytes paired with their index in the
+/// escaped input, reporting errors at those indices.
+fn decode_unescaped<I>(
+    escaped_len: usize,
+    unescaped: I,
+    precision: u32,
+) -> Result<LineString<f64>, PolylineError>
+where
+    I: Iterator<Item = (usize, u8)> + Clone,
+{
+    let bytes = unescaped.clone().map(|(_, byte)| byte).collect::<Vec<_>>();
+    decode_polyline_bytes(&bytes, precision).map_err(|err| {
+        err.map_string_idx(|idx| {
+            unescaped
+                .clone()
+                .nth(idx)
+                .map_or(escaped_len, |(escaped_idx, _)| escaped_idx)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_polyline;
+
+    #[test]
+    fn json_round_trips() {
+        let coords = [
+            Coord {
+                x: 1.0,
+                y: -0.00015,
+            },
+            Coord {
+                x: 0.99985,
+                y: -0.0003,
+            },
+        ];
+        let encoded = encode_coordinates(coords, 5).unwrap();
+        assert_eq!(encoded, r"\_ibE\\");
+        let escaped = encode_json_safe(coords, 5).unwrap();
+        assert_eq!(escaped, r"\\_ibE\\\\");
+
+        let line = decode_polyline(&encoded, 5).unwrap();
+        assert_eq!(decode_json_safe(&escaped, 5).unwrap(), line);
+        // lone backslashes are kept
+        assert_eq!(
+            decode_json_safe(r"\_ibE", 5).unwrap(),
+            decode_polyline(r"\_ibE", 5).unwrap()
+        );
+        assert_eq!(
+            decode_json_safe("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap(),
+            decode_polyline("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap()
+        );
+    }
+
+    #[test]
+    fn decodes_percent_encoded() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let line = decode_polyline(polyline, 5).unwrap();
+        assert_eq!(decode_percent_encoded(polyline, 5).unwrap(), line);
+        assert_eq!(
+            decode_percent_encoded("%5Fp%7eiF%7Eps%7CU_ulLnnqC_mqNvxq%60%40", 5).unwrap(),
+            line
+        );
+        assert_eq!(
+            decode_percent_encoded("+_p~iF~ps|U%20_ulLnnqC _mqNvxq`@+", 5).unwrap(),
+            line
+        );
+    }
+
+    #[test]
+    fn percent_errors_refer_to_encoded_input() {
+        assert_eq!(
+            decode_percent_encoded("%7E%7E!", 5).unwrap_err(),
+            PolylineError::DecodeError { idx: 6 }
+        );
+        // malformed escapes are left as they are
+        assert_eq!(
+            decode_percent_encoded("%5F%7EiF%7Eps%7CU%7", 5).unwrap_err(),
+            PolylineError::DecodeError { idx: 17 }
+        );
+        assert_eq!(
+            decode_percent_encoded("+_ibE%20_seK%20_seK", 5).unwrap_err(),
+            PolylineError::NoLongError { idx: 15 }
+        );
+    }
+
+    #[test]
+    fn skips_whitespace() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let line = decode_polyline(polyline, 5).unwrap();
+        assert_eq!(
+            decode_polyline_ignoring_whitespace(polyline, 5).unwrap(),
+            line
+        );
+        assert_eq!(
+            decode_polyline_ignoring_whitespace(" _p~iF~ps|U\n_ulL\tnnqC\r\n_mqNvxq`@\n", 5)
+                .unwrap(),
+            line
+        );
+        assert_eq!(
+            decode_polyline_ignoring_whitespace("_ibE\n_seK\n_seK\n", 5).unwrap_err(),
+            PolylineError::NoLongError { idx: 10 }
+        );
+    }
+
+    #[test]
+    fn json_errors_refer_to_escaped_input() {
+        assert_eq!(
+            decode_json_safe(r"\\\\_ibE_seK_seK", 5).unwrap_err(),
+            PolylineError::NoLongError { idx: 12 }
+        );
+        assert_eq!(
+            decode_json_safe(r"\\\\!", 5).unwrap_err(),
+            PolylineError::DecodeError { idx: 4 }
+        );
+    }
+}
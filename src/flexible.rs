@@ -0,0 +1,518 @@
+//! HERE Flexible Polylines, enabled with the `flexible_polyline` feature.
+//!
+//! Flexible Polylines delta-encode coordinates in the same way as Google Encoded Polylines, but
+//! write their chunks in the URL-safe base64 alphabet and start with a header recording the
+//! precision and an optional third dimension. As elsewhere in this crate, coordinates are `x`
+//! for longitude and `y` for latitude.
+//!
+//! # Examples
+//!
+//! ```
+//! use polyline::flexible;
+//! use geo_types::line_string;
+//!
+//! let line = line_string![(x: 8.69821, y: 50.10228), (x: 8.69567, y: 50.10201)];
+//! let encoded = flexible::encode(line.clone(), 5).unwrap();
+//! assert_eq!(encoded, "BFoz5xJ67i1B1B7P");
+//! assert_eq!(flexible::decode(&encoded).unwrap().line, line);
+//! ```
+
+use crate::alphabet::{from_base64, to_base64};
+use crate::errors::PolylineError;
+use crate::{add_latitude, add_longitude, decode_next, emit, factor, scale, scale_coord, zigzag};
+use geo_types::{Coord, LineString};
+
+/// The only version of the format
+const FORMAT_VERSION: u64 = 1;
+/// The largest precision the header can hold, for both coordinates and the third dimension
+pub const MAX_PRECISION: u32 = 15;
+/// The bound of scaled third dimension values, whose changes fit in the 60 bits a value can be
+/// decoded from
+const MAX_SCALED_THIRD: f64 = (1u64 << 58) as f64;
+
+/// What the third dimension of a Flexible Polyline's coordinates represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ThirdDimension {
+    /// The coordinates are two-dimensional
+    #[default]
+    Absent,
+    Level,
+    Altitude,
+    Elevation,
+    /// Reserved for future use by the format
+    Reserved1,
+    /// Reserved for future use by the format
+    Reserved2,
+    Custom1,
+    Custom2,
+}
+
+impl ThirdDimension {
+    const ALL: [ThirdDimension; 8] = [
+        ThirdDimension::Absent,
+        ThirdDimension::Level,
+        ThirdDimension::Altitude,
+        ThirdDimension::Elevation,
+        ThirdDimension::Reserved1,
+        ThirdDimension::Reserved2,
+        ThirdDimension::Custom1,
+        ThirdDimension::Custom2,
+    ];
+}
+
+/// A decoded Flexible Polyline
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct FlexiblePolyline {
+    /// The number of decimal places the coordinates were encoded with
+    pub precision: u32,
+    pub third_dimension: ThirdDimension,
+    /// The number of decimal places the third dimension was encoded with
+    pub third_dimension_precision: u32,
+    pub line: LineString<f64>,
+    /// The third dimension of each coordinate, or nothing if it's
+    /// [`Absent`](ThirdDimension::Absent)
+    pub third_dimension_values: Vec<f64>,
+}
+
+/// Encodes two-dimensional coordinates as a Flexible Polyline.
+///
+/// Precisions above [`MAX_PRECISION`] are reported as a
+/// [`PolylineError::FlexiblePolylineError`].
+pub fn encode<C>(coordinates: C, precision: u32) -> Result<String, PolylineError>
+where
+    C: IntoIterator<Item = Coord<f64>>,
+{
+    let mut output = header(precision, ThirdDimension::Absent, 0)?;
+    let factor = factor(precision);
+    let mut previous = Coord { x: 0, y: 0 };
+    for (i, coord) in coordinates.into_iter().enumerate() {
+        let scaled = scale_coord(i, coord, factor)?;
+        crate::encode(scaled.y - previous.y, &mut output);
+        crate::encode(scaled.x - previous.x, &mut output);
+        previous = scaled;
+    }
+    Ok(into_base64(output))
+}
+
+/// Encodes coordinates paired with their `third_dimension` as a Flexible Polyline.
+///
+/// Precisions above [`MAX_PRECISION`], an [`Absent`](ThirdDimension::Absent) third dimension,
+/// and third dimension values which are non-finite or too large to encode at
+/// `third_dimension_precision` are reported as a [`PolylineError::FlexiblePolylineError`].
+///
+/// # Examples
+///
+/// ```
+/// use polyline::flexible::{self, ThirdDimension};
+/// use geo_types::coord;
+///
+/// let coords = [(coord! { x: 8.69821, y: 50.10228 }, 10.0)];
+/// let encoded = flexible::encode_3d(coords, 5, ThirdDimension::Altitude, 0).unwrap();
+/// assert_eq!(encoded, "BlBoz5xJ67i1BU");
+/// ```
+pub fn encode_3d<C>(
+    coordinates: C,
+    precision: u32,
+    third_dimension: ThirdDimension,
+    third_dimension_precision: u32,
+) -> Result<String, PolylineError>
+where
+    C: IntoIterator<Item = (Coord<f64>, f64)>,
+{
+    if third_dimension == ThirdDimension::Absent {
+        return Err(flexible_error(
+            "three-dimensional coordinates need a third dimension".to_string(),
+        ));
+    }
+    let mut output = header(precision, third_dimension, third_dimension_precision)?;
+    let third_factor = factor(third_dimension_precision);
+    let factor = factor(precision);
+    let mut previous = (Coord { x: 0, y: 0 }, 0);
+    for (i, (coord, third)) in coordinates.into_iter().enumerate() {
+        let scaled_third = third * third_factor;
+        if !(-MAX_SCALED_THIRD..MAX_SCALED_THIRD).contains(&scaled_third) {
+            return Err(flexible_error(format!(
+                "the third dimension {} at index {} can't be encoded",
+                third, i
+            )));
+        }
+        let scaled = (scale_coord(i, coord, factor)?, scale(third, third_factor));
+        crate::encode(scaled.0.y - previous.0.y, &mut output);
+        crate::encode(scaled.0.x - previous.0.x, &mut output);
+        crate::encode(scaled.1 - previous.1, &mut output);
+        previous = scaled;
+    }
+    Ok(into_base64(output))
+}
+
+/// Decodes a Flexible Polyline, along with its third dimension if it has one.
+///
+/// Invalid characters and out-of-bounds coordinates are reported at their index, as when
+/// decoding Google Encoded Polylines, while headers which can't be read and coordinates
+/// missing their third dimension are reported as a [`PolylineError::FlexiblePolylineError`].
+///
+/// # Examples
+///
+/// ```
+/// use polyline::flexible::{self, ThirdDimension};
+///
+/// let decoded = flexible::decode("BlBoz5xJ67i1BU1B7PU").unwrap();
+/// assert_eq!(decoded.third_dimension, ThirdDimension::Altitude);
+/// assert_eq!(decoded.line.0[1].y, 50.10201);
+/// assert_eq!(decoded.third_dimension_values, [10.0, 20.0]);
+/// ```
+pub fn decode(polyline: &str) -> Result<FlexiblePolyline, PolylineError> {
+    let bytes = from_base64(polyline.as_bytes());
+    let mut pos = 0;
+    let (precision, third_dimension, third_dimension_precision) = read_header(&bytes, &mut pos)?;
+    let has_third = third_dimension != ThirdDimension::Absent;
+
+    let third_factor = factor(third_dimension_precision);
+    let factor = factor(precision);
+    let mut coordinates = vec![];
+    let mut third_dimension_values = vec![];
+    let mut scaled = [0; 3];
+    while pos < bytes.len() {
+        next_deltas(&bytes, &mut pos, has_third, factor, &mut scaled)?;
+        let [lat, lon, third] = scaled;
+        coordinates.push(Coord {
+            x: lon as f64 / factor,
+            y: lat as f64 / factor,
+        });
+        if has_third {
+            third_dimension_values.push(third as f64 / third_factor);
+        }
+    }
+
+    Ok(FlexiblePolyline {
+        precision,
+        third_dimension,
+        third_dimension_precision,
+        line: LineString::new(coordinates),
+        third_dimension_values,
+    })
+}
+
+/// Converts a Google Encoded Polyline encoded at `precision` to a two-dimensional Flexible
+/// Polyline.
+///
+/// The integer deltas are carried over as they are, so the conversion is exact. Precisions
+/// above [`MAX_PRECISION`] are reported as a [`PolylineError::FlexiblePolylineError`], while
+/// error indices refer to the Google Encoded Polyline.
+///
+/// # Examples
+///
+/// ```
+/// use polyline::flexible;
+///
+/// let flexible = flexible::google_to_flex("_p~iF~ps|U_ulLnnqC", 5).unwrap();
+/// assert_eq!(flexible, "BFgx_qH_x09Wg2tNvvyE");
+/// ```
+pub fn google_to_flex(polyline: &str, precision: u32) -> Result<String, PolylineError> {
+    let mut output = header(precision, ThirdDimension::Absent, 0)?;
+    let bytes = polyline.as_bytes();
+    let factor = factor(precision);
+    let (mut pos, mut scaled) = (0, [0; 3]);
+    while pos < bytes.len() {
+        let [lat, lon, _] = next_deltas(bytes, &mut pos, false, factor, &mut scaled)?;
+        crate::encode(lat, &mut output);
+        crate::encode(lon, &mut output);
+    }
+    Ok(into_base64(output))
+}
+
+/// Converts a two-dimensional Flexible Polyline to a Google Encoded Polyline, returned along with
+/// the precision it's encoded at.
+///
+/// The integer deltas are carried over as they are, so the conversion is exact. Flexible
+/// Polylines with a third dimension, which Google Encoded Polylines can't hold, are reported as
+/// a [`PolylineError::FlexiblePolylineError`].
+///
+/// # Examples
+///
+/// ```
+/// use polyline::flexible;
+///
+/// let (google, precision) = flexible::flex_to_google("BFgx_qH_x09Wg2tNvvyE").unwrap();
+/// assert_eq!(google, "_p~iF~ps|U_ulLnnqC");
+/// assert_eq!(precision, 5);
+/// ```
+pub fn flex_to_google(polyline: &str) -> Result<(String, u32), PolylineError> {
+    let bytes = from_base64(polyline.as_bytes());
+    let mut pos = 0;
+    let (precision, third_dimension, _) = read_header(&bytes, &mut pos)?;
+    if third_dimension != ThirdDimension::Absent {
+        return Err(flexible_error(format!(
+            "a third dimension ({:?}) can't be converted to a Google Encoded Polyline",
+            third_dimension
+        )));
+    }
+
+    let factor = factor(precision);
+    let mut output = Vec::with_capacity(bytes.len() - pos);
+    let mut scaled = [0; 3];
+    while pos < bytes.len() {
+        let [lat, lon, _] = next_deltas(&bytes, &mut pos, false, factor, &mut scaled)?;
+        crate::encode(lat, &mut output);
+        crate::encode(lon, &mut output);
+    }
+    // SAFETY: encoded values are ASCII
+    Ok((unsafe { String::from_utf8_unchecked(output) }, precision))
+}
+
+/// Reads the header, returning the precision, third dimension and its precision.
+fn read_header(bytes: &[u8], pos: &mut usize) -> Result<(u32, ThirdDimension, u32), PolylineError> {
+    let version = header_value(bytes, pos)?;
+    if version != FORMAT_VERSION {
+        return Err(flexible_error(format!("unsupported version {}", version)));
+    }
+    let content = header_value(bytes, pos)?;
+    if content > 0x7ff {
+        return Err(flexible_error(format!("invalid header {:#x}", content)));
+    }
+    Ok((
+        (content & 0xf) as u32,
+        ThirdDimension::ALL[((content >> 4) & 0x7) as usize],
+        (content >> 7) as u32,
+    ))
+}
+
+/// Reads the deltas of the next coordinate's latitude, longitude and, if `has_third`, third
+/// dimension, adding them to the `scaled` values and checking the bounds of the coordinate.
+fn next_deltas(
+    bytes: &[u8],
+    pos: &mut usize,
+    has_third: bool,
+    factor: f64,
+    scaled: &mut [i64; 3],
+) -> Result<[i64; 3], PolylineError> {
+    let lat_start = *pos;
+    let lat_delta = decode_next(bytes, pos)?;
+    scaled[0] = add_latitude(scaled[0], lat_delta, factor, lat_start)?;
+
+    let lon_start = *pos;
+    if lon_start == bytes.len() {
+        return Err(PolylineError::NoLongError { idx: lat_start });
+    }
+    let lon_delta = decode_next(bytes, pos)?;
+    scaled[1] = add_longitude(scaled[1], lon_delta, factor, lon_start)?;
+
+    let mut third_delta = 0;
+    if has_third {
+        if *pos == bytes.len() {
+            return Err(flexible_error(format!(
+                "no third dimension for the coordinate at index {}",
+                lat_start
+            )));
+        }
+        third_delta = decode_next(bytes, pos)?;
+        scaled[2] = scaled[2].checked_add(third_delta).ok_or_else(|| {
+            flexible_error(format!(
+                "the third dimension of the coordinate at index {} overflows",
+                lat_start
+            ))
+        })?;
+    }
+    Ok([lat_delta, lon_delta, third_delta])
+}
+
+/// Encodes the header, in the Google alphabet like the values that follow it.
+fn header(
+    precision: u32,
+    third_dimension: ThirdDimension,
+    third_dimension_precision: u32,
+) -> Result<Vec<u8>, PolylineError> {
+    if precision > MAX_PRECISION || third_dimension_precision > MAX_PRECISION {
+        return Err(flexible_error(format!(
+            "precisions above {} can't be encoded",
+            MAX_PRECISION
+        )));
+    }
+    let mut output = vec![];
+    emit(FORMAT_VERSION, &mut output);
+    emit(
+        u64::from(precision)
+            | ((third_dimension as u64) << 4)
+            | (u64::from(third_dimension_precision) << 7),
+        &mut output,
+    );
+    Ok(output)
+}
+
+/// Decodes an unsigned header value, which isn't zigzag-encoded.
+fn header_value(bytes: &[u8], pos: &mut usize) -> Result<u64, PolylineError> {
+    if *pos == bytes.len() {
+        return Err(flexible_error("missing header".to_string()));
+    }
+    Ok(zigzag(decode_next(bytes, pos)?))
+}
+
+fn into_base64(mut output: Vec<u8>) -> String {
+    to_base64(&mut output);
+    // SAFETY: the base64 alphabet is ASCII
+    unsafe { String::from_utf8_unchecked(output) }
+}
+
+fn flexible_error(message: String) -> PolylineError {
+    PolylineError::FlexiblePolylineError { message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo_types::{coord, line_string};
+
+    // The examples of the format's specification
+    const ENCODED_2D: &str = "BFoz5xJ67i1B1B7PzIhaxL7Y";
+    const ENCODED_3D: &str = "BlBoz5xJ67i1BU1B7PUzIhaUxL7YU";
+
+    fn line() -> LineString<f64> {
+        line_string![
+            (x: 8.69821, y: 50.10228),
+            (x: 8.69567, y: 50.10201),
+            (x: 8.6915, y: 50.10063),
+            (x: 8.68752, y: 50.09878),
+        ]
+    }
+
+    #[test]
+    fn round_trips() {
+        assert_eq!(encode(line(), 5).unwrap(), ENCODED_2D);
+        assert_eq!(
+            decode(ENCODED_2D).unwrap(),
+            FlexiblePolyline {
+                precision: 5,
+                third_dimension: ThirdDimension::Absent,
+                third_dimension_precision: 0,
+                line: line(),
+                third_dimension_values: vec![],
+            }
+        );
+
+        let heights = [10.0, 20.0, 30.0, 40.0];
+        let coords = line().into_iter().zip(heights);
+        assert_eq!(
+            encode_3d(coords, 5, ThirdDimension::Altitude, 0).unwrap(),
+            ENCODED_3D
+        );
+        let decoded = decode(ENCODED_3D).unwrap();
+        assert_eq!(decoded.third_dimension, ThirdDimension::Altitude);
+        assert_eq!(decoded.line, line());
+        assert_eq!(decoded.third_dimension_values, heights);
+
+        assert_eq!(decode(&encode([], 7).unwrap()).unwrap().precision, 7);
+        let coords = [(Coord { x: 1.0, y: 2.0 }, -0.125)];
+        let encoded = encode_3d(coords, 15, ThirdDimension::Custom2, 3).unwrap();
+        let decoded = decode(&encoded).unwrap();
+        assert_eq!(decoded.third_dimension, ThirdDimension::Custom2);
+        assert_eq!(decoded.third_dimension_precision, 3);
+        assert_eq!(decoded.third_dimension_values, [-0.125]);
+    }
+
+    #[test]
+    fn converts_google_polylines() {
+        let google = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let flexible = google_to_flex(google, 5).unwrap();
+        assert_eq!(flexible, "BFgx_qH_x09Wg2tNvvyEguyP35yhB");
+        assert_eq!(flex_to_google(&flexible).unwrap(), (google.to_string(), 5));
+        assert_eq!(
+            decode(&flexible).unwrap().line,
+            crate::decode_polyline(google, 5).unwrap()
+        );
+
+        let (google, precision) = flex_to_google(ENCODED_2D).unwrap();
+        assert_eq!(crate::decode_polyline(&google, precision).unwrap(), line());
+        assert_eq!(google_to_flex(&google, precision).unwrap(), ENCODED_2D);
+        assert_eq!(google_to_flex("", 6).unwrap(), "BG");
+        assert_eq!(flex_to_google("BG").unwrap(), (String::new(), 6));
+    }
+
+    #[test]
+    fn reports_conversion_errors() {
+        assert!(matches!(
+            flex_to_google(ENCODED_3D),
+            Err(PolylineError::FlexiblePolylineError { .. })
+        ));
+        assert!(matches!(
+            google_to_flex("_p~iF~ps|U", 16),
+            Err(PolylineError::FlexiblePolylineError { .. })
+        ));
+        assert_eq!(
+            google_to_flex("_p~iF~ps|U_ulL", 5).unwrap_err(),
+            PolylineError::NoLongError { idx: 10 }
+        );
+        assert_eq!(
+            flex_to_google("BFgx_qH!").unwrap_err(),
+            PolylineError::DecodeError { idx: 7 }
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert!(matches!(
+            encode([], 16),
+            Err(PolylineError::FlexiblePolylineError { .. })
+        ));
+        assert!(matches!(
+            encode_3d([], 5, ThirdDimension::Absent, 0),
+            Err(PolylineError::FlexiblePolylineError { .. })
+        ));
+        assert_eq!(
+            encode(line_string![(x: 0.0, y: 91.0)], 5).unwrap_err(),
+            PolylineError::LatitudeCoordError {
+                coord: 91.0,
+                idx: 0
+            }
+        );
+
+        for invalid in ["", "B", "CF", "B___B"] {
+            assert!(matches!(
+                decode(invalid),
+                Err(PolylineError::FlexiblePolylineError { .. })
+            ));
+        }
+        assert_eq!(
+            decode("BFoz5xJ!").unwrap_err(),
+            PolylineError::DecodeError { idx: 7 }
+        );
+        assert_eq!(
+            decode("BFoz5xJ67i1B1B").unwrap_err(),
+            PolylineError::NoLongError { idx: 12 }
+        );
+        assert!(matches!(
+            decode("BlBoz5xJ67i1B"),
+            Err(PolylineError::FlexiblePolylineError { .. })
+        ));
+        // third dimension values of maximum length overflow when summed
+        assert!(matches!(
+            decode(&format!("BlB{}", "AA___________f".repeat(20))),
+            Err(PolylineError::FlexiblePolylineError { .. })
+        ));
+
+        let coord = coord! { x: 8.69821, y: 50.10228 };
+        for third in [1e300, -1e300, f64::INFINITY, f64::NAN] {
+            assert!(matches!(
+                encode_3d(
+                    [(coord, 0.0), (coord, third)],
+                    5,
+                    ThirdDimension::Altitude,
+                    0
+                ),
+                Err(PolylineError::FlexiblePolylineError { .. })
+            ));
+        }
+        let large = (1u64 << 57) as f64;
+        let encoded = encode_3d(
+            [(coord, -large), (coord, large)],
+            5,
+            ThirdDimension::Custom1,
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            decode(&encoded).unwrap().third_dimension_values,
+            [-large, large]
+        );
+    }
+}
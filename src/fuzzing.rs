@@ -0,0 +1,87 @@
+//! Generating coordinates and polylines for fuzzing with [`arbitrary`]
+
+use crate::encoded::Polyline;
+use crate::{MAX_LATITUDE, MAX_LONGITUDE, MIN_LATITUDE, MIN_LONGITUDE};
+use arbitrary::{Arbitrary, Result, Unstructured};
+use geo_types::{Coord, LineString};
+
+/// The highest precision generated polylines are encoded at
+const MAX_PRECISION: u32 = 15;
+
+/// A sequence of coordinates within the bounds, which can be encoded at any precision.
+///
+/// [`Arbitrary`] can't be implemented for [`LineString`] in this crate, so fuzz targets take
+/// this wrapper instead.
+///
+/// # Examples
+///
+/// ```
+/// use arbitrary::{Arbitrary, Unstructured};
+/// use polyline::Coordinates;
+///
+/// let mut u = Unstructured::new(&[7; 64]);
+/// let Coordinates(coords) = Coordinates::arbitrary(&mut u).unwrap();
+/// assert!(polyline::encode_coordinates(coords, 5).is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coordinates(pub LineString<f64>);
+
+impl<'a> Arbitrary<'a> for Coordinates {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        u.arbitrary_iter::<(u32, u32)>()?
+            .map(|coord| {
+                let (x, y) = coord?;
+                Ok(Coord {
+                    x: within(x, MIN_LONGITUDE, MAX_LONGITUDE),
+                    y: within(y, MIN_LATITUDE, MAX_LATITUDE),
+                })
+            })
+            .collect::<Result<_>>()
+            .map(Coordinates)
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (0, None)
+    }
+}
+
+/// Generates polylines which decode, encoded at precisions up to 15.
+impl<'a> Arbitrary<'a> for Polyline {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let precision = u.int_in_range(0..=MAX_PRECISION)?;
+        let Coordinates(coords) = u.arbitrary()?;
+        // coordinates within the bounds always encode
+        Ok(Polyline::encode(coords, precision).unwrap())
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (1, None)
+    }
+}
+
+/// Maps `n` onto the range from `min` to `max`.
+fn within(n: u32, min: f64, max: f64) -> f64 {
+    min + f64::from(n) / f64::from(u32::MAX) * (max - min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_onto_bounds() {
+        assert_eq!(within(0, MIN_LONGITUDE, MAX_LONGITUDE), -180.0);
+        assert_eq!(within(u32::MAX, MIN_LATITUDE, MAX_LATITUDE), 90.0);
+    }
+
+    #[test]
+    fn generates_valid_polylines() {
+        let data: Vec<u8> = (0..=255).cycle().take(4096).collect();
+        let mut u = Unstructured::new(&data);
+        while !u.is_empty() {
+            let polyline = Polyline::arbitrary(&mut u).unwrap();
+            assert!(polyline.precision() <= MAX_PRECISION);
+            assert!(polyline.decode().is_ok(), "{:?}", polyline);
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Reading and writing polylines in [`geozero`] pipelines.
+//!
+//! [`Polyline`] implements [`GeozeroGeometry`], so it can be converted to any format geozero
+//! writes, such as GeoJSON or FlatGeobuf, while [`PolylineWriter`] is a [`GeomProcessor`]
+//! encoding the lines of geometries read by geozero.
+//!
+//! With the `wkb` feature, polylines can also be converted to and from WKB and EWKB, as used by
+//! PostGIS.
+
+use crate::errors::PolylineError;
+use crate::{encode_coord, factor, Polyline};
+use geo_types::{Coord, LineString};
+use geozero::error::{GeozeroError, Result};
+#[cfg(feature = "wkb")]
+use geozero::wkb::{Ewkb, Wkb, WkbDialect, WkbWriter};
+use geozero::{GeomProcessor, GeozeroGeometry};
+
+impl GeozeroGeometry for Polyline {
+    fn process_geom<P: GeomProcessor>(&self, processor: &mut P) -> Result<()> {
+        process_line(&self.decode().map_err(geometry_error)?, processor)
+    }
+}
+
+fn process_line<P: GeomProcessor>(coords: &LineString<f64>, processor: &mut P) -> Result<()> {
+    processor.linestring_begin(true, coords.0.len(), 0)?;
+    for (idx, coord) in coords.0.iter().enumerate() {
+        processor.xy(coord.x, coord.y, idx)?;
+    }
+    processor.linestring_end(true, 0)
+}
+
+/// A [`GeomProcessor`] which encodes every line it processes as a polyline.
+///
+/// The rings of polygons are encoded as lines of their own, while points outside a line can't
+/// be encoded, and are reported as errors.
+///
+/// # Examples
+///
+/// ```
+/// use geozero::GeozeroGeometry;
+/// use polyline::geozero_io::PolylineWriter;
+/// use polyline::Polyline;
+///
+/// let polyline = Polyline::new("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5);
+/// let mut writer = PolylineWriter::new(6);
+/// polyline.process_geom(&mut writer).unwrap();
+/// assert_eq!(writer.into_polylines()[0].as_str(), "_izlhA~rlgdF_{geC~ywl@_kwzCn`{nI");
+/// ```
+#[derive(Debug)]
+pub struct PolylineWriter {
+    precision: u32,
+    factor: f64,
+    polylines: Vec<Polyline>,
+    line: Option<Line>,
+    /// The error encoding the last line failed with, if any
+    error: Option<PolylineError>,
+}
+
+/// The line being encoded
+#[derive(Debug)]
+struct Line {
+    output: Vec<u8>,
+    previous: Coord<i64>,
+    count: usize,
+}
+
+impl PolylineWriter {
+    /// Creates a writer encoding lines at `precision`.
+    pub fn new(precision: u32) -> Self {
+        PolylineWriter {
+            precision,
+            factor: factor(precision),
+            polylines: vec![],
+            line: None,
+            error: None,
+        }
+    }
+
+    /// Returns the polylines encoded so far, in the order their lines were processed.
+    pub fn into_polylines(self) -> Vec<Polyline> {
+        self.polylines
+    }
+}
+
+impl GeomProcessor for PolylineWriter {
+    fn xy(&mut self, x: f64, y: f64, _idx: usize) -> Result<()> {
+        let Some(line) = &mut self.line else {
+            return Err(GeozeroError::Geometry(
+                "only lines can be encoded as polylines".to_string(),
+            ));
+        };
+        if let Err(err) = encode_coord(
+            line.count,
+            Coord { x, y },
+            self.factor,
+            &mut line.previous,
+            &mut line.output,
+        ) {
+            let message = err.to_string();
+            self.error = Some(err);
+            return Err(GeozeroError::Geometry(message));
+        }
+        line.count += 1;
+        Ok(())
+    }
+
+    fn linestring_begin(&mut self, _tagged: bool, size: usize, _idx: usize) -> Result<()> {
+        self.line = Some(Line {
+            output: Vec::with_capacity(size * 8),
+            previous: Coord { x: 0, y: 0 },
+            count: 0,
+        });
+        Ok(())
+    }
+
+    fn linestring_end(&mut self, _tagged: bool, _idx: usize) -> Result<()> {
+        if let Some(line) = self.line.take() {
+            // SAFETY: encoded values are ASCII
+            let encoded = unsafe { String::from_utf8_unchecked(line.output) };
+            self.polylines.push(Polyline::new(encoded, self.precision));
+        }
+        Ok(())
+    }
+}
+
+fn geometry_error(err: PolylineError) -> GeozeroError {
+    GeozeroError::Geometry(err.to_string())
+}
+
+/// Decodes a Google Encoded Polyline as a WKB `LINESTRING`.
+///
+/// # Examples
+///
+/// ```
+/// use polyline::geozero_io::{polyline_to_wkb, wkb_to_polyline};
+///
+/// let wkb = polyline_to_wkb("_p~iF~ps|U_ulLnnqC", 5).unwrap();
+/// assert_eq!(wkb_to_polyline(&wkb, 5).unwrap(), "_p~iF~ps|U_ulLnnqC");
+/// ```
+#[cfg(feature = "wkb")]
+pub fn polyline_to_wkb(
+    polyline: &str,
+    precision: u32,
+) -> std::result::Result<Vec<u8>, PolylineError> {
+    write_wkb(polyline, precision, WkbDialect::Wkb, None)
+}
+
+/// Decodes a Google Encoded Polyline as an EWKB `LINESTRING`, with an optional SRID, for
+/// PostGIS.
+#[cfg(feature = "wkb")]
+pub fn polyline_to_ewkb(
+    polyline: &str,
+    precision: u32,
+    srid: Option<i32>,
+) -> std::result::Result<Vec<u8>, PolylineError> {
+    write_wkb(polyline, precision, WkbDialect::Ewkb, srid)
+}
+
+/// Encodes a WKB geometry holding a single line, such as a `LINESTRING`, as a Google Encoded
+/// Polyline.
+///
+/// WKB which can't be read, or doesn't hold exactly one line, is reported as a
+/// [`PolylineError::WkbError`].
+#[cfg(feature = "wkb")]
+pub fn wkb_to_polyline(wkb: &[u8], precision: u32) -> std::result::Result<String, PolylineError> {
+    read_line(&Wkb(wkb), precision)
+}
+
+/// Encodes an EWKB geometry holding a single line, such as a PostGIS `LINESTRING`, as a Google
+/// Encoded Polyline.
+///
+/// Any SRID is ignored. Errors are reported as by [`wkb_to_polyline`].
+#[cfg(feature = "wkb")]
+pub fn ewkb_to_polyline(ewkb: &[u8], precision: u32) -> std::result::Result<String, PolylineError> {
+    read_line(&Ewkb(ewkb), precision)
+}
+
+#[cfg(feature = "wkb")]
+fn write_wkb(
+    polyline: &str,
+    precision: u32,
+    dialect: WkbDialect,
+    srid: Option<i32>,
+) -> std::result::Result<Vec<u8>, PolylineError> {
+    let coords = crate::decode_polyline(polyline, precision)?;
+    let mut wkb = vec![];
+    let mut writer = WkbWriter::with_opts(&mut wkb, dialect, Default::default(), srid, vec![]);
+    process_line(&coords, &mut writer).map_err(wkb_error)?;
+    Ok(wkb)
+}
+
+#[cfg(feature = "wkb")]
+fn read_line(
+    geometry: &impl GeozeroGeometry,
+    precision: u32,
+) -> std::result::Result<String, PolylineError> {
+    let mut writer = PolylineWriter::new(precision);
+    if let Err(err) = geometry.process_geom(&mut writer) {
+        return Err(writer.error.unwrap_or_else(|| wkb_error(err)));
+    }
+    let mut polylines = writer.into_polylines();
+    if polylines.len() != 1 {
+        return Err(PolylineError::WkbError {
+            message: format!("expected a single line, found {}", polylines.len()),
+        });
+    }
+    Ok(polylines.remove(0).into_string())
+}
+
+#[cfg(feature = "wkb")]
+fn wkb_error(err: GeozeroError) -> PolylineError {
+    PolylineError::WkbError {
+        message: err.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geozero::ToGeo;
+
+    #[test]
+    fn round_trips() {
+        let polyline = Polyline::new("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5);
+        let mut writer = PolylineWriter::new(5);
+        polyline.process_geom(&mut writer).unwrap();
+        assert_eq!(writer.into_polylines()[0], polyline);
+
+        assert_eq!(
+            polyline.to_geo().unwrap(),
+            geo_types::Geometry::LineString(polyline.decode().unwrap())
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        let mut writer = PolylineWriter::new(5);
+        assert!(Polyline::new("_ibE_seK_seK", 5)
+            .process_geom(&mut writer)
+            .is_err());
+        assert!(writer.xy(1.0, 2.0, 0).is_err());
+
+        writer.linestring_begin(true, 1, 0).unwrap();
+        assert!(writer.xy(181.0, 2.0, 0).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "wkb")]
+    fn wkb() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let wkb = polyline_to_wkb(polyline, 5).unwrap();
+        // little-endian LINESTRING of 3 points
+        assert_eq!(wkb[..9], [1, 2, 0, 0, 0, 3, 0, 0, 0]);
+        assert_eq!(wkb_to_polyline(&wkb, 5).unwrap(), polyline);
+
+        let ewkb = polyline_to_ewkb(polyline, 5, Some(4326)).unwrap();
+        assert_eq!(ewkb[..9], [1, 2, 0, 0, 0x20, 0xe6, 0x10, 0, 0]);
+        assert_eq!(ewkb_to_polyline(&ewkb, 5).unwrap(), polyline);
+        assert_eq!(
+            polyline_to_ewkb("", 5, None).unwrap(),
+            [1, 2, 0, 0, 0, 0, 0, 0, 0]
+        );
+
+        assert_eq!(
+            polyline_to_wkb("_ibE_seK_seK", 5).unwrap_err(),
+            PolylineError::NoLongError { idx: 8 }
+        );
+        // a point
+        let mut point = vec![1, 1, 0, 0, 0];
+        point.extend(1f64.to_le_bytes());
+        point.extend(2f64.to_le_bytes());
+        assert!(matches!(
+            wkb_to_polyline(&point, 5),
+            Err(PolylineError::WkbError { .. })
+        ));
+        assert!(matches!(
+            wkb_to_polyline(&wkb[..20], 5),
+            Err(PolylineError::WkbError { .. })
+        ));
+        // a line out of bounds
+        let mut line = vec![1, 2, 0, 0, 0, 1, 0, 0, 0];
+        line.extend(181f64.to_le_bytes());
+        line.extend(2f64.to_le_bytes());
+        assert!(matches!(
+            wkb_to_polyline(&line, 5),
+            Err(PolylineError::LongitudeCoordError { idx: 0, .. })
+        ));
+    }
+}
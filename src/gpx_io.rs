@@ -0,0 +1,361 @@
+//! Conversion between polylines and GPX tracks, enabled with the `gpx` feature
+
+use crate::errors::PolylineError;
+use crate::{decode_polyline, encode_coordinates};
+use geo_types::{Coord, LineString};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::fmt::Display;
+
+/// A `trkpt`, with its `ele` if it has one
+struct TrackPoint {
+    coord: Coord<f64>,
+    elevation: Option<f64>,
+}
+
+fn gpx_error(err: impl Display) -> PolylineError {
+    PolylineError::GpxError {
+        message: err.to_string(),
+    }
+}
+
+fn number(value: &str, name: &str) -> Result<f64, PolylineError> {
+    value.trim().parse().map_err(|_| PolylineError::GpxError {
+        message: format!("`{}` in `{}` isn't a number", value, name),
+    })
+}
+
+fn attribute(tag: &BytesStart, name: &str) -> Result<f64, PolylineError> {
+    let value = tag
+        .try_get_attribute(name)
+        .map_err(gpx_error)?
+        .ok_or_else(|| PolylineError::GpxError {
+            message: format!("a `trkpt` has no `{}` attribute", name),
+        })?;
+    number(&value.unescape_value().map_err(gpx_error)?, name)
+}
+
+/// Reads the points of each `trkseg` in a GPX document, in the order they appear.
+///
+/// Waypoints and routes are skipped, as are the elements of track points other than `ele`.
+fn read_segments(gpx: &str) -> Result<Vec<Vec<TrackPoint>>, PolylineError> {
+    let mut reader = Reader::from_str(gpx);
+    let mut segments: Vec<Vec<TrackPoint>> = vec![];
+    let (mut in_segment, mut in_point, mut in_elevation) = (false, false, false);
+    loop {
+        let event = reader.read_event().map_err(gpx_error)?;
+        match &event {
+            Event::Start(tag) | Event::Empty(tag) => match tag.local_name().as_ref() {
+                b"trkseg" => {
+                    segments.push(vec![]);
+                    in_segment = matches!(event, Event::Start(_));
+                }
+                b"trkpt" if in_segment => {
+                    let coord = Coord {
+                        x: attribute(tag, "lon")?,
+                        y: attribute(tag, "lat")?,
+                    };
+                    if let Some(segment) = segments.last_mut() {
+                        segment.push(TrackPoint {
+                            coord,
+                            elevation: None,
+                        });
+                    }
+                    in_point = matches!(event, Event::Start(_));
+                }
+                b"trkpt" => return Err(gpx_error("a `trkpt` isn't in a `trkseg`")),
+                b"ele" => in_elevation = in_point && matches!(event, Event::Start(_)),
+                _ => {}
+            },
+            Event::Text(text) if in_elevation => {
+                let elevation = number(&text.decode().map_err(gpx_error)?, "ele")?;
+                if let Some(point) = segments.last_mut().and_then(|segment| segment.last_mut()) {
+                    point.elevation = Some(elevation);
+                }
+            }
+            Event::End(tag) => match tag.local_name().as_ref() {
+                b"trkseg" => in_segment = false,
+                b"trkpt" => in_point = false,
+                b"ele" => in_elevation = false,
+                _ => {}
+            },
+            Event::Eof => return Ok(segments),
+            _ => {}
+        }
+    }
+}
+
+/// Writes a GPX document with a track holding a segment for each line, with the line's
+/// elevations if it has them.
+fn write_gpx<S>(segments: S) -> Result<String, PolylineError>
+where
+    S: IntoIterator<Item = Result<(LineString<f64>, Vec<f64>), PolylineError>>,
+{
+    let mut output = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+        "\n",
+        r#"<gpx version="1.1" creator="polyline" xmlns="http://www.topografix.com/GPX/1/1">"#,
+        "\n  <trk>\n",
+    ));
+    for segment in segments {
+        let (line, elevations) = segment?;
+        output.push_str("    <trkseg>\n");
+        for (i, coord) in line.coords().enumerate() {
+            match elevations.get(i) {
+                Some(elevation) => output.push_str(&format!(
+                    "      <trkpt lat=\"{}\" lon=\"{}\"><ele>{}</ele></trkpt>\n",
+                    coord.y, coord.x, elevation
+                )),
+                None => output.push_str(&format!(
+                    "      <trkpt lat=\"{}\" lon=\"{}\"/>\n",
+                    coord.y, coord.x
+                )),
+            }
+        }
+        output.push_str("    </trkseg>\n");
+    }
+    output.push_str("  </trk>\n</gpx>\n");
+    Ok(output)
+}
+
+/// Encodes each track segment of a GPX document as a Google Encoded Polyline.
+///
+/// The segments of every track are encoded in the order they appear, while waypoints, routes
+/// and elevations are skipped. GPX which can't be parsed, or whose track points lack a
+/// latitude or longitude, is reported as a [`PolylineError::GpxError`], while out-of-bounds
+/// coordinates are reported at their index in the segment.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let gpx = r#"<gpx version="1.1"><trk><trkseg>
+///     <trkpt lat="38.5" lon="-120.2"><ele>1200</ele></trkpt>
+///     <trkpt lat="40.7" lon="-120.95"/>
+/// </trkseg></trk></gpx>"#;
+/// let polylines = polyline::gpx_to_polylines(gpx, 5).unwrap();
+/// assert_eq!(polylines, ["_p~iF~ps|U_ulLnnqC"]);
+/// ```
+pub fn gpx_to_polylines(gpx: &str, precision: u32) -> Result<Vec<String>, PolylineError> {
+    read_segments(gpx)?
+        .into_iter()
+        .map(|segment| encode_coordinates(segment.iter().map(|point| point.coord), precision))
+        .collect()
+}
+
+/// Decodes Google Encoded Polylines as a GPX 1.1 document, with a track holding a segment for
+/// each polyline.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let gpx = polyline::polylines_to_gpx(["_p~iF~ps|U_ulLnnqC"], 5).unwrap();
+/// assert!(gpx.contains(r#"<trkpt lat="40.7" lon="-120.95"/>"#));
+/// assert_eq!(polyline::gpx_to_polylines(&gpx, 5).unwrap(), ["_p~iF~ps|U_ulLnnqC"]);
+/// ```
+pub fn polylines_to_gpx<'a, P>(polylines: P, precision: u32) -> Result<String, PolylineError>
+where
+    P: IntoIterator<Item = &'a str>,
+{
+    write_gpx(
+        polylines
+            .into_iter()
+            .map(|polyline| Ok((decode_polyline(polyline, precision)?, vec![]))),
+    )
+}
+
+/// Encodes each track segment of a GPX document as a HERE Flexible Polyline, carrying the
+/// elevations of its points as the third dimension. This requires the `flexible_polyline`
+/// feature too.
+///
+/// Segments whose points have no elevations are encoded in two dimensions, while segments
+/// where only some points have them are reported as a [`PolylineError::GpxError`].
+///
+/// # Examples
+///
+/// ```
+/// use polyline::flexible::{self, ThirdDimension};
+///
+/// let gpx = r#"<gpx version="1.1"><trk><trkseg>
+///     <trkpt lat="50.10228" lon="8.69821"><ele>10</ele></trkpt>
+/// </trkseg></trk></gpx>"#;
+/// let polylines = polyline::gpx_to_flexible_polylines(gpx, 5, 0).unwrap();
+/// let decoded = flexible::decode(&polylines[0]).unwrap();
+/// assert_eq!(decoded.third_dimension, ThirdDimension::Elevation);
+/// assert_eq!(decoded.third_dimension_values, [10.0]);
+/// ```
+#[cfg(feature = "flexible_polyline")]
+pub fn gpx_to_flexible_polylines(
+    gpx: &str,
+    precision: u32,
+    elevation_precision: u32,
+) -> Result<Vec<String>, PolylineError> {
+    use crate::flexible::{self, ThirdDimension};
+
+    read_segments(gpx)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, segment)| {
+            let elevations = segment.iter().filter(|point| point.elevation.is_some());
+            match elevations.count() {
+                0 => flexible::encode(segment.iter().map(|point| point.coord), precision),
+                count if count == segment.len() => flexible::encode_3d(
+                    segment
+                        .iter()
+                        .map(|point| (point.coord, point.elevation.unwrap_or_default())),
+                    precision,
+                    ThirdDimension::Elevation,
+                    elevation_precision,
+                ),
+                _ => Err(PolylineError::GpxError {
+                    message: format!("only some points of segment {} have an `ele`", i),
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Decodes HERE Flexible Polylines as a GPX 1.1 document, with a track holding a segment for
+/// each polyline. This requires the `flexible_polyline` feature too.
+///
+/// Third dimensions holding an [`Elevation`](crate::flexible::ThirdDimension::Elevation) or
+/// [`Altitude`](crate::flexible::ThirdDimension::Altitude) are written as the `ele` of each
+/// point, while other kinds of third dimension are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let gpx = polyline::flexible_polylines_to_gpx(["BlBoz5xJ67i1BU"]).unwrap();
+/// assert!(gpx.contains(r#"<trkpt lat="50.10228" lon="8.69821"><ele>10</ele></trkpt>"#));
+/// ```
+#[cfg(feature = "flexible_polyline")]
+pub fn flexible_polylines_to_gpx<'a, P>(polylines: P) -> Result<String, PolylineError>
+where
+    P: IntoIterator<Item = &'a str>,
+{
+    use crate::flexible::{self, ThirdDimension};
+
+    write_gpx(polylines.into_iter().map(|polyline| {
+        let decoded = flexible::decode(polyline)?;
+        let elevations = match decoded.third_dimension {
+            ThirdDimension::Elevation | ThirdDimension::Altitude => decoded.third_dimension_values,
+            _ => vec![],
+        };
+        Ok((decoded.line, elevations))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GPX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <metadata><name>Morning Ride</name></metadata>
+  <wpt lat="1.0" lon="2.0"><ele>3.0</ele></wpt>
+  <trk>
+    <name>Morning Ride</name>
+    <trkseg>
+      <trkpt lat="38.5" lon="-120.2"><ele>1200.5</ele><time>2024-05-01T07:00:00Z</time></trkpt>
+      <trkpt lat="40.7" lon="-120.95"><ele>1210</ele></trkpt>
+    </trkseg>
+    <trkseg>
+      <trkpt lat="43.252" lon="-126.453"><ele> 1190.25 </ele></trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+
+    #[test]
+    fn round_trips() {
+        let polylines = gpx_to_polylines(GPX, 5).unwrap();
+        assert_eq!(polylines, ["_p~iF~ps|U_ulLnnqC", "_t~fGfzxbW"]);
+        let gpx = polylines_to_gpx(polylines.iter().map(String::as_str), 5).unwrap();
+        assert_eq!(gpx_to_polylines(&gpx, 5).unwrap(), polylines);
+        assert_eq!(gpx.matches("<trkseg>").count(), 2);
+
+        let empty = polylines_to_gpx([], 5).unwrap();
+        assert_eq!(gpx_to_polylines(&empty, 5).unwrap(), Vec::<String>::new());
+        assert_eq!(
+            gpx_to_polylines("<gpx><trk><trkseg/></trk></gpx>", 5).unwrap(),
+            [""]
+        );
+    }
+
+    #[cfg(feature = "flexible_polyline")]
+    #[test]
+    fn round_trips_elevations() {
+        use crate::flexible;
+
+        let polylines = gpx_to_flexible_polylines(GPX, 5, 2).unwrap();
+        let decoded = flexible::decode(&polylines[0]).unwrap();
+        assert_eq!(decoded.third_dimension_values, [1200.5, 1210.0]);
+        assert_eq!(
+            flexible::decode(&polylines[1])
+                .unwrap()
+                .third_dimension_values,
+            [1190.25]
+        );
+
+        let gpx = flexible_polylines_to_gpx(polylines.iter().map(String::as_str)).unwrap();
+        assert!(gpx.contains("<ele>1200.5</ele>"));
+        assert_eq!(gpx_to_flexible_polylines(&gpx, 5, 2).unwrap(), polylines);
+
+        // segments without elevations are two-dimensional
+        let flat = gpx_to_flexible_polylines(&polylines_to_gpx(["_p~iF~ps|U"], 5).unwrap(), 5, 2);
+        assert_eq!(
+            flat.unwrap(),
+            [flexible::encode(decoded.line.0[..1].to_vec(), 5).unwrap()]
+        );
+
+        let mixed = r#"<gpx><trk><trkseg>
+            <trkpt lat="1" lon="2"><ele>3</ele></trkpt><trkpt lat="1" lon="2"/>
+        </trkseg></trk></gpx>"#;
+        assert_eq!(
+            gpx_to_flexible_polylines(mixed, 5, 0).unwrap_err(),
+            PolylineError::GpxError {
+                message: "only some points of segment 0 have an `ele`".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert!(matches!(
+            gpx_to_polylines(
+                "<gpx><trk><trkseg><trkpt lat=\"1\"/></trkseg></trk></gpx>",
+                5
+            ),
+            Err(PolylineError::GpxError { .. })
+        ));
+        assert_eq!(
+            gpx_to_polylines("<gpx><trk><trkseg><trkpt lat=\"x\" lon=\"1\"/>", 5).unwrap_err(),
+            PolylineError::GpxError {
+                message: "`x` in `lat` isn't a number".to_string()
+            }
+        );
+        assert!(matches!(
+            gpx_to_polylines("<gpx><trk><trkpt lat=\"1\" lon=\"1\"/></trk></gpx>", 5),
+            Err(PolylineError::GpxError { .. })
+        ));
+        assert!(matches!(
+            gpx_to_polylines("<gpx><trk></trkseg></trk></gpx>", 5),
+            Err(PolylineError::GpxError { .. })
+        ));
+        assert!(matches!(
+            gpx_to_polylines(
+                "<gpx><trk><trkseg><trkpt lat=\"1\" lon=\"1\"/><trkpt lat=\"91\" lon=\"1\"/></trkseg></trk></gpx>",
+                5
+            ),
+            Err(PolylineError::LatitudeCoordError { idx: 1, .. })
+        ));
+        assert_eq!(
+            polylines_to_gpx(["_ibE_seK_seK"], 5).unwrap_err(),
+            PolylineError::NoLongError { idx: 8 }
+        );
+    }
+}
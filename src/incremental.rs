@@ -0,0 +1,308 @@
+//! Decoding of polylines which arrive a piece at a time.
+
+use crate::errors::PolylineError;
+use crate::{add_latitude, add_longitude, factor, DECODING_TABLE, INVALID_CHUNK, MAX_SHIFT};
+use geo_types::Coord;
+
+/// Decodes a polyline one byte at a time, keeping the partially decoded value between bytes.
+///
+/// It reports the same coordinates and errors, at the same indices, as [`crate::Decoder`]
+/// does for the whole polyline.
+#[derive(Debug)]
+pub(crate) struct IncrementalDecoder {
+    factor: f64,
+    scaled_lat: i64,
+    scaled_lon: i64,
+    /// The bits of the value being decoded so far
+    value: u64,
+    shift: u32,
+    /// Whether the next value completes a coordinate
+    in_longitude: bool,
+    /// The index of the next byte
+    idx: usize,
+    /// The index at which the current value started
+    value_start: usize,
+    /// The index at which the current coordinate started
+    lat_start: usize,
+}
+
+impl IncrementalDecoder {
+    pub(crate) fn new(precision: u32) -> Self {
+        IncrementalDecoder {
+            factor: factor(precision),
+            scaled_lat: 0,
+            scaled_lon: 0,
+            value: 0,
+            shift: 0,
+            in_longitude: false,
+            idx: 0,
+            value_start: 0,
+            lat_start: 0,
+        }
+    }
+
+    /// Decodes the next byte, returning a coordinate if it completes one.
+    #[inline]
+    pub(crate) fn push(&mut self, byte: u8) -> Result<Option<Coord<f64>>, PolylineError> {
+        let idx = self.idx;
+        self.idx += 1;
+        if self.shift == 0 {
+            self.value_start = idx;
+            if !self.in_longitude {
+                self.lat_start = idx;
+            }
+        }
+
+        let chunk = DECODING_TABLE[byte as usize];
+        if chunk == INVALID_CHUNK || self.shift > MAX_SHIFT {
+            return Err(PolylineError::DecodeError { idx });
+        }
+        self.value |= ((chunk & 0x1f) as u64) << self.shift;
+        self.shift += 5;
+        if chunk < 0x20 {
+            self.complete_value()
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Ends the polyline, returning the last coordinate if it was left unterminated.
+    pub(crate) fn finish(&mut self) -> Result<Option<Coord<f64>>, PolylineError> {
+        let coord = if self.shift > 0 {
+            self.complete_value()?
+        } else {
+            None
+        };
+        if self.in_longitude {
+            return Err(PolylineError::NoLongError {
+                idx: self.lat_start,
+            });
+        }
+        Ok(coord)
+    }
+
+    fn complete_value(&mut self) -> Result<Option<Coord<f64>>, PolylineError> {
+        let value = self.value;
+        self.value = 0;
+        self.shift = 0;
+        let change = if (value & 1) > 0 {
+            !(value >> 1)
+        } else {
+            value >> 1
+        } as i64;
+
+        if !self.in_longitude {
+            self.scaled_lat = add_latitude(self.scaled_lat, change, self.factor, self.value_start)?;
+            self.in_longitude = true;
+            return Ok(None);
+        }
+
+        self.scaled_lon = add_longitude(self.scaled_lon, change, self.factor, self.value_start)?;
+        self.in_longitude = false;
+        Ok(Some(Coord {
+            x: self.scaled_lon as f64 / self.factor,
+            y: self.scaled_lat as f64 / self.factor,
+        }))
+    }
+}
+
+/// Decodes a polyline which arrives in chunks, such as websocket messages, which may split it
+/// anywhere, including in the middle of a value.
+///
+/// Coordinates are returned as soon as the chunk completing them is fed, and reported with the
+/// same errors, at the same indices, as decoding the whole polyline at once. Decoding stops
+/// after the first error, and anything fed afterwards is ignored. If the chunk which caused it
+/// completed coordinates before it, they're returned, and the error is returned by the next call
+/// to [`feed`](StreamingDecoder::feed) or [`finish`](StreamingDecoder::finish).
+///
+/// # Examples
+///
+/// ```
+/// use polyline::StreamingDecoder;
+///
+/// let mut decoder = StreamingDecoder::new(5);
+/// assert_eq!(decoder.feed(b"_p~iF~ps").unwrap().len(), 0);
+/// assert_eq!(decoder.feed(b"|U_ulLnnqC_mqNvx").unwrap().len(), 2);
+/// assert_eq!(decoder.feed(b"q`@").unwrap().len(), 1);
+/// assert_eq!(decoder.finish().unwrap(), None);
+/// ```
+#[derive(Debug)]
+pub struct StreamingDecoder {
+    decoder: IncrementalDecoder,
+    failed: bool,
+    /// An error found after coordinates earlier in the same chunk
+    pending: Option<PolylineError>,
+}
+
+impl StreamingDecoder {
+    /// Creates a decoder for a polyline encoded at `precision`.
+    pub fn new(precision: u32) -> Self {
+        StreamingDecoder {
+            decoder: IncrementalDecoder::new(precision),
+            failed: false,
+            pending: None,
+        }
+    }
+
+    /// Decodes the next chunk of the polyline, returning the coordinates it completes.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Vec<Coord<f64>>, PolylineError> {
+        if let Some(err) = self.pending.take() {
+            return Err(err);
+        }
+        let mut coords = vec![];
+        if self.failed {
+            return Ok(coords);
+        }
+        for &byte in chunk {
+            match self.decoder.push(byte) {
+                Ok(Some(coord)) => coords.push(coord),
+                Ok(None) => {}
+                Err(err) => {
+                    self.failed = true;
+                    if coords.is_empty() {
+                        return Err(err);
+                    }
+                    self.pending = Some(err);
+                    break;
+                }
+            }
+        }
+        Ok(coords)
+    }
+
+    /// Ends the polyline, returning its last coordinate if its last value was left
+    /// unterminated.
+    ///
+    /// A polyline which ends without the longitude of its last coordinate is reported as a
+    /// [`PolylineError::NoLongError`].
+    pub fn finish(mut self) -> Result<Option<Coord<f64>>, PolylineError> {
+        if let Some(err) = self.pending {
+            return Err(err);
+        }
+        if self.failed {
+            return Ok(None);
+        }
+        self.decoder.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IncrementalDecoder, StreamingDecoder};
+    use crate::errors::PolylineError;
+    use crate::Decoder;
+
+    fn decode(
+        polyline: &str,
+        precision: u32,
+    ) -> Vec<Result<geo_types::Coord<f64>, crate::errors::PolylineError>> {
+        let mut decoder = IncrementalDecoder::new(precision);
+        let mut decoded = vec![];
+        for &byte in polyline.as_bytes() {
+            match decoder.push(byte) {
+                Ok(Some(coord)) => decoded.push(Ok(coord)),
+                Ok(None) => {}
+                Err(err) => {
+                    decoded.push(Err(err));
+                    return decoded;
+                }
+            }
+        }
+        decoded.extend(decoder.finish().transpose());
+        decoded
+    }
+
+    #[test]
+    // the same coordinates and errors as decoding the whole polyline
+    fn matches_decoder() {
+        for polyline in [
+            "",
+            "_p~iF~ps|U_ulLnnqC_mqNvxq`@",
+            "_ibE_seK_seK_seK",
+            "_ibE_seK_seK",
+            "_ibE_seK_se",
+            "_ibE_seK_seK_se",
+            "_p~iF~ps|U_ulLn😀nqC_mqNvxq`@",
+            "_p~iF~ps|U_ulLnnqC_mqNvxq`@ ",
+            "ugh_ugh",
+            "__________________________________",
+            // sums which overflow at high precisions
+            &"~~~~~~~~~~~^".repeat(64),
+        ] {
+            for precision in [5, 19] {
+                let expected: Vec<_> = Decoder::new(polyline.as_bytes(), precision).collect();
+                assert_eq!(decode(polyline, precision), expected, "{polyline}");
+
+                let mut decoder = StreamingDecoder::new(precision);
+                let streamed = decoder
+                    .feed(polyline.as_bytes())
+                    .and_then(|mut coords| {
+                        coords.extend(decoder.feed(&[])?);
+                        coords.extend(decoder.finish()?);
+                        Ok(coords)
+                    })
+                    .map(geo_types::LineString::new);
+                assert_eq!(streamed, crate::decode_polyline(polyline, precision));
+            }
+        }
+    }
+
+    #[test]
+    fn feeds_chunks() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let expected = crate::decode_polyline(polyline, 5).unwrap().0;
+        for split in 0..=polyline.len() {
+            let (start, end) = polyline.as_bytes().split_at(split);
+            let mut decoder = StreamingDecoder::new(5);
+            let mut coords = decoder.feed(start).unwrap();
+            coords.extend(decoder.feed(&[]).unwrap());
+            coords.extend(decoder.feed(end).unwrap());
+            coords.extend(decoder.finish().unwrap());
+            assert_eq!(coords, expected);
+        }
+
+        let mut decoder = StreamingDecoder::new(5);
+        assert_eq!(decoder.feed(b"_ibE_se").unwrap(), []);
+        assert_eq!(
+            decoder.finish().unwrap(),
+            Decoder::new(b"_ibE_se", 5).next().transpose().unwrap()
+        );
+    }
+
+    #[test]
+    fn stops_after_an_error() {
+        let mut decoder = StreamingDecoder::new(5);
+        assert_eq!(decoder.feed(b"_ibE_se").unwrap().len(), 0);
+        assert_eq!(
+            decoder.feed(b"!").unwrap_err(),
+            PolylineError::DecodeError { idx: 7 }
+        );
+        assert_eq!(decoder.feed(b"_seK").unwrap(), []);
+        assert_eq!(decoder.finish().unwrap(), None);
+
+        // coordinates before the error in the same chunk are kept
+        let mut decoder = StreamingDecoder::new(5);
+        assert_eq!(decoder.feed(b"_ibE_se").unwrap().len(), 0);
+        assert_eq!(decoder.feed(b"K_s!").unwrap().len(), 1);
+        assert_eq!(
+            decoder.feed(b"_seK").unwrap_err(),
+            PolylineError::DecodeError { idx: 10 }
+        );
+        assert_eq!(decoder.feed(b"_seK").unwrap(), []);
+        assert_eq!(decoder.finish().unwrap(), None);
+
+        let mut decoder = StreamingDecoder::new(5);
+        assert_eq!(decoder.feed(b"_ibE_seK!").unwrap().len(), 1);
+        assert_eq!(
+            decoder.finish().unwrap_err(),
+            PolylineError::DecodeError { idx: 8 }
+        );
+
+        let mut decoder = StreamingDecoder::new(5);
+        decoder.feed(b"_ibE_seK_seK").unwrap();
+        assert_eq!(
+            decoder.finish().unwrap_err(),
+            PolylineError::NoLongError { idx: 8 }
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! JNI bindings for Android, enabled with the `jni` feature
+//!
+//! These implement the native methods of the `org.georust.polyline.Polyline` class found in the
+//! `java` directory of this repository. Coordinates cross the boundary as primitive arrays to
+//! avoid per-point JNI calls, and failures are thrown as `IllegalArgumentException`.
+
+use crate::{decode_polyline, encode_coordinates};
+use geo_types::Coord;
+use jni::objects::{JClass, JDoubleArray, JObject, JObjectArray, JString};
+use jni::sys::jint;
+use jni::JNIEnv;
+
+const ILLEGAL_ARGUMENT: &str = "java/lang/IllegalArgumentException";
+
+fn check_precision(precision: jint) -> Result<u32, String> {
+    u32::try_from(precision).map_err(|_| format!("negative precision {}", precision))
+}
+
+fn encode_columns(lats: &[f64], lons: &[f64], precision: jint) -> Result<String, String> {
+    let precision = check_precision(precision)?;
+    if lats.len() != lons.len() {
+        return Err(format!(
+            "{} latitudes but {} longitudes",
+            lats.len(),
+            lons.len()
+        ));
+    }
+    let coords = lats.iter().zip(lons).map(|(&y, &x)| Coord { x, y });
+    encode_coordinates(coords, precision).map_err(|e| e.to_string())
+}
+
+fn decode_columns(polyline: &str, precision: jint) -> Result<(Vec<f64>, Vec<f64>), String> {
+    let line_string =
+        decode_polyline(polyline, check_precision(precision)?).map_err(|e| e.to_string())?;
+    Ok(line_string.coords().map(|c| (c.y, c.x)).unzip())
+}
+
+fn read_doubles(env: &mut JNIEnv, array: &JDoubleArray) -> jni::errors::Result<Vec<f64>> {
+    let mut buf = vec![0.0; env.get_array_length(array)? as usize];
+    env.get_double_array_region(array, 0, &mut buf)?;
+    Ok(buf)
+}
+
+fn new_doubles<'local>(
+    env: &mut JNIEnv<'local>,
+    values: &[f64],
+) -> jni::errors::Result<JDoubleArray<'local>> {
+    let array = env.new_double_array(values.len() as i32)?;
+    env.set_double_array_region(&array, 0, values)?;
+    Ok(array)
+}
+
+/// Implements `Polyline.encode(double[] lats, double[] lons, int precision)`.
+#[no_mangle]
+pub extern "system" fn Java_org_georust_polyline_Polyline_encode<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    lats: JDoubleArray<'local>,
+    lons: JDoubleArray<'local>,
+    precision: jint,
+) -> JString<'local> {
+    let result = (|| {
+        let lats = read_doubles(&mut env, &lats)?;
+        let lons = read_doubles(&mut env, &lons)?;
+        match encode_columns(&lats, &lons, precision) {
+            Ok(encoded) => env.new_string(encoded),
+            Err(message) => {
+                env.throw_new(ILLEGAL_ARGUMENT, message)?;
+                Ok(JString::default())
+            }
+        }
+    })();
+    // Any JNI error has already left a pending Java exception.
+    result.unwrap_or_default()
+}
+
+/// Implements `Polyline.decode(String polyline, int precision)`, returning `{lats, lons}`.
+#[no_mangle]
+pub extern "system" fn Java_org_georust_polyline_Polyline_decode<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    polyline: JString<'local>,
+    precision: jint,
+) -> JObjectArray<'local> {
+    let result = (|| {
+        let polyline: String = env.get_string(&polyline)?.into();
+        let (lats, lons) = match decode_columns(&polyline, precision) {
+            Ok(columns) => columns,
+            Err(message) => {
+                env.throw_new(ILLEGAL_ARGUMENT, message)?;
+                return Ok(JObjectArray::default());
+            }
+        };
+        let lats = new_doubles(&mut env, &lats)?;
+        let lons = new_doubles(&mut env, &lons)?;
+        let columns = env.new_object_array(2, "[D", JObject::null())?;
+        env.set_object_array_element(&columns, 0, lats)?;
+        env.set_object_array_element(&columns, 1, lons)?;
+        Ok::<_, jni::errors::Error>(columns)
+    })();
+    result.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns() {
+        assert_eq!(
+            encode_columns(&[38.5, 40.7, 43.252], &[-120.2, -120.95, -126.453], 5).unwrap(),
+            "_p~iF~ps|U_ulLnnqC_mqNvxq`@"
+        );
+        assert_eq!(
+            encode_columns(&[1.0], &[], 5).unwrap_err(),
+            "1 latitudes but 0 longitudes"
+        );
+        assert_eq!(
+            encode_columns(&[1.0], &[1.0], -5).unwrap_err(),
+            "negative precision -5"
+        );
+
+        assert_eq!(
+            decode_columns("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap(),
+            (vec![38.5, 40.7, 43.252], vec![-120.2, -120.95, -126.453])
+        );
+        assert_eq!(
+            decode_columns("_p~iF~ps|U", -1).unwrap_err(),
+            "negative precision -1"
+        );
+        assert!(decode_columns("_p~iF~ps|U_ulL", 5).is_err());
+    }
+}
@@ -21,39 +21,245 @@
 //! This crate uses `Coordinate` and `LineString` types from the `geo-types` crate, which encodes coordinates
 //! in `(x, y)` order. The Polyline algorithm and first-party documentation assumes the _opposite_ coordinate order.
 //! It is thus advisable to pay careful attention to the order of the coordinates you use for encoding and decoding.
+//!
+//! # Optional Features
+//!
+//! - `allocator_api`: adds `decode_polyline_in`, which decodes into a `Vec` placed in a custom
+//!   allocator. This requires a nightly toolchain.
+//! - `arbitrary`: implements `arbitrary::Arbitrary` for [`Polyline`] and the new `Coordinates`,
+//!   for generating structured inputs in fuzz targets.
+//! - `arrow`: adds `decode_polyline_array` and `encode_line_string_array`, which convert
+//!   between Arrow arrays of polylines and GeoArrow `LineString` arrays a batch at a time.
+//! - `bytes`: adds `decode_polyline_buf`, decoding polylines held in a `bytes::Buf`.
+//! - `csv`: adds `encode_csv` and `decode_to_csv`, which convert between polylines and CSV
+//!   records of coordinates.
+//! - `flexible_polyline`: adds [`flexible`], which encodes and decodes HERE Flexible Polylines,
+//!   including their optional third dimension.
+//! - `futures`: adds [`stream::CoordStream`], a `Stream` of the coordinates decoded from a
+//!   stream of polyline chunks.
+//! - `geo`: adds `decode_polyline_transformed` and `encode_coordinates_transformed`, which apply
+//!   a `geo::AffineTransform` while decoding or encoding, and `encode_simplified` and
+//!   `encode_densified`, which simplify or densify lines before encoding them, and lets
+//!   [`providers::google::StaticMapPath`] simplify lines to fit in a URL.
+//! - `geozero`: implements `geozero::GeozeroGeometry` for [`Polyline`], and adds
+//!   `geozero_io::PolylineWriter`, which encodes the lines of geometries processed by geozero.
+//! - `gpx`: adds `gpx_to_polylines` and `polylines_to_gpx`, which convert between GPX track
+//!   segments and polylines, and, with `flexible_polyline`, `gpx_to_flexible_polylines` and
+//!   `flexible_polylines_to_gpx`, which carry the elevations of track points as the third
+//!   dimension of Flexible Polylines.
+//! - `nalgebra`: adds `decode_to_nalgebra_points` and `encode_nalgebra_points`, which convert
+//!   between polylines and `nalgebra::Point2`s.
+//! - `ndarray`: adds `decode_to_ndarray`, which decodes a polyline into an `N×2` matrix of
+//!   `longitude, latitude` rows.
+//! - `polars`: adds [`polars_io`], with expressions encoding and decoding Polars columns of
+//!   polylines.
+//! - `proj`: adds `decode_polyline_reproject` and `encode_coordinates_reproject`, which
+//!   reproject coordinates between WGS84 and a `proj4rs::Proj`, such as Web Mercator, while
+//!   decoding or encoding.
+//! - `proptest`: adds [`strategies`], which generate coordinates and valid or invalid
+//!   polylines for property tests.
+//! - `serde_json`: adds helpers to [`providers`] which decode the polylines in routing
+//!   services' JSON responses.
+//! - `smallvec`: adds `decode_polyline_smallvec`, which decodes short polylines without
+//!   allocating.
+//! - `test-util`: adds [`testvectors`], the conformance suite this crate's codec is tested
+//!   against, for checking wrappers and other implementations.
+//! - `tokio`: adds [`tokio_io::AsyncDecoder`], which decodes polylines as they're read from
+//!   a Tokio `AsyncRead`.
+//! - `wkb`: adds conversions between polylines and WKB or EWKB `LINESTRING`s to `geozero_io`.
+//! - `wkt`: adds `polyline_to_wkt` and `wkt_to_polyline`, which convert between polylines and
+//!   WKT `LINESTRING`s.
+//! - `simd`: vectorises encoding and decoding using `std::simd`, picking the best instruction set
+//!   supported by the CPU at runtime. This requires a nightly toolchain.
+
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+#![cfg_attr(feature = "simd", feature(portable_simd))]
 
 pub mod errors;
 use errors::PolylineError;
 
-use geo_types::{Coord, LineString};
-use std::char;
-use std::iter::{Enumerate, Peekable};
+#[cfg(feature = "geo")]
+mod algorithms;
+#[cfg(feature = "geo")]
+pub use algorithms::{
+    decode_polyline_transformed, encode_coordinates_transformed, encode_densified,
+    encode_simplified, Spacing,
+};
+mod alphabet;
+pub use alphabet::{decode_polyline_with_alphabet, encode_coordinates_with_alphabet, Alphabet};
+#[cfg(feature = "arrow")]
+mod arrow_io;
+#[cfg(feature = "arrow")]
+pub use arrow_io::{decode_polyline_array, encode_line_string_array, line_string_field};
+#[cfg(feature = "capi")]
+pub mod capi;
+mod container;
+pub use container::{decode_collection, encode_collection, split_collection};
+#[cfg(feature = "csv")]
+mod csv_io;
+#[cfg(feature = "csv")]
+pub use csv_io::{decode_to_csv, encode_csv};
+mod encoded;
+pub use encoded::Polyline;
+mod encoder;
+pub use encoder::Encoder;
+mod escaping;
+pub use escaping::{
+    decode_json_safe, decode_percent_encoded, decode_polyline_ignoring_whitespace, encode_json_safe,
+};
+#[cfg(feature = "flexible_polyline")]
+pub mod flexible;
+#[cfg(feature = "arbitrary")]
+mod fuzzing;
+#[cfg(feature = "arbitrary")]
+pub use fuzzing::Coordinates;
+#[cfg(feature = "geozero")]
+pub mod geozero_io;
+#[cfg(feature = "gpx")]
+mod gpx_io;
+#[cfg(all(feature = "gpx", feature = "flexible_polyline"))]
+pub use gpx_io::{flexible_polylines_to_gpx, gpx_to_flexible_polylines};
+#[cfg(feature = "gpx")]
+pub use gpx_io::{gpx_to_polylines, polylines_to_gpx};
+mod incremental;
+pub use incremental::StreamingDecoder;
+#[cfg(feature = "jni")]
+pub mod jni_bindings;
+mod ops;
+pub use ops::{
+    analyze, append_coordinate, apply_patch, approx_eq, canonicalize, chunk_by_encoded_len,
+    concat_polylines, convert_precision, decode_polyline_strict, decoded_bounds, diff,
+    join_segments, point_at_distance, point_at_fraction, point_count, quantization_report,
+    slice_points, split_at_point, truncate_points, PolylinePatch, PolylineStats,
+    QuantizationReport,
+};
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "rayon")]
+pub use parallel::{par_decode_polylines, par_encode};
+#[cfg(feature = "polars")]
+pub mod polars_io;
+pub mod providers;
+#[cfg(feature = "python")]
+pub mod python;
+mod recovery;
+pub use recovery::{
+    decode_polyline_lossy, decode_polyline_resync, repair, RecoveredPolyline, Repair, Repaired,
+};
+mod rounding;
+pub use rounding::{encode_coordinates_with_rounding, Rounding};
+#[cfg(feature = "proj")]
+mod reproject;
+#[cfg(feature = "simd")]
+mod simd;
+#[cfg(feature = "proj")]
+pub use reproject::{decode_polyline_reproject, encode_coordinates_reproject};
+#[cfg(feature = "proptest")]
+pub mod strategies;
+#[cfg(feature = "futures")]
+pub mod stream;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testvectors;
+#[cfg(feature = "tokio")]
+pub mod tokio_io;
+#[cfg(feature = "uniffi")]
+pub mod uniffi_bindings;
+mod validation;
+pub use validation::{
+    is_valid_polyline, validate, Problem, ProblemKind, Severity, ValidationReport,
+};
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "wkt")]
+mod wkt_io;
+#[cfg(feature = "wkt")]
+pub use wkt_io::{polyline_to_wkt, wkt_to_polyline};
+
+#[cfg(feature = "uniffi")]
+::uniffi::setup_scaffolding!();
+
+use geo_types::{Coord, LineString, MultiLineString, Rect};
 
 const MIN_LONGITUDE: f64 = -180.0;
 const MAX_LONGITUDE: f64 = 180.0;
 const MIN_LATITUDE: f64 = -90.0;
 const MAX_LATITUDE: f64 = 90.0;
 
-fn scale(n: f64, factor: i32) -> i64 {
-    let scaled = n * (f64::from(factor));
+include!(concat!(env!("OUT_DIR"), "/tables.rs"));
+
+/// Looks up the scale factor for a precision, only computing it for excessive precisions.
+#[inline]
+fn factor(precision: u32) -> f64 {
+    match POWERS_OF_TEN.get(precision as usize) {
+        Some(&factor) => factor,
+        None => 10f64.powi(precision.try_into().unwrap_or(i32::MAX)),
+    }
+}
+
+fn scale(n: f64, factor: f64) -> i64 {
+    let scaled = n * factor;
     scaled.round() as i64
 }
 
 #[inline(always)]
-fn encode(delta: i64, output: &mut String) -> Result<(), PolylineError> {
+fn zigzag(delta: i64) -> u64 {
     let mut value = delta << 1;
     if value < 0 {
         value = !value;
     }
-    while value >= 0x20 {
-        let from_char = char::from_u32(((0x20 | (value & 0x1f)) + 63) as u32)
-            .ok_or(PolylineError::EncodeToCharError)?;
-        output.push(from_char);
-        value >>= 5;
+    value as u64
+}
+
+#[inline(always)]
+fn encode(delta: i64, output: &mut Vec<u8>) {
+    emit(zigzag(delta), output);
+}
+
+/// The number of characters taken up by a zigzag-encoded value
+#[inline(always)]
+fn encoded_value_len(value: u64) -> usize {
+    (u64::BITS - (value | 1).leading_zeros()).div_ceil(5) as usize
+}
+
+/// The most chunks which are encoded together in a single word
+const WORD_CHUNKS: usize = 8;
+
+/// Appends the chunks of a zigzag-encoded value.
+///
+/// The number of chunks is derived from the value's leading zeros and they are all encoded at
+/// once, so values of varying magnitude don't lead to branch mispredictions.
+#[inline(always)]
+fn emit(value: u64, output: &mut Vec<u8>) {
+    let len = encoded_value_len(value);
+    // Near the end of the buffer the characters are pushed one at a time instead, so that
+    // presized buffers are never grown
+    if len > WORD_CHUNKS || output.capacity() - output.len() < WORD_CHUNKS {
+        for i in 0..len {
+            let continuation = u64::from(i + 1 < len) << 5;
+            output.push(ENCODING_TABLE[(continuation | ((value >> (5 * i)) & 0x1f)) as usize]);
+        }
+        return;
+    }
+
+    // Spread the value into 5-bit chunks, one per byte, halving the width of the groups each step
+    let mut chunks = value;
+    chunks = (chunks & 0xfffff) | ((chunks & 0xff_fff0_0000) << 12);
+    chunks = (chunks & 0x0000_03ff_0000_03ff) | ((chunks & 0x000f_fc00_000f_fc00) << 6);
+    chunks = (chunks & 0x001f_001f_001f_001f) | ((chunks & 0x03e0_03e0_03e0_03e0) << 3);
+    let continuations = (ONES * 0x20) & ((1 << (8 * (len - 1))) - 1);
+    let encoded = ((chunks | continuations) + ONES * 63).to_le_bytes();
+
+    // SAFETY: there's space for the whole word, checked above, of which the first `len` bytes
+    // are kept
+    unsafe {
+        let end = output.len();
+        std::ptr::copy_nonoverlapping(
+            encoded.as_ptr(),
+            output.as_mut_ptr().add(end),
+            encoded.len(),
+        );
+        output.set_len(end + len);
     }
-    let from_char = char::from_u32((value + 63) as u32).ok_or(PolylineError::EncodeToCharError)?;
-    output.push(from_char);
-    Ok(())
 }
 
 /// Encodes a Google Encoded Polyline.
@@ -71,45 +277,188 @@ pub fn encode_coordinates<C>(coordinates: C, precision: u32) -> Result<String, P
 where
     C: IntoIterator<Item = Coord<f64>>,
 {
-    let base: i32 = 10;
-    let factor: i32 = base.pow(precision);
+    encode_into(coordinates, precision, Vec::new())
+}
 
-    let mut output = String::new();
-    let mut previous = Coord { x: 0, y: 0 };
+/// Encodes a Google Encoded Polyline into a buffer allocated at its exact length up front.
+///
+/// The coordinates are traversed twice: once to compute the encoded length with
+/// [`encoded_len`], then again to encode them. This avoids repeatedly growing the output
+/// when encoding very long lines.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+/// use geo_types::line_string;
+///
+/// let coords = line_string![(x: 2.0, y: 1.0), (x: 4.0, y: 3.0)];
+/// let encoded = polyline::encode_coordinates_presized(coords, 5).unwrap();
+/// assert_eq!(encoded.capacity(), encoded.len());
+/// ```
+pub fn encode_coordinates_presized<C>(
+    coordinates: C,
+    precision: u32,
+) -> Result<String, PolylineError>
+where
+    C: IntoIterator<Item = Coord<f64>>,
+    C::IntoIter: Clone,
+{
+    let coordinates = coordinates.into_iter();
+    let len = encoded_len(coordinates.clone(), precision)?;
+    encode_into(coordinates, precision, Vec::with_capacity(len))
+}
+
+/// Computes the exact length of the polyline [`encode_coordinates`] would produce, without
+/// encoding it.
+///
+/// Returns the same errors as [`encode_coordinates`] for out-of-bounds coordinates.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+/// use geo_types::line_string;
+///
+/// let coords = line_string![(x: 2.0, y: 1.0), (x: 4.0, y: 3.0)];
+/// assert_eq!(polyline::encoded_len(coords, 5).unwrap(), 16);
+/// ```
+pub fn encoded_len<C>(coordinates: C, precision: u32) -> Result<usize, PolylineError>
+where
+    C: IntoIterator<Item = Coord<f64>>,
+{
+    let factor = factor(precision);
 
+    let mut len = 0;
+    let mut previous = Coord { x: 0, y: 0 };
     for (i, next) in coordinates.into_iter().enumerate() {
-        if !(MIN_LATITUDE..=MAX_LATITUDE).contains(&next.y) {
-            return Err(PolylineError::LatitudeCoordError {
-                coord: next.y,
-                idx: i,
-            });
-        }
-        if !(MIN_LONGITUDE..=MAX_LONGITUDE).contains(&next.x) {
-            return Err(PolylineError::LongitudeCoordError {
-                coord: next.x,
-                idx: i,
-            });
-        }
+        let scaled_next = scale_coord(i, next, factor)?;
+        len += encoded_value_len(zigzag(scaled_next.y - previous.y));
+        len += encoded_value_len(zigzag(scaled_next.x - previous.x));
+        previous = scaled_next;
+    }
+    Ok(len)
+}
+
+/// Computes the longest polyline [`encode_coordinates`] can produce for `point_count`
+/// coordinates, without needing the coordinates themselves.
+///
+/// This is useful for sizing buffers or database columns up front.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+/// use geo_types::line_string;
+///
+/// let coords = line_string![(x: 2.0, y: 1.0), (x: 4.0, y: 3.0)];
+/// let encoded = polyline::encode_coordinates(coords, 5).unwrap();
+/// assert!(encoded.len() <= polyline::encoded_len_upper_bound(2, 5));
+/// ```
+pub fn encoded_len_upper_bound(point_count: usize, precision: u32) -> usize {
+    let factor = 10u64.saturating_pow(precision);
+    // The largest change between consecutive coordinates spans the whole range of each axis
+    let max_lat = zigzag_bound((MAX_LATITUDE - MIN_LATITUDE) as u64, factor);
+    let max_lon = zigzag_bound((MAX_LONGITUDE - MIN_LONGITUDE) as u64, factor);
+    point_count.saturating_mul(encoded_value_len(max_lat) + encoded_value_len(max_lon))
+}
+
+/// The largest zigzag-encoded value of a change of at most `range` degrees
+fn zigzag_bound(range: u64, factor: u64) -> u64 {
+    range.saturating_mul(factor).saturating_mul(2)
+}
+
+fn encode_into<C>(
+    coordinates: C,
+    precision: u32,
+    mut output: Vec<u8>,
+) -> Result<String, PolylineError>
+where
+    C: IntoIterator<Item = Coord<f64>>,
+{
+    let factor = factor(precision);
+
+    let mut previous = Coord { x: 0, y: 0 };
+
+    let coordinates = coordinates.into_iter().enumerate();
+    #[cfg(feature = "simd")]
+    simd::encode_batches(coordinates, factor, &mut previous, &mut output)?;
+    #[cfg(not(feature = "simd"))]
+    for (i, next) in coordinates {
+        encode_coord(i, next, factor, &mut previous, &mut output)?;
+    }
+    // SAFETY: every character is encoded through `ENCODING_TABLE`, which only holds ASCII
+    Ok(unsafe { String::from_utf8_unchecked(output) })
+}
+
+/// Checks the bounds of the coordinate at index `i`.
+#[inline(always)]
+fn check_coord(i: usize, next: Coord<f64>) -> Result<(), PolylineError> {
+    if !(MIN_LATITUDE..=MAX_LATITUDE).contains(&next.y) {
+        return Err(PolylineError::LatitudeCoordError {
+            coord: next.y,
+            idx: i,
+        });
+    }
+    if !(MIN_LONGITUDE..=MAX_LONGITUDE).contains(&next.x) {
+        return Err(PolylineError::LongitudeCoordError {
+            coord: next.x,
+            idx: i,
+        });
+    }
+    Ok(())
+}
+
+/// The bound of the magnitude of scaled coordinates, so the change between any two fits in the 60
+/// bits a value is decoded from
+const MAX_SCALED: i64 = 1 << 58;
 
-        let scaled_next = Coord {
+/// Checks the bounds of the coordinate at index `i` and scales it.
+#[inline(always)]
+fn scale_coord(i: usize, next: Coord<f64>, factor: f64) -> Result<Coord<i64>, PolylineError> {
+    check_coord(i, next)?;
+    check_scaled(
+        i,
+        next,
+        Coord {
             x: scale(next.x, factor),
             y: scale(next.y, factor),
-        };
-        encode(scaled_next.y - previous.y, &mut output).map_err(|_| {
-            PolylineError::CoordEncodingError {
-                coord: next,
-                idx: i,
-            }
-        })?;
-        encode(scaled_next.x - previous.x, &mut output).map_err(|_| {
-            PolylineError::CoordEncodingError {
-                coord: next,
-                idx: i,
-            }
-        })?;
-        previous = scaled_next;
+        },
+    )
+}
+
+/// Checks the coordinate at index `i` can be encoded once scaled, which it can't at precisions
+/// so high that the change between coordinates doesn't fit in a value.
+#[inline(always)]
+fn check_scaled(
+    i: usize,
+    next: Coord<f64>,
+    scaled: Coord<i64>,
+) -> Result<Coord<i64>, PolylineError> {
+    if scaled.x.unsigned_abs() >= MAX_SCALED as u64 || scaled.y.unsigned_abs() >= MAX_SCALED as u64
+    {
+        return Err(PolylineError::CoordEncodingError {
+            coord: next,
+            idx: i,
+        });
     }
-    Ok(output)
+    Ok(scaled)
+}
+
+/// Encodes the coordinate at index `i` relative to the `previous` scaled coordinate.
+#[inline(always)]
+fn encode_coord(
+    i: usize,
+    next: Coord<f64>,
+    factor: f64,
+    previous: &mut Coord<i64>,
+    output: &mut Vec<u8>,
+) -> Result<(), PolylineError> {
+    let scaled_next = scale_coord(i, next, factor)?;
+    encode(scaled_next.y - previous.y, output);
+    encode(scaled_next.x - previous.x, output);
+    *previous = scaled_next;
+    Ok(())
 }
 
 /// Decodes a Google Encoded Polyline.
@@ -124,31 +473,582 @@ where
 /// let decoded_polyline = polyline::decode_polyline(&"_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5);
 /// ```
 pub fn decode_polyline(polyline: &str, precision: u32) -> Result<LineString<f64>, PolylineError> {
-    let mut scaled_lat: i64 = 0;
-    let mut scaled_lon: i64 = 0;
-    let mut coordinates = vec![];
-    let base: i32 = 10;
-    let factor = i64::from(base.pow(precision));
-
-    let mut chars = polyline.as_bytes().iter().copied().enumerate().peekable();
-
-    while let Some((lat_start, _)) = chars.peek().copied() {
-        let latitude_change = decode_next(&mut chars)?;
-        scaled_lat += latitude_change;
-        let lat = scaled_lat as f64 / factor as f64;
+    decode_polyline_bytes(polyline.as_bytes(), precision)
+}
+
+/// Returns an iterator lazily decoding the coordinates of a Google Encoded Polyline.
+///
+/// Only as much of the polyline as the coordinates taken is decoded, which suits polylines
+/// whose first or last coordinates are all that's needed.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+/// use geo_types::coord;
+///
+/// let mut coords = polyline::decode_polyline_iter("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5);
+/// assert_eq!(coords.next(), Some(Ok(coord! { x: -120.2, y: 38.5 })));
+/// assert_eq!(coords.next_back(), Some(Ok(coord! { x: -126.453, y: 43.252 })));
+/// assert_eq!(coords.next(), Some(Ok(coord! { x: -120.95, y: 40.7 })));
+/// assert_eq!(coords.next(), None);
+/// ```
+pub fn decode_polyline_iter(polyline: &str, precision: u32) -> Decoder<'_> {
+    Decoder::new(polyline.as_bytes(), precision)
+}
+
+/// Decodes a Google Encoded Polyline from raw bytes, without validating them as UTF-8 first.
+///
+/// This is handy for decoding request bodies and other binary payloads, which are decoded
+/// exactly as [`decode_polyline`] decodes the equivalent string.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let decoded_polyline = polyline::decode_polyline_bytes(b"_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5);
+/// ```
+pub fn decode_polyline_bytes(
+    polyline: &[u8],
+    precision: u32,
+) -> Result<LineString<f64>, PolylineError> {
+    #[cfg(feature = "simd")]
+    if let Some(coordinates) = simd::decode(polyline, factor(precision)) {
+        return Ok(LineString::new(coordinates));
+    }
+    let mut coordinates = Vec::with_capacity(count_terminators(polyline) / 2);
+    for coordinate in Decoder::new(polyline, precision) {
+        coordinates.push(coordinate?);
+    }
+    Ok(LineString::new(coordinates))
+}
+
+/// Decodes a Google Encoded Polyline from a [`bytes::Buf`], such as a [`bytes::Bytes`] request
+/// body.
+///
+/// [`bytes::Bytes`] are decoded in place, while buffers made up of several chunks are gathered
+/// into a contiguous one first.
+///
+/// # Examples
+///
+/// ```
+/// use bytes::Bytes;
+/// use polyline;
+///
+/// let body = Bytes::from_static(b"_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+/// let decoded_polyline = polyline::decode_polyline_buf(body, 5);
+/// ```
+#[cfg(feature = "bytes")]
+pub fn decode_polyline_buf<B: bytes::Buf>(
+    mut polyline: B,
+    precision: u32,
+) -> Result<LineString<f64>, PolylineError> {
+    let polyline = polyline.copy_to_bytes(polyline.remaining());
+    decode_polyline_bytes(&polyline, precision)
+}
+
+/// Decodes a Google Encoded Polyline into a [`SmallVec`](smallvec::SmallVec), which only
+/// allocates if there are more than `N` coordinates.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let decoded = polyline::decode_polyline_smallvec::<4>("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap();
+/// assert!(!decoded.spilled());
+/// ```
+#[cfg(feature = "smallvec")]
+pub fn decode_polyline_smallvec<const N: usize>(
+    polyline: &str,
+    precision: u32,
+) -> Result<smallvec::SmallVec<[Coord<f64>; N]>, PolylineError> {
+    Decoder::new(polyline.as_bytes(), precision).collect()
+}
+
+/// Decodes a Google Encoded Polyline into an `N×2` [`Array2`](ndarray::Array2), with a
+/// `longitude, latitude` row for each coordinate, ready for numerical work.
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::array;
+/// use polyline;
+///
+/// let decoded = polyline::decode_to_ndarray("_p~iF~ps|U_ulLnnqC", 5).unwrap();
+/// assert_eq!(decoded, array![[-120.2, 38.5], [-120.95, 40.7]]);
+/// ```
+#[cfg(feature = "ndarray")]
+pub fn decode_to_ndarray(
+    polyline: &str,
+    precision: u32,
+) -> Result<ndarray::Array2<f64>, PolylineError> {
+    let mut values = Vec::with_capacity(estimate_point_count(polyline) * 2);
+    for coordinate in Decoder::new(polyline.as_bytes(), precision) {
+        let coordinate = coordinate?;
+        values.push(coordinate.x);
+        values.push(coordinate.y);
+    }
+    let rows = values.len() / 2;
+    Ok(ndarray::Array2::from_shape_vec((rows, 2), values)
+        .expect("values are pushed in longitude, latitude pairs"))
+}
+
+/// Decodes a Google Encoded Polyline into nalgebra [`Point2`](nalgebra::Point2)s, with
+/// longitude as `x` and latitude as `y`.
+///
+/// # Examples
+///
+/// ```
+/// use nalgebra::Point2;
+/// use polyline;
+///
+/// let decoded = polyline::decode_to_nalgebra_points("_p~iF~ps|U_ulLnnqC", 5).unwrap();
+/// assert_eq!(decoded, [Point2::new(-120.2, 38.5), Point2::new(-120.95, 40.7)]);
+/// ```
+#[cfg(feature = "nalgebra")]
+pub fn decode_to_nalgebra_points(
+    polyline: &str,
+    precision: u32,
+) -> Result<Vec<nalgebra::Point2<f64>>, PolylineError> {
+    Decoder::new(polyline.as_bytes(), precision)
+        .map(|coordinate| coordinate.map(|c| nalgebra::Point2::new(c.x, c.y)))
+        .collect()
+}
+
+/// Encodes nalgebra [`Point2`](nalgebra::Point2)s, with longitude as `x` and latitude as `y`,
+/// as a Google Encoded Polyline.
+///
+/// # Examples
+///
+/// ```
+/// use nalgebra::Point2;
+/// use polyline;
+///
+/// let points = [Point2::new(-120.2, 38.5), Point2::new(-120.95, 40.7)];
+/// let encoded = polyline::encode_nalgebra_points(points, 5).unwrap();
+/// assert_eq!(encoded, "_p~iF~ps|U_ulLnnqC");
+/// ```
+#[cfg(feature = "nalgebra")]
+pub fn encode_nalgebra_points<P>(points: P, precision: u32) -> Result<String, PolylineError>
+where
+    P: IntoIterator<Item = nalgebra::Point2<f64>>,
+{
+    encode_coordinates(
+        points.into_iter().map(|p| Coord { x: p.x, y: p.y }),
+        precision,
+    )
+}
+
+/// Decodes a Google Encoded Polyline into a `Vec` allocated in `alloc`, such as an arena.
+///
+/// # Examples
+///
+/// ```
+/// #![feature(allocator_api)]
+/// use polyline;
+/// use std::alloc::Global;
+///
+/// let decoded = polyline::decode_polyline_in("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5, Global).unwrap();
+/// assert_eq!(decoded.len(), 3);
+/// ```
+#[cfg(feature = "allocator_api")]
+pub fn decode_polyline_in<A: std::alloc::Allocator>(
+    polyline: &str,
+    precision: u32,
+    alloc: A,
+) -> Result<Vec<Coord<f64>, A>, PolylineError> {
+    let mut coordinates = Vec::with_capacity_in(estimate_point_count(polyline), alloc);
+    for coordinate in Decoder::new(polyline.as_bytes(), precision) {
+        coordinates.push(coordinate?);
+    }
+    Ok(coordinates)
+}
+
+/// Decodes only every `stride`th coordinate of a Google Encoded Polyline, starting with the
+/// first, for cheaply downsampling dense traces.
+///
+/// Every coordinate is still decoded and checked for errors, since each one is encoded relative
+/// to the one before it, but only those which are kept are stored.
+///
+/// # Panics
+///
+/// Panics if `stride` is 0.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let decoded = polyline::decode_polyline_strided("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5, 2).unwrap();
+/// assert_eq!(decoded.0.len(), 2);
+/// assert_eq!(decoded.0[1].y, 43.252);
+/// ```
+pub fn decode_polyline_strided(
+    polyline: &str,
+    precision: u32,
+    stride: usize,
+) -> Result<LineString<f64>, PolylineError> {
+    assert!(stride > 0, "stride must be positive");
+    let mut coordinates = Vec::with_capacity(estimate_point_count(polyline).div_ceil(stride));
+    for (i, coordinate) in Decoder::new(polyline.as_bytes(), precision).enumerate() {
+        let coordinate = coordinate?;
+        if i % stride == 0 {
+            coordinates.push(coordinate);
+        }
+    }
+    Ok(LineString::new(coordinates))
+}
+
+/// Decodes only the parts of a Google Encoded Polyline which lie within `rect`, such as a map's
+/// viewport.
+///
+/// Each run of consecutive segments which intersect `rect` becomes a line of its own, keeping
+/// the coordinates just outside `rect` at either end so the line can be drawn up to its edge.
+/// Every coordinate is still decoded and checked for errors.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+/// use geo_types::{coord, Rect};
+///
+/// let viewport = Rect::new(coord! { x: -127.0, y: 43.0 }, coord! { x: -126.0, y: 44.0 });
+/// let clipped = polyline::decode_polyline_clipped("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5, viewport).unwrap();
+/// assert_eq!(clipped.0.len(), 1);
+/// assert_eq!(clipped.0[0].0.len(), 2);
+/// ```
+pub fn decode_polyline_clipped(
+    polyline: &str,
+    precision: u32,
+    rect: Rect<f64>,
+) -> Result<MultiLineString<f64>, PolylineError> {
+    let mut lines = vec![];
+    let mut line = vec![];
+    let mut coordinates = Decoder::new(polyline.as_bytes(), precision);
+    let Some(mut previous) = coordinates.next().transpose()? else {
+        return Ok(MultiLineString::new(lines));
+    };
+    let mut single = true;
+    for coordinate in coordinates {
+        let coordinate = coordinate?;
+        single = false;
+        if segment_intersects(previous, coordinate, &rect) {
+            if line.is_empty() {
+                line.push(previous);
+            }
+            line.push(coordinate);
+        } else if !line.is_empty() {
+            lines.push(LineString::new(std::mem::take(&mut line)));
+        }
+        previous = coordinate;
+    }
+    if !line.is_empty() {
+        lines.push(LineString::new(line));
+    } else if single && segment_intersects(previous, previous, &rect) {
+        // a lone coordinate has no segments, so it's kept if it lies within `rect`
+        lines.push(LineString::new(vec![previous]));
+    }
+    Ok(MultiLineString::new(lines))
+}
+
+/// Whether the segment from `start` to `end` intersects `rect`, by Liang–Barsky clipping.
+fn segment_intersects(start: Coord<f64>, end: Coord<f64>, rect: &Rect<f64>) -> bool {
+    let (min, max) = (rect.min(), rect.max());
+    let delta = end - start;
+    // the portion of the segment within the rect, as fractions of its length
+    let (mut entry, mut exit) = (0.0_f64, 1.0_f64);
+    for (direction, distance) in [
+        (-delta.x, start.x - min.x),
+        (delta.x, max.x - start.x),
+        (-delta.y, start.y - min.y),
+        (delta.y, max.y - start.y),
+    ] {
+        if direction == 0.0 {
+            if distance < 0.0 {
+                return false;
+            }
+        } else if direction < 0.0 {
+            entry = entry.max(distance / direction);
+        } else {
+            exit = exit.min(distance / direction);
+        }
+    }
+    entry <= exit
+}
+
+/// Decodes the first `n` coordinates of a Google Encoded Polyline, returning them along with the
+/// byte offset at which decoding stopped.
+///
+/// The rest of the polyline isn't scanned, so this is cheap for previewing or sampling huge
+/// polylines. The offset is the length of the polyline if it has no more than `n` coordinates.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let (preview, offset) = polyline::decode_first_n("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 2, 5).unwrap();
+/// assert_eq!(preview.0.len(), 2);
+/// assert_eq!(offset, 18);
+/// ```
+pub fn decode_first_n(
+    polyline: &str,
+    n: usize,
+    precision: u32,
+) -> Result<(LineString<f64>, usize), PolylineError> {
+    let mut decoder = Decoder::new(polyline.as_bytes(), precision);
+    let coordinates = decoder.by_ref().take(n).collect::<Result<Vec<_>, _>>()?;
+    Ok((LineString::new(coordinates), decoder.pos))
+}
+
+/// Decodes a Google Encoded Polyline, applying `transform` to each coordinate as it's decoded,
+/// for example to shift its datum or swap its axes.
+///
+/// This avoids a second pass over the decoded coordinates. Coordinates are checked for errors
+/// before they're transformed.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+/// use geo_types::Coord;
+///
+/// let swapped = polyline::decode_polyline_map("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5, |coord| Coord {
+///     x: coord.y,
+///     y: coord.x,
+/// })
+/// .unwrap();
+/// assert_eq!(swapped.0[0], Coord { x: 38.5, y: -120.2 });
+/// ```
+pub fn decode_polyline_map<F>(
+    polyline: &str,
+    precision: u32,
+    mut transform: F,
+) -> Result<LineString<f64>, PolylineError>
+where
+    F: FnMut(Coord<f64>) -> Coord<f64>,
+{
+    let mut coordinates = Vec::with_capacity(estimate_point_count(polyline));
+    for coordinate in Decoder::new(polyline.as_bytes(), precision) {
+        coordinates.push(transform(coordinate?));
+    }
+    Ok(LineString::new(coordinates))
+}
+
+/// Estimates the number of coordinates in a polyline without decoding it, for example to
+/// preallocate storage for them.
+///
+/// Every value ends with a character below `_`, so this is exact for valid polylines.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// assert_eq!(polyline::estimate_point_count("_p~iF~ps|U_ulLnnqC_mqNvxq`@"), 3);
+/// ```
+pub fn estimate_point_count(polyline: &str) -> usize {
+    count_terminators(polyline.as_bytes()) / 2
+}
+
+/// Counts the characters which end a value.
+fn count_terminators(polyline: &[u8]) -> usize {
+    // Counting in blocks small enough for a byte-sized count lets this vectorise well
+    polyline
+        .chunks(u8::MAX as usize)
+        .map(|block| {
+            let count: u8 = block.iter().map(|&byte| u8::from(byte < 63 + 0x20)).sum();
+            usize::from(count)
+        })
+        .sum()
+}
+
+/// Adds a change to a scaled latitude, checking the sum is within the bounds.
+///
+/// Sums which overflow are out of the bounds at any precision, and are reported with their
+/// value as a float.
+#[inline(always)]
+fn add_latitude(scaled: i64, change: i64, factor: f64, idx: usize) -> Result<i64, PolylineError> {
+    match scaled.checked_add(change) {
+        Some(sum) if (MIN_LATITUDE..=MAX_LATITUDE).contains(&(sum as f64 / factor)) => Ok(sum),
+        sum => Err(PolylineError::LatitudeCoordError {
+            coord: sum.map_or((scaled as f64 + change as f64) / factor, |sum| {
+                sum as f64 / factor
+            }),
+            idx,
+        }),
+    }
+}
+
+/// Adds a change to a scaled longitude, checking the sum is within the bounds.
+#[inline(always)]
+fn add_longitude(scaled: i64, change: i64, factor: f64, idx: usize) -> Result<i64, PolylineError> {
+    match scaled.checked_add(change) {
+        Some(sum) if (MIN_LONGITUDE..=MAX_LONGITUDE).contains(&(sum as f64 / factor)) => Ok(sum),
+        sum => Err(PolylineError::LongitudeCoordError {
+            coord: sum.map_or((scaled as f64 + change as f64) / factor, |sum| {
+                sum as f64 / factor
+            }),
+            idx,
+        }),
+    }
+}
+
+/// Lazily decodes the coordinates of a polyline, one `(lat, lon)` pair at a time, as returned by
+/// [`decode_polyline_iter`].
+///
+/// Iteration stops after the first error. Coordinates can also be decoded from the end, with
+/// [`DoubleEndedIterator`]; the position of the last coordinate depends on every value before
+/// it, so the first coordinate taken from the end sums the remaining values first, without
+/// converting them to floats.
+#[derive(Debug, Clone)]
+pub struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    scaled_lat: i64,
+    scaled_lon: i64,
+    /// Where the coordinates taken from the end start
+    end: usize,
+    /// The scaled position of the coordinate ending at `end`, once it's been summed
+    scaled_end: Option<Coord<i64>>,
+    factor: f64,
+    failed: bool,
+}
+
+impl<'a> Decoder<'a> {
+    pub(crate) fn new(polyline: &'a [u8], precision: u32) -> Self {
+        Decoder {
+            bytes: polyline,
+            pos: 0,
+            scaled_lat: 0,
+            scaled_lon: 0,
+            end: polyline.len(),
+            scaled_end: None,
+            factor: factor(precision),
+            failed: false,
+        }
+    }
+
+    fn decode_coord(&mut self) -> Result<Coord<f64>, PolylineError> {
+        let lat_start = self.pos;
+        let latitude_change = decode_next(self.bytes, &mut self.pos)?;
+        self.scaled_lat = add_latitude(self.scaled_lat, latitude_change, self.factor, lat_start)?;
+
+        let lon_start = self.pos;
+        if lon_start == self.bytes.len() {
+            return Err(PolylineError::NoLongError { idx: lat_start });
+        }
+        let longitude_change = decode_next(self.bytes, &mut self.pos)?;
+        self.scaled_lon = add_longitude(self.scaled_lon, longitude_change, self.factor, lon_start)?;
+
+        Ok(Coord {
+            x: self.scaled_lon as f64 / self.factor,
+            y: self.scaled_lat as f64 / self.factor,
+        })
+    }
+
+    /// Returns the number of bytes decoded from the start of the polyline.
+    ///
+    /// After an error, this is just past the byte at which decoding stopped.
+    pub fn byte_offset(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the part of the polyline which is yet to be decoded, from either end.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyline;
+    ///
+    /// let mut coords = polyline::decode_polyline_iter("_p~iF~ps|U_ulLnnqC", 5);
+    /// coords.next();
+    /// assert_eq!(coords.byte_offset(), 10);
+    /// assert_eq!(coords.remaining(), b"_ulLnnqC");
+    /// ```
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.bytes[self.pos..self.end]
+    }
+
+    /// Counts the coordinates left to decode, so the iterator can report its exact length.
+    ///
+    /// Counting only scans for the characters which end values, which is much cheaper than
+    /// decoding them. An invalid polyline can still end early, after its first error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use polyline;
+    ///
+    /// let mut coords = polyline::decode_polyline_iter("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).counted();
+    /// assert_eq!(coords.len(), 3);
+    /// coords.next();
+    /// assert_eq!(coords.len(), 2);
+    /// ```
+    pub fn counted(self) -> CountedDecoder<'a> {
+        let rest = &self.bytes[self.pos..self.end];
+        // an unterminated last value is decoded too
+        let unterminated = rest
+            .last()
+            .is_some_and(|&byte| DECODING_TABLE[byte as usize] >= 0x20);
+        let values = count_terminators(rest) + usize::from(unterminated);
+        CountedDecoder {
+            remaining: if self.failed { 0 } else { values.div_ceil(2) },
+            decoder: self,
+        }
+    }
+
+    /// Sums the values up to `end`, returning the scaled position of the last coordinate.
+    ///
+    /// Each coordinate is checked against the bounds as it's summed, so errors are reported as
+    /// they would be when decoding from the start.
+    fn sum_to_end(&self) -> Result<Coord<i64>, PolylineError> {
+        let mut pos = self.pos;
+        let mut scaled = Coord {
+            x: self.scaled_lon,
+            y: self.scaled_lat,
+        };
+        while pos < self.end {
+            let lat_start = pos;
+            let latitude_change = decode_next(self.bytes, &mut pos)?;
+            scaled.y = add_latitude(scaled.y, latitude_change, self.factor, lat_start)?;
+            if pos == self.end {
+                return Err(PolylineError::NoLongError { idx: lat_start });
+            }
+            let lon_start = pos;
+            let longitude_change = decode_next(self.bytes, &mut pos)?;
+            scaled.x = add_longitude(scaled.x, longitude_change, self.factor, lon_start)?;
+        }
+        Ok(scaled)
+    }
+
+    /// Returns the start of the value ending just before `end`.
+    fn value_start(&self, end: usize) -> usize {
+        let mut start = end - 1;
+        while start > self.pos && DECODING_TABLE[self.bytes[start - 1] as usize] >= 0x20 {
+            start -= 1;
+        }
+        start
+    }
+
+    fn decode_coord_back(&mut self) -> Result<Coord<f64>, PolylineError> {
+        let scaled = match self.scaled_end {
+            Some(scaled) => scaled,
+            None => self.sum_to_end()?,
+        };
+        let lon_start = self.value_start(self.end);
+        let lat_start = self.value_start(lon_start);
+
+        let lat = scaled.y as f64 / self.factor;
         if !(MIN_LATITUDE..=MAX_LATITUDE).contains(&lat) {
             return Err(PolylineError::LatitudeCoordError {
                 coord: lat,
                 idx: lat_start,
             });
         }
-
-        let Some((lon_start, _)) = chars.peek().copied() else {
-            return Err(PolylineError::NoLongError { idx: lat_start });
-        };
-        let longitude_change = decode_next(&mut chars)?;
-        scaled_lon += longitude_change;
-        let lon = scaled_lon as f64 / factor as f64;
+        let lon = scaled.x as f64 / self.factor;
         if !(MIN_LONGITUDE..=MAX_LONGITUDE).contains(&lon) {
             return Err(PolylineError::LongitudeCoordError {
                 coord: lon,
@@ -156,26 +1056,138 @@ pub fn decode_polyline(polyline: &str, precision: u32) -> Result<LineString<f64>
             });
         }
 
-        coordinates.push(Coord { x: lon, y: lat });
+        // the values were checked when they were summed
+        let mut pos = lat_start;
+        let latitude_change = decode_next(self.bytes, &mut pos)?;
+        let longitude_change = decode_next(self.bytes, &mut pos)?;
+        self.scaled_end = Some(Coord {
+            x: scaled.x - longitude_change,
+            y: scaled.y - latitude_change,
+        });
+        self.end = lat_start;
+        Ok(Coord { x: lon, y: lat })
     }
+}
 
-    Ok(LineString::new(coordinates))
+impl Iterator for Decoder<'_> {
+    type Item = Result<Coord<f64>, PolylineError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.pos == self.end {
+            return None;
+        }
+        let result = self.decode_coord();
+        self.failed = result.is_err();
+        Some(result)
+    }
 }
 
-fn decode_next(
-    chars: &mut Peekable<Enumerate<impl std::iter::Iterator<Item = u8>>>,
-) -> Result<i64, PolylineError> {
-    let mut shift = 0;
-    let mut result = 0;
-    for (idx, mut byte) in chars.by_ref() {
-        if byte < 63 || (shift > 64 - 5) {
-            return Err(PolylineError::DecodeError { idx });
+impl DoubleEndedIterator for Decoder<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.failed || self.pos == self.end {
+            return None;
+        }
+        let result = self.decode_coord_back();
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
+
+/// A [`Decoder`] which knows how many coordinates it has left, as returned by
+/// [`Decoder::counted`].
+#[derive(Debug, Clone)]
+pub struct CountedDecoder<'a> {
+    decoder: Decoder<'a>,
+    remaining: usize,
+}
+
+impl CountedDecoder<'_> {
+    fn count(&mut self, item: &Option<Result<Coord<f64>, PolylineError>>) {
+        match item {
+            Some(Ok(_)) => self.remaining = self.remaining.saturating_sub(1),
+            // iteration stops after an error
+            Some(Err(_)) | None => self.remaining = 0,
         }
-        byte -= 63;
-        result |= ((byte & 0x1f) as u64) << shift;
-        shift += 5;
-        if byte < 0x20 {
-            break;
+    }
+}
+
+impl Iterator for CountedDecoder<'_> {
+    type Item = Result<Coord<f64>, PolylineError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.decoder.next();
+        self.count(&item);
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl DoubleEndedIterator for CountedDecoder<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let item = self.decoder.next_back();
+        self.count(&item);
+        item
+    }
+}
+
+impl ExactSizeIterator for CountedDecoder<'_> {}
+
+/// `0x01` in every byte of a word
+const ONES: u64 = u64::MAX / 0xff;
+/// The high bit of every byte of a word
+const HIGH_BITS: u64 = ONES * 0x80;
+
+/// Decodes a value from the first 8 bytes of the input at once, returning it along with the
+/// number of bytes it occupies.
+///
+/// Returns `None` if one of the bytes is outside the range `63..128` or the value spans more
+/// than 8 bytes, in which case it has to be decoded byte by byte.
+#[inline(always)]
+fn decode_word(word: u64) -> Option<(u64, usize)> {
+    // Subtracting 63 from a byte below 63, or any byte of 128 and up, leaves its high bit set
+    if (word.wrapping_sub(ONES * 63) | word) & HIGH_BITS != 0 {
+        return None;
+    }
+    let chunks = word - ONES * 63;
+    let terminators = !chunks & (ONES * 0x20);
+    if terminators == 0 {
+        return None;
+    }
+    let last = terminators.trailing_zeros() as usize / 8;
+    let mut bits = chunks & (ONES * 0x1f) & (u64::MAX >> (56 - 8 * last));
+    // Pack the 5-bit chunks together, doubling the width of the packed groups at each step
+    bits = (bits & 0x00ff_00ff_00ff_00ff) | ((bits & 0xff00_ff00_ff00_ff00) >> 3);
+    bits = (bits & 0x0000_ffff_0000_ffff) | ((bits & 0xffff_0000_ffff_0000) >> 6);
+    bits = (bits & 0x0000_0000_ffff_ffff) | ((bits & 0xffff_ffff_0000_0000) >> 12);
+    Some((bits, last + 1))
+}
+
+#[inline(always)]
+fn decode_next(bytes: &[u8], pos: &mut usize) -> Result<i64, PolylineError> {
+    let mut result = 0;
+    let word = bytes
+        .get(*pos..*pos + 8)
+        .and_then(|word| decode_word(u64::from_le_bytes(word.try_into().unwrap())));
+    if let Some((bits, len)) = word {
+        result = bits;
+        *pos += len;
+    } else {
+        let mut shift = 0;
+        while let Some(&byte) = bytes.get(*pos) {
+            let idx = *pos;
+            *pos += 1;
+            let chunk = DECODING_TABLE[byte as usize];
+            if chunk == INVALID_CHUNK || shift > MAX_SHIFT {
+                return Err(PolylineError::DecodeError { idx });
+            }
+            result |= ((chunk & 0x1f) as u64) << shift;
+            shift += 5;
+            if chunk < 0x20 {
+                break;
+            }
         }
     }
 
@@ -192,7 +1204,13 @@ mod tests {
 
     use super::decode_polyline;
     use super::encode_coordinates;
-    use geo_types::LineString;
+    use super::{
+        decode_first_n, decode_next, decode_polyline_bytes, decode_polyline_clipped,
+        decode_polyline_iter, decode_polyline_map, decode_polyline_strided, encode,
+        encode_coordinates_presized, encoded_len, encoded_len_upper_bound, estimate_point_count,
+        factor,
+    };
+    use geo_types::{Coord, LineString};
 
     struct TestCase {
         input: LineString<f64>,
@@ -349,4 +1367,489 @@ mod tests {
             _ => panic!("Got wrong error"),
         }
     }
+
+    #[test]
+    // values decoded a word at a time must match those decoded byte by byte
+    fn word_decoding() {
+        for delta in [
+            0,
+            1,
+            -1,
+            17,
+            -12020,
+            1_234_567,
+            -9_000_000_000,
+            i64::MAX >> 24,
+            i64::MIN >> 5,
+            i64::MAX >> 5,
+        ] {
+            let mut encoded = Vec::new();
+            encode(delta, &mut encoded);
+            let encoded = String::from_utf8(encoded).unwrap();
+            // shorter than a word, so decoded byte by byte
+            let mut pos = 0;
+            assert_eq!(decode_next(encoded.as_bytes(), &mut pos), Ok(delta));
+            assert_eq!(pos, encoded.len());
+            // padded to fill a word
+            let padded = format!("{encoded}????????");
+            let mut pos = 0;
+            assert_eq!(decode_next(padded.as_bytes(), &mut pos), Ok(delta));
+            assert_eq!(pos, encoded.len());
+        }
+
+        let err = decode_polyline("_ibE_se K_seK", 5).unwrap_err();
+        assert_eq!(err, crate::errors::PolylineError::DecodeError { idx: 7 });
+    }
+
+    #[test]
+    fn presized() {
+        let coords = vec![
+            Coord { x: -120.2, y: 38.5 },
+            Coord {
+                x: -120.95,
+                y: 40.7,
+            },
+            Coord {
+                x: -126.453,
+                y: 43.252,
+            },
+            Coord {
+                x: 179.99999,
+                y: -89.99999,
+            },
+        ];
+        for precision in [5, 6] {
+            let encoded = encode_coordinates(coords.clone(), precision).unwrap();
+            assert_eq!(
+                encoded_len(coords.clone(), precision).unwrap(),
+                encoded.len()
+            );
+            let presized = encode_coordinates_presized(coords.clone(), precision).unwrap();
+            assert_eq!(presized, encoded);
+            assert_eq!(presized.capacity(), presized.len());
+        }
+
+        let bad = vec![Coord { x: 1.0, y: 2.0 }, Coord { x: 190.0, y: 2.0 }];
+        assert_eq!(
+            encoded_len(bad.clone(), 5).unwrap_err(),
+            encode_coordinates(bad, 5).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn upper_bound() {
+        // every change is as large as it can be
+        let zigzag = [
+            Coord {
+                x: -180.0,
+                y: -90.0,
+            },
+            Coord { x: 180.0, y: 90.0 },
+            Coord {
+                x: -180.0,
+                y: -90.0,
+            },
+        ];
+        for precision in [0, 5, 6, 9] {
+            let len = encoded_len(zigzag, precision).unwrap();
+            assert!(len <= encoded_len_upper_bound(zigzag.len(), precision));
+            // after the first point, each one reaches the bound
+            let first_len = encoded_len(zigzag[..1].iter().copied(), precision).unwrap();
+            assert_eq!(
+                len - first_len,
+                encoded_len_upper_bound(zigzag.len() - 1, precision)
+            );
+        }
+        assert_eq!(encoded_len_upper_bound(0, 5), 0);
+        assert_eq!(encoded_len_upper_bound(usize::MAX, 5), usize::MAX);
+    }
+
+    #[test]
+    fn point_count() {
+        assert_eq!(estimate_point_count(""), 0);
+        // long enough to span several counting blocks
+        let coords: Vec<_> = (0..1000)
+            .map(|i| Coord {
+                x: f64::from(i) / 10.0,
+                y: -f64::from(i) / 20.0,
+            })
+            .collect();
+        let encoded = encode_coordinates(coords.clone(), 5).unwrap();
+        assert_eq!(estimate_point_count(&encoded), coords.len());
+    }
+
+    #[test]
+    fn scale_factors() {
+        assert_eq!(factor(0), 1.0);
+        assert_eq!(factor(5), 1e5);
+        assert_eq!(factor(22), 1e22);
+        assert_eq!(factor(23), 1e23);
+
+        // precisions whose factor doesn't fit in an i32
+        let coords = LineString::from(vec![[-120.2, 38.5], [-120.95, 40.7]]);
+        let encoded = encode_coordinates(coords.clone(), 10).unwrap();
+        assert_eq!(decode_polyline(&encoded, 10).unwrap(), coords);
+
+        use crate::errors::PolylineError;
+        use crate::{chunk_by_encoded_len, encode_coordinates_with_rounding, Encoder, Rounding};
+
+        // precisions at which the change between coordinates within bounds overflows
+        let limits = [Coord { x: 0.0, y: -90.0 }, Coord { x: 0.0, y: 90.0 }];
+        for precision in [17, 18] {
+            let err = PolylineError::CoordEncodingError {
+                coord: limits[0],
+                idx: 0,
+            };
+            assert_eq!(encode_coordinates(limits, precision).unwrap_err(), err);
+            assert_eq!(
+                encode_coordinates(vec![limits[1]; 8], precision).unwrap_err(),
+                PolylineError::CoordEncodingError {
+                    coord: limits[1],
+                    idx: 0
+                }
+            );
+            assert_eq!(encoded_len(limits, precision).unwrap_err(), err);
+            assert_eq!(
+                encode_coordinates_with_rounding(limits, precision, Rounding::HalfUp).unwrap_err(),
+                err
+            );
+            assert_eq!(
+                chunk_by_encoded_len(limits, precision, 100).unwrap_err(),
+                err
+            );
+            assert_eq!(Encoder::new(precision).push(limits[0]).unwrap_err(), err);
+        }
+        // small coordinates can still be encoded at high precisions
+        let small = [Coord { x: 0.0, y: 0.0 }, Coord { x: 0.25, y: -0.5 }];
+        let encoded = encode_coordinates(small, 17).unwrap();
+        assert_eq!(decode_polyline(&encoded, 17).unwrap().0, small);
+    }
+
+    #[test]
+    fn bytes() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        assert_eq!(
+            decode_polyline_bytes(polyline.as_bytes(), 5),
+            decode_polyline(polyline, 5)
+        );
+        assert_eq!(
+            decode_polyline_bytes(b"_p~iF~ps|U_ulLnnqC_mqN vxq`@", 5).unwrap_err(),
+            crate::errors::PolylineError::DecodeError { idx: 22 }
+        );
+
+        #[cfg(feature = "bytes")]
+        {
+            use bytes::{Buf, Bytes};
+            let (start, end) = polyline.split_at(13);
+            let chunked = Bytes::from_static(start.as_bytes()).chain(end.as_bytes());
+            assert_eq!(
+                crate::decode_polyline_buf(chunked, 5),
+                decode_polyline(polyline, 5)
+            );
+        }
+    }
+
+    #[test]
+    fn strided() {
+        let coords: Vec<_> = (0..10)
+            .map(|i| Coord {
+                x: f64::from(i),
+                y: -f64::from(i),
+            })
+            .collect();
+        let encoded = encode_coordinates(coords.clone(), 5).unwrap();
+        for stride in 1..12 {
+            let expected: Vec<_> = coords.iter().copied().step_by(stride).collect();
+            assert_eq!(
+                decode_polyline_strided(&encoded, 5, stride).unwrap().0,
+                expected
+            );
+        }
+        // errors in skipped coordinates are still reported
+        assert_eq!(
+            decode_polyline_strided("_ibE_seK_seK", 5, 2).unwrap_err(),
+            crate::errors::PolylineError::NoLongError { idx: 8 }
+        );
+    }
+
+    #[test]
+    fn clipped() {
+        use geo_types::{coord, Rect};
+
+        let rect = Rect::new(coord! { x: 0.0, y: 0.0 }, coord! { x: 10.0, y: 10.0 });
+        let clip = |coords: Vec<[f64; 2]>| {
+            let encoded = encode_coordinates(LineString::from(coords), 5).unwrap();
+            let clipped = decode_polyline_clipped(&encoded, 5, rect).unwrap();
+            clipped.0.into_iter().map(|line| line.0).collect::<Vec<_>>()
+        };
+        let line = |coords: &[[f64; 2]]| coords.iter().map(|&c| Coord::from(c)).collect::<Vec<_>>();
+
+        assert!(clip(vec![]).is_empty());
+        assert_eq!(clip(vec![[5.0, 5.0]]), [line(&[[5.0, 5.0]])]);
+        assert!(clip(vec![[15.0, 5.0]]).is_empty());
+        // leaving and re-entering the rect starts a new line
+        assert_eq!(
+            clip(vec![
+                [-5.0, 5.0],
+                [5.0, 5.0],
+                [15.0, 5.0],
+                [20.0, 5.0],
+                [15.0, 6.0],
+                [5.0, 6.0]
+            ]),
+            [
+                line(&[[-5.0, 5.0], [5.0, 5.0], [15.0, 5.0]]),
+                line(&[[15.0, 6.0], [5.0, 6.0]]),
+            ]
+        );
+        // segments crossing the rect without a coordinate inside it
+        assert_eq!(
+            clip(vec![[-5.0, 5.0], [15.0, 5.0], [15.0, -5.0], [20.0, 20.0]]),
+            [line(&[[-5.0, 5.0], [15.0, 5.0]])]
+        );
+        // passing just outside a corner
+        assert!(clip(vec![[-5.0, 6.0], [4.0, 15.0]]).is_empty());
+        assert_eq!(
+            decode_polyline_clipped("_ibE_seK_seK", 5, rect).unwrap_err(),
+            crate::errors::PolylineError::NoLongError { idx: 8 }
+        );
+    }
+
+    #[test]
+    fn first_n() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let coords = decode_polyline(polyline, 5).unwrap().0;
+        for (n, offset) in [(0, 0), (1, 10), (2, 18), (3, 27), (4, 27)] {
+            let (decoded, end) = decode_first_n(polyline, n, 5).unwrap();
+            assert_eq!(decoded.0, coords[..n.min(3)]);
+            assert_eq!(end, offset);
+        }
+        // errors after the first `n` coordinates aren't reached
+        assert_eq!(decode_first_n("_ibE_seK_seK", 1, 5).unwrap().1, 8);
+        assert_eq!(
+            decode_first_n("_ibE_seK_seK", 2, 5).unwrap_err(),
+            crate::errors::PolylineError::NoLongError { idx: 8 }
+        );
+    }
+
+    #[test]
+    fn map() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let mut calls = 0;
+        let shifted = decode_polyline_map(polyline, 5, |coord| {
+            calls += 1;
+            coord + Coord { x: 1.0, y: -1.0 }
+        })
+        .unwrap();
+        assert_eq!(calls, 3);
+        let expected: Vec<_> = decode_polyline(polyline, 5)
+            .unwrap()
+            .0
+            .into_iter()
+            .map(|coord| coord + Coord { x: 1.0, y: -1.0 })
+            .collect();
+        assert_eq!(shifted.0, expected);
+
+        assert_eq!(
+            decode_polyline_map("_ibE_seK_seK", 5, |coord| coord).unwrap_err(),
+            crate::errors::PolylineError::NoLongError { idx: 8 }
+        );
+    }
+
+    #[test]
+    fn iterates_from_both_ends() {
+        for polyline in [
+            "_p~iF~ps|U_ulLnnqC_mqNvxq`@",
+            "_ibE_seK_seK_seK",
+            "_ibE_se",
+            "",
+        ] {
+            let mut forward = decode_polyline_iter(polyline, 5)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            forward.reverse();
+            let backward = decode_polyline_iter(polyline, 5)
+                .rev()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(backward, forward);
+        }
+
+        let line = decode_polyline("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap();
+        let mut coords = decode_polyline_iter("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5);
+        assert_eq!(coords.next_back(), Some(Ok(line.0[2])));
+        assert_eq!(coords.next(), Some(Ok(line.0[0])));
+        assert_eq!(coords.next_back(), Some(Ok(line.0[1])));
+        assert_eq!(coords.next(), None);
+        assert_eq!(coords.next_back(), None);
+
+        assert_eq!(
+            decode_polyline_iter("_ibE_seK_seK", 5).next_back(),
+            Some(Err(crate::errors::PolylineError::NoLongError { idx: 8 }))
+        );
+        let mut coords = decode_polyline_iter("_ibE_seK!", 5);
+        assert_eq!(
+            coords.next_back(),
+            Some(Err(crate::errors::PolylineError::DecodeError { idx: 8 }))
+        );
+        assert_eq!(coords.next(), None);
+        let polyline =
+            encode_coordinates([Coord { x: 0.0, y: 5.0 }, Coord { x: 0.0, y: 80.0 }], 5).unwrap();
+        let err = decode_polyline(&polyline, 4).unwrap_err();
+        assert_eq!(
+            decode_polyline_iter(&polyline, 4).next_back(),
+            Some(Err(err))
+        );
+
+        // values of maximum length would overflow if summed without checking bounds
+        let polyline = "~~~~~~~~~~~^".repeat(64);
+        let err = decode_polyline_iter(&polyline, 5).next().unwrap();
+        assert!(matches!(
+            err,
+            Err(crate::errors::PolylineError::LatitudeCoordError { idx: 0, .. })
+        ));
+        assert_eq!(decode_polyline_iter(&polyline, 5).next_back(), Some(err));
+        assert!(decode_polyline_iter(&polyline, 15)
+            .next_back()
+            .unwrap()
+            .is_err());
+    }
+
+    #[test]
+    fn reports_progress() {
+        let mut coords = decode_polyline_iter("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5);
+        assert_eq!(coords.byte_offset(), 0);
+        assert_eq!(coords.remaining(), b"_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+        coords.next_back();
+        assert_eq!(coords.remaining(), b"_p~iF~ps|U_ulLnnqC");
+        coords.next();
+        coords.next();
+        assert_eq!(coords.byte_offset(), 18);
+        assert_eq!(coords.remaining(), b"");
+
+        let mut coords = decode_polyline_iter("_ibE_seK_i!E", 5);
+        coords.next();
+        assert!(coords.next().unwrap().is_err());
+        assert_eq!(coords.byte_offset(), 11);
+        assert_eq!(coords.remaining(), b"E");
+    }
+
+    #[test]
+    fn counts_coordinates() {
+        for (polyline, len) in [
+            ("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 3),
+            ("_ibE_se", 1),
+            ("", 0),
+            ("_ibE_seK_seK", 2),
+        ] {
+            let coords = decode_polyline_iter(polyline, 5).counted();
+            assert_eq!(coords.len(), len);
+            assert_eq!(coords.count(), len);
+        }
+
+        let mut coords = decode_polyline_iter("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5);
+        coords.next();
+        let mut coords = coords.counted();
+        assert_eq!(coords.len(), 2);
+        coords.next_back();
+        assert_eq!(coords.size_hint(), (1, Some(1)));
+        coords.next();
+        assert_eq!(coords.len(), 0);
+
+        // the count is only exact for valid polylines
+        let mut coords = decode_polyline_iter("_ibE!seK_seK_seK", 5).counted();
+        assert!(coords.next().unwrap().is_err());
+        assert_eq!(coords.len(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "smallvec")]
+    fn smallvec() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let inline = crate::decode_polyline_smallvec::<3>(polyline, 5).unwrap();
+        assert!(!inline.spilled());
+        assert_eq!(inline.as_slice(), decode_polyline(polyline, 5).unwrap().0);
+
+        let spilled = crate::decode_polyline_smallvec::<2>(polyline, 5).unwrap();
+        assert!(spilled.spilled());
+        assert_eq!(spilled, inline);
+
+        assert_eq!(
+            crate::decode_polyline_smallvec::<2>("_ibE_seK_seK", 5).unwrap_err(),
+            crate::errors::PolylineError::NoLongError { idx: 8 }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "ndarray")]
+    fn ndarray() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let decoded = crate::decode_to_ndarray(polyline, 5).unwrap();
+        assert_eq!(decoded.dim(), (3, 2));
+        let coords: Vec<_> = decoded
+            .rows()
+            .into_iter()
+            .map(|row| (row[0], row[1]))
+            .collect();
+        let expected: Vec<_> = decode_polyline(polyline, 5)
+            .unwrap()
+            .0
+            .iter()
+            .map(|c| (c.x, c.y))
+            .collect();
+        assert_eq!(coords, expected);
+
+        assert_eq!(crate::decode_to_ndarray("", 5).unwrap().dim(), (0, 2));
+        assert_eq!(
+            crate::decode_to_ndarray("_ibE_seK_seK", 5).unwrap_err(),
+            crate::errors::PolylineError::NoLongError { idx: 8 }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "nalgebra")]
+    fn nalgebra() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let points = crate::decode_to_nalgebra_points(polyline, 5).unwrap();
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[2], nalgebra::Point2::new(-126.453, 43.252));
+        assert_eq!(crate::encode_nalgebra_points(points, 5).unwrap(), polyline);
+
+        assert_eq!(
+            crate::decode_to_nalgebra_points("_ibE_seK_seK", 5).unwrap_err(),
+            crate::errors::PolylineError::NoLongError { idx: 8 }
+        );
+        assert_eq!(
+            crate::encode_nalgebra_points([nalgebra::Point2::new(181.0, 0.0)], 5).unwrap_err(),
+            crate::errors::PolylineError::LongitudeCoordError {
+                coord: 181.0,
+                idx: 0
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "allocator_api")]
+    fn allocator() {
+        use std::alloc::{AllocError, Allocator, Global, Layout};
+        use std::cell::Cell;
+        use std::ptr::NonNull;
+
+        struct Counting<'a>(&'a Cell<usize>);
+        unsafe impl Allocator for Counting<'_> {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                self.0.set(self.0.get() + 1);
+                Global.allocate(layout)
+            }
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                unsafe { Global.deallocate(ptr, layout) }
+            }
+        }
+
+        let allocations = Cell::new(0);
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let decoded = crate::decode_polyline_in(polyline, 5, Counting(&allocations)).unwrap();
+        assert_eq!(decoded.as_slice(), decode_polyline(polyline, 5).unwrap().0);
+        assert_eq!(allocations.get(), 1);
+    }
 }
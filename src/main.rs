@@ -0,0 +1,380 @@
+//! Command-line encoder and decoder for Google Encoded Polylines, enabled with the `cli` feature
+//!
+//! Coordinates are read and written in the format selected with `--format`; by default, one per
+//! line as `longitude,latitude`.
+//!
+//! With `--batch`, every input line is converted on its own, in parallel, and written as one
+//! output line in input order. Batch geometries are single-line: `text` writes space-separated
+//! `longitude,latitude` pairs. With `--column`, the input is instead CSV with a header row, and
+//! only the named column is converted.
+
+use clap::{Parser, Subcommand, ValueEnum};
+use geo_types::{Coord, LineString};
+use polyline::{decode_polyline, encode_coordinates};
+use rayon::prelude::*;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use wkt::{ToWkt, TryFromWkt};
+
+#[derive(Parser)]
+#[command(version, about = "Encode and decode Google Encoded Polylines")]
+struct Cli {
+    /// The number of decimal places encoded in the polyline
+    #[arg(short, long, default_value_t = 5, global = true)]
+    precision: u32,
+    /// The format of the coordinates read by `encode` and written by `decode`
+    #[arg(short, long, value_enum, default_value_t = Format::Text, global = true)]
+    format: Format,
+    /// Convert every input line separately, in parallel
+    #[arg(short, long, global = true)]
+    batch: bool,
+    /// With `--batch`, read CSV with a header row and convert the named column
+    #[arg(short, long, global = true, requires = "batch")]
+    column: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// One `longitude,latitude` pair per line
+    Text,
+    /// `longitude,latitude` rows below a header row
+    Csv,
+    /// A GeoJSON LineString geometry, or a Feature containing one
+    Geojson,
+    /// A WKT LINESTRING
+    Wkt,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Encode coordinates into a polyline
+    Encode {
+        /// The file to read; stdin if omitted
+        input: Option<PathBuf>,
+    },
+    /// Decode a polyline into coordinates
+    Decode {
+        /// The file to read; stdin if omitted
+        input: Option<PathBuf>,
+    },
+}
+
+fn open(input: Option<PathBuf>) -> io::Result<Box<dyn BufRead>> {
+    Ok(match input {
+        Some(path) => Box::new(BufReader::new(File::open(path)?)),
+        None => Box::new(io::stdin().lock()),
+    })
+}
+
+fn parse_coord(line: &str) -> Result<Coord<f64>, String> {
+    let Some((x, y)) = line.split_once(',') else {
+        return Err(format!("expected `longitude,latitude`, got `{}`", line));
+    };
+    let parse = |value: &str| {
+        value
+            .trim()
+            .parse::<f64>()
+            .map_err(|e| format!("invalid coordinate `{}`: {}", value.trim(), e))
+    };
+    Ok(Coord {
+        x: parse(x)?,
+        y: parse(y)?,
+    })
+}
+
+fn read_lines(input: impl BufRead, skip_header: bool) -> Result<Vec<Coord<f64>>, Box<dyn Error>> {
+    let mut coords = vec![];
+    for line in input.lines().skip(usize::from(skip_header)) {
+        let line = line?;
+        let line = line.trim();
+        if !line.is_empty() {
+            coords.push(parse_coord(line)?);
+        }
+    }
+    Ok(coords)
+}
+
+fn read_geojson(input: impl BufRead) -> Result<Vec<Coord<f64>>, Box<dyn Error>> {
+    let value: Value = serde_json::from_reader(input)?;
+    let geometry = match value["type"].as_str() {
+        Some("Feature") => &value["geometry"],
+        _ => &value,
+    };
+    if geometry["type"] != "LineString" {
+        return Err("expected a GeoJSON LineString geometry or Feature".into());
+    }
+    let coords: Vec<[f64; 2]> = serde_json::from_value(geometry["coordinates"].clone())?;
+    Ok(coords.into_iter().map(|[x, y]| Coord { x, y }).collect())
+}
+
+fn read_coords(format: Format, input: impl BufRead) -> Result<Vec<Coord<f64>>, Box<dyn Error>> {
+    match format {
+        Format::Text => read_lines(input, false),
+        Format::Csv => read_lines(input, true),
+        Format::Geojson => read_geojson(input),
+        Format::Wkt => {
+            let line_string = LineString::try_from_wkt_str(&io::read_to_string(input)?)?;
+            Ok(line_string.0)
+        }
+    }
+}
+
+fn write_coords(
+    format: Format,
+    line_string: LineString<f64>,
+    mut output: impl Write,
+) -> io::Result<()> {
+    match format {
+        Format::Text | Format::Csv => {
+            if let Format::Csv = format {
+                writeln!(output, "longitude,latitude")?;
+            }
+            for coord in line_string {
+                writeln!(output, "{},{}", coord.x, coord.y)?;
+            }
+        }
+        Format::Geojson => {
+            let coords: Vec<[f64; 2]> = line_string.coords().map(|c| [c.x, c.y]).collect();
+            let geometry = json!({ "type": "LineString", "coordinates": coords });
+            writeln!(output, "{}", geometry)?;
+        }
+        Format::Wkt => writeln!(output, "{}", line_string.wkt_string())?,
+    }
+    Ok(())
+}
+
+/// The number of lines or records converted in parallel at a time, bounding memory use
+const BATCH_SIZE: usize = 16 * 1024;
+
+fn format_line(format: Format, line_string: &LineString<f64>) -> Result<String, String> {
+    match format {
+        Format::Text => Ok(line_string
+            .coords()
+            .map(|c| format!("{},{}", c.x, c.y))
+            .collect::<Vec<_>>()
+            .join(" ")),
+        Format::Csv => Err("csv is not a single-line format; use --column instead".into()),
+        Format::Geojson => {
+            let coords: Vec<[f64; 2]> = line_string.coords().map(|c| [c.x, c.y]).collect();
+            Ok(json!({ "type": "LineString", "coordinates": coords }).to_string())
+        }
+        Format::Wkt => Ok(line_string.wkt_string()),
+    }
+}
+
+fn parse_line(format: Format, line: &str) -> Result<Vec<Coord<f64>>, String> {
+    match format {
+        Format::Text => line.split_whitespace().map(parse_coord).collect(),
+        Format::Csv => Err("csv is not a single-line format; use --column instead".into()),
+        Format::Geojson => read_geojson(line.as_bytes()).map_err(|e| e.to_string()),
+        Format::Wkt => LineString::try_from_wkt_str(line)
+            .map(|line_string| line_string.0)
+            .map_err(|e| e.to_string()),
+    }
+}
+
+fn convert(
+    command: &Command,
+    format: Format,
+    precision: u32,
+    item: &str,
+) -> Result<String, String> {
+    match command {
+        Command::Encode { .. } => {
+            let coords = parse_line(format, item)?;
+            encode_coordinates(coords, precision).map_err(|e| e.to_string())
+        }
+        Command::Decode { .. } => {
+            let line_string = decode_polyline(item, precision).map_err(|e| e.to_string())?;
+            format_line(format, &line_string)
+        }
+    }
+}
+
+fn run_lines(
+    input: impl BufRead,
+    convert: impl Fn(&str) -> Result<String, String> + Sync,
+    mut output: impl Write,
+) -> Result<(), Box<dyn Error>> {
+    let mut lines = input.lines().enumerate().peekable();
+    while lines.peek().is_some() {
+        let chunk = lines
+            .by_ref()
+            .take(BATCH_SIZE)
+            .map(|(idx, line)| line.map(|line| (idx, line)))
+            .collect::<io::Result<Vec<_>>>()?;
+        let converted = chunk
+            .par_iter()
+            .map(|(idx, line)| convert(line.trim()).map_err(|e| format!("line {}: {}", idx + 1, e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        for line in converted {
+            writeln!(output, "{}", line)?;
+        }
+    }
+    Ok(())
+}
+
+fn run_csv(
+    input: impl BufRead,
+    column: &str,
+    convert: impl Fn(&str) -> Result<String, String> + Sync,
+    output: impl Write,
+) -> Result<(), Box<dyn Error>> {
+    let mut reader = csv::Reader::from_reader(input);
+    let mut writer = csv::Writer::from_writer(output);
+    let headers = reader.headers()?.clone();
+    let Some(col) = headers.iter().position(|header| header == column) else {
+        return Err(format!("no column named `{}`", column).into());
+    };
+    writer.write_record(&headers)?;
+    let mut records = reader.records().peekable();
+    while records.peek().is_some() {
+        let chunk = records
+            .by_ref()
+            .take(BATCH_SIZE)
+            .collect::<Result<Vec<_>, _>>()?;
+        let converted = chunk
+            .par_iter()
+            .map(|record| {
+                let converted = convert(&record[col]).map_err(|e| {
+                    let line = record.position().map_or(0, |pos| pos.line());
+                    format!("line {}: {}", line, e)
+                })?;
+                Ok(record
+                    .iter()
+                    .enumerate()
+                    .map(|(i, field)| if i == col { &converted } else { field })
+                    .collect::<csv::StringRecord>())
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        for record in converted {
+            writer.write_record(&record)?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let mut output = BufWriter::new(io::stdout().lock());
+    if cli.batch {
+        let (Command::Encode { input } | Command::Decode { input }) = &cli.command;
+        let input = open(input.clone())?;
+        let convert = |item: &str| convert(&cli.command, cli.format, cli.precision, item);
+        match &cli.column {
+            Some(column) => run_csv(input, column, convert, &mut output)?,
+            None => run_lines(input, convert, &mut output)?,
+        }
+        output.flush()?;
+        return Ok(());
+    }
+    match cli.command {
+        Command::Encode { input } => {
+            let coords = read_coords(cli.format, open(input)?)?;
+            writeln!(output, "{}", encode_coordinates(coords, cli.precision)?)?;
+        }
+        Command::Decode { input } => {
+            let encoded = io::read_to_string(open(input)?)?;
+            let line_string = decode_polyline(encoded.trim(), cli.precision)?;
+            write_coords(cli.format, line_string, &mut output)?;
+        }
+    }
+    output.flush()?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("polyline: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coordinate_lines() {
+        let input = "-120.2,38.5\n\n -120.95, 40.7 \n";
+        assert_eq!(
+            read_coords(Format::Text, input.as_bytes()).unwrap(),
+            vec![
+                Coord { x: -120.2, y: 38.5 },
+                Coord {
+                    x: -120.95,
+                    y: 40.7
+                }
+            ]
+        );
+        assert_eq!(
+            parse_coord("1.0").unwrap_err(),
+            "expected `longitude,latitude`, got `1.0`"
+        );
+    }
+
+    #[test]
+    fn formats_roundtrip() {
+        let line_string: LineString<f64> = vec![[-120.2, 38.5], [-120.95, 40.7]].into();
+        for format in [Format::Text, Format::Csv, Format::Geojson, Format::Wkt] {
+            let mut output = vec![];
+            write_coords(format, line_string.clone(), &mut output).unwrap();
+            assert_eq!(read_coords(format, &output[..]).unwrap(), line_string.0);
+        }
+    }
+
+    #[test]
+    fn batch_lines() {
+        let input = "_ibE_seK_seK_seK\n_p~iF~ps|U_ulLnnqC_mqNvxq`@\n";
+        let decode = Command::Decode { input: None };
+        let mut output = vec![];
+        run_lines(
+            input.as_bytes(),
+            |item| convert(&decode, Format::Wkt, 5, item),
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "LINESTRING(2 1,4 3)\nLINESTRING(-120.2 38.5,-120.95 40.7,-126.453 43.252)\n"
+        );
+
+        let err = run_lines(
+            "_ibE_seK\nugh\n".as_bytes(),
+            |item| convert(&decode, Format::Text, 5, item),
+            io::sink(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2: no longitude to go with latitude at index: 0"
+        );
+    }
+
+    #[test]
+    fn batch_csv_column() {
+        let input = "id,geometry\n1,\"2,1 4,3\"\n";
+        let encode = Command::Encode { input: None };
+        let mut output = vec![];
+        run_csv(
+            input.as_bytes(),
+            "geometry",
+            |item| convert(&encode, Format::Text, 5, item),
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "id,geometry\n1,_ibE_seK_seK_seK\n"
+        );
+    }
+}
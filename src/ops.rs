@@ -0,0 +1,1765 @@
+//! Operations on encoded polylines which avoid fully decoding and re-encoding them
+
+use crate::errors::PolylineError;
+use crate::{
+    decode_next, encode, encoded_value_len, factor, scale_coord, zigzag, Decoder, DECODING_TABLE,
+    INVALID_CHUNK, MAX_LATITUDE, MAX_LONGITUDE, MAX_SHIFT, MIN_LATITUDE, MIN_LONGITUDE,
+};
+use geo_types::{Coord, LineString, Rect};
+use std::ops::{Bound, RangeBounds};
+
+/// Where a coordinate's values lie in a polyline, and its scaled position
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    start: usize,
+    /// The start of the longitude value
+    lon_start: usize,
+    end: usize,
+    scaled: Coord<i64>,
+    /// The change encoded by the longitude value
+    lon_delta: i64,
+}
+
+/// Iterates over the coordinates of a polyline without converting them to floats.
+///
+/// Error indices are offset by `offset`, and iteration stops after the first error.
+struct Spans<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    offset: usize,
+    scaled: Coord<i64>,
+    failed: bool,
+}
+
+impl<'a> Spans<'a> {
+    fn new(bytes: &'a [u8], offset: usize) -> Self {
+        Spans {
+            bytes,
+            pos: 0,
+            offset,
+            scaled: Coord { x: 0, y: 0 },
+            failed: false,
+        }
+    }
+
+    fn next_span(&mut self) -> Result<Span, PolylineError> {
+        let offset = self.offset;
+        let offset_err = |err| match err {
+            PolylineError::DecodeError { idx } => PolylineError::DecodeError { idx: idx + offset },
+            err => err,
+        };
+
+        let start = self.pos;
+        let lat_delta = decode_next(self.bytes, &mut self.pos).map_err(offset_err)?;
+        // a sum which overflows is out of bounds at any precision, though spans don't know the
+        // precision to report the coordinate at
+        self.scaled.y = self.scaled.y.checked_add(lat_delta).ok_or_else(|| {
+            PolylineError::LatitudeCoordError {
+                coord: self.scaled.y as f64 + lat_delta as f64,
+                idx: start + offset,
+            }
+        })?;
+        if self.pos == self.bytes.len() {
+            return Err(PolylineError::NoLongError {
+                idx: start + offset,
+            });
+        }
+        let lon_start = self.pos;
+        let lon_delta = decode_next(self.bytes, &mut self.pos).map_err(offset_err)?;
+        self.scaled.x = self.scaled.x.checked_add(lon_delta).ok_or_else(|| {
+            PolylineError::LongitudeCoordError {
+                coord: self.scaled.x as f64 + lon_delta as f64,
+                idx: lon_start + offset,
+            }
+        })?;
+        Ok(Span {
+            start,
+            lon_start,
+            end: self.pos,
+            scaled: self.scaled,
+            lon_delta,
+        })
+    }
+}
+
+impl<'a> Spans<'a> {
+    /// Checks each coordinate against the bounds of `precision`, reporting errors as the decoder
+    /// does.
+    fn checked(self, precision: u32) -> impl Iterator<Item = Result<Span, PolylineError>> + 'a {
+        let offset = self.offset;
+        self.map(move |span| {
+            let span = span?;
+            check_bounds(&span, precision).map_err(|err| match err {
+                PolylineError::LatitudeCoordError { coord, idx } => {
+                    PolylineError::LatitudeCoordError {
+                        coord,
+                        idx: idx + offset,
+                    }
+                }
+                PolylineError::LongitudeCoordError { coord, idx } => {
+                    PolylineError::LongitudeCoordError {
+                        coord,
+                        idx: idx + offset,
+                    }
+                }
+                err => err,
+            })?;
+            Ok(span)
+        })
+    }
+}
+
+impl Iterator for Spans<'_> {
+    type Item = Result<Span, PolylineError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.pos == self.bytes.len() {
+            return None;
+        }
+        let span = self.next_span();
+        self.failed = span.is_err();
+        Some(span)
+    }
+}
+
+/// Whether a polyline ends with a complete value.
+///
+/// The decoder accepts an unterminated last value, which mustn't be followed by more values.
+fn is_terminated(bytes: &[u8]) -> bool {
+    bytes
+        .last()
+        .is_none_or(|&byte| DECODING_TABLE[byte as usize] < 0x20)
+}
+
+/// Copies the values of the coordinates after `first` up to and including `last`, terminating
+/// the last value if it isn't.
+fn copy_rest(bytes: &[u8], first: &Span, last: &Span, output: &mut Vec<u8>) {
+    if last.end == first.end {
+        return;
+    }
+    if last.end < bytes.len() || is_terminated(bytes) {
+        output.extend_from_slice(&bytes[first.end..last.end]);
+    } else {
+        output.extend_from_slice(&bytes[first.end..last.lon_start]);
+        encode(last.lon_delta, output);
+    }
+}
+
+/// Joins encoded polylines end to end, as if their coordinates had been encoded together.
+///
+/// Only the first coordinate of each polyline after the first is re-encoded, relative to the end
+/// of the previous one; the rest is copied as it is. This is much cheaper than decoding the
+/// polylines and encoding their coordinates again.
+///
+/// Coordinates are checked against the bounds of `precision`, and error indices are relative to
+/// the start of the first polyline, as if they had been joined into a single string.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+/// use geo_types::line_string;
+///
+/// let first = polyline::encode_coordinates(line_string![(x: -120.2, y: 38.5)], 5).unwrap();
+/// let rest = line_string![(x: -120.95, y: 40.7), (x: -126.453, y: 43.252)];
+/// let rest = polyline::encode_coordinates(rest, 5).unwrap();
+///
+/// let joined = polyline::concat_polylines(&[&first, &rest], 5).unwrap();
+/// assert_eq!(joined, "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+/// ```
+pub fn concat_polylines(polylines: &[&str], precision: u32) -> Result<String, PolylineError> {
+    let total_len: usize = polylines.iter().map(|polyline| polyline.len()).sum();
+    let mut output = Vec::with_capacity(total_len);
+    let mut previous = Coord { x: 0, y: 0 };
+    let mut offset = 0;
+
+    for polyline in polylines {
+        let bytes = polyline.as_bytes();
+        let mut spans = Spans::new(bytes, offset).checked(precision);
+        if let Some(first) = spans.next().transpose()? {
+            let last = spans.try_fold(first, |_, span| span)?;
+            encode(first.scaled.y - previous.y, &mut output);
+            encode(first.scaled.x - previous.x, &mut output);
+            copy_rest(bytes, &first, &last, &mut output);
+            previous = last.scaled;
+        }
+        offset += bytes.len();
+    }
+
+    // SAFETY: the copied ranges start and end just after ASCII characters terminating values,
+    // so they're valid UTF-8, and encoded values are ASCII
+    Ok(unsafe { String::from_utf8_unchecked(output) })
+}
+
+/// Joins the encoded polylines of consecutive legs of a route end to end, dropping the first
+/// coordinate of each leg which repeats the last coordinate of the leg before it.
+///
+/// Like [`concat_polylines`], this only re-encodes the first coordinate kept from each leg.
+/// Coordinates are checked against the bounds of `precision`, and error indices are relative to
+/// the start of the first polyline, as if they had been joined into a single string.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// // the second leg starts where the first ends
+/// let joined = polyline::join_segments(&["_p~iF~ps|U_ulLnnqC", "_flwFn`faV_mqNvxq`@"], 5).unwrap();
+/// assert_eq!(joined, "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+/// ```
+pub fn join_segments(polylines: &[&str], precision: u32) -> Result<String, PolylineError> {
+    let total_len: usize = polylines.iter().map(|polyline| polyline.len()).sum();
+    let mut output = Vec::with_capacity(total_len);
+    let mut previous = None;
+    let mut offset = 0;
+
+    for polyline in polylines {
+        let bytes = polyline.as_bytes();
+        let mut spans = Spans::new(bytes, offset).checked(precision);
+        let mut first = spans.next().transpose()?;
+        if first.is_some_and(|first| Some(first.scaled) == previous) {
+            first = spans.next().transpose()?;
+        }
+        if let Some(first) = first {
+            let last = spans.try_fold(first, |_, span| span)?;
+            let start = previous.unwrap_or(Coord { x: 0, y: 0 });
+            encode(first.scaled.y - start.y, &mut output);
+            encode(first.scaled.x - start.x, &mut output);
+            copy_rest(bytes, &first, &last, &mut output);
+            previous = Some(last.scaled);
+        }
+        offset += bytes.len();
+    }
+
+    // SAFETY: the copied ranges start and end just after ASCII characters terminating values,
+    // so they're valid UTF-8, and encoded values are ASCII
+    Ok(unsafe { String::from_utf8_unchecked(output) })
+}
+
+/// Appends a coordinate to an encoded polyline.
+///
+/// Only the scaled position of the last coordinate is recovered from the polyline, without
+/// converting anything to floats, which makes this cheap enough for live tracking.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+/// use geo_types::coord;
+///
+/// let mut encoded = String::from("_p~iF~ps|U_ulLnnqC");
+/// polyline::append_coordinate(&mut encoded, coord! { x: -126.453, y: 43.252 }, 5).unwrap();
+/// assert_eq!(encoded, "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+/// ```
+pub fn append_coordinate(
+    encoded: &mut String,
+    coord: Coord<f64>,
+    precision: u32,
+) -> Result<(), PolylineError> {
+    let mut count = 0;
+    let mut last = None;
+    for span in Spans::new(encoded.as_bytes(), 0) {
+        last = Some(span?);
+        count += 1;
+    }
+    let scaled = scale_coord(count, coord, factor(precision))?;
+
+    let previous = last.map_or(Coord { x: 0, y: 0 }, |last| last.scaled);
+    // SAFETY: only ASCII is appended, after the end of a value
+    let bytes = unsafe { encoded.as_mut_vec() };
+    if let Some(last) = last.filter(|_| !is_terminated(bytes)) {
+        bytes.truncate(last.lon_start);
+        encode(last.lon_delta, bytes);
+    }
+    encode(scaled.y - previous.y, bytes);
+    encode(scaled.x - previous.x, bytes);
+    Ok(())
+}
+
+/// Shortens an encoded polyline to its first `n` coordinates.
+///
+/// This only finds where the `n`th coordinate ends, so it's cheap even for long polylines. Only
+/// the first `n` coordinates are checked for errors, including the bounds of `precision`, and the
+/// whole polyline is returned if it has no more than `n`.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// assert_eq!(polyline::truncate_points("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5, 2).unwrap(), "_p~iF~ps|U_ulLnnqC");
+/// ```
+pub fn truncate_points(polyline: &str, precision: u32, n: usize) -> Result<&str, PolylineError> {
+    let mut end = 0;
+    for span in Spans::new(polyline.as_bytes(), 0)
+        .checked(precision)
+        .take(n)
+    {
+        end = span?.end;
+    }
+    Ok(&polyline[..end])
+}
+
+/// Splits an encoded polyline into two at a coordinate index, without decoding it to floats.
+///
+/// The first polyline holds the coordinates before `idx`, and the second those from `idx`
+/// onwards, re-encoded to start from the coordinate itself. If `idx` is past the last coordinate,
+/// the second polyline is empty.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let (first, second) = polyline::split_at_point("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5, 1).unwrap();
+/// assert_eq!(first, "_p~iF~ps|U");
+/// assert_eq!(polyline::concat_polylines(&[&first, &second], 5).unwrap(), "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+/// ```
+pub fn split_at_point(
+    polyline: &str,
+    precision: u32,
+    idx: usize,
+) -> Result<(String, String), PolylineError> {
+    let bytes = polyline.as_bytes();
+    let mut split = 0;
+    let mut first = None;
+    let mut last = None;
+    for (i, span) in Spans::new(bytes, 0).checked(precision).enumerate() {
+        let span = span?;
+        if i < idx {
+            split = span.end;
+        } else if i == idx {
+            first = Some(span);
+        }
+        last = Some(span);
+    }
+
+    let second = match (first, last) {
+        (Some(first), Some(last)) => rebase(bytes, &first, &last),
+        _ => String::new(),
+    };
+    Ok((polyline[..split].to_string(), second))
+}
+
+/// Extracts the coordinates in `range` from an encoded polyline, without decoding it to floats.
+///
+/// The result is re-encoded to start from the first coordinate in the range. Only coordinates up
+/// to the end of the range are checked for errors, including the bounds of `precision`, and the
+/// range is clamped to the coordinates the polyline has.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let middle = polyline::slice_points("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5, 1..2).unwrap();
+/// assert_eq!(polyline::decode_polyline(&middle, 5).unwrap().0[0].y, 40.7);
+/// ```
+pub fn slice_points(
+    polyline: &str,
+    precision: u32,
+    range: impl RangeBounds<usize>,
+) -> Result<String, PolylineError> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.saturating_add(1),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => usize::MAX,
+    };
+
+    let bytes = polyline.as_bytes();
+    let mut first = None;
+    let mut last = None;
+    for (i, span) in Spans::new(bytes, 0)
+        .checked(precision)
+        .enumerate()
+        .take(end)
+    {
+        let span = span?;
+        if i == start {
+            first = Some(span);
+        }
+        last = Some(span);
+    }
+    Ok(match (first, last) {
+        (Some(first), Some(last)) => rebase(bytes, &first, &last),
+        _ => String::new(),
+    })
+}
+
+/// Encodes the coordinates from `first` to `last` as a polyline of their own.
+fn rebase(bytes: &[u8], first: &Span, last: &Span) -> String {
+    let mut output = Vec::new();
+    encode(first.scaled.y, &mut output);
+    encode(first.scaled.x, &mut output);
+    copy_rest(bytes, first, last, &mut output);
+    // SAFETY: see `concat_polylines`
+    unsafe { String::from_utf8_unchecked(output) }
+}
+
+/// Encodes coordinates as several polylines, each shorter than `max_chars`, for systems which
+/// limit the size of each field, such as SMS, query parameters or message queues.
+///
+/// Each polyline starts with the coordinate the previous one ended with, so together they trace
+/// the whole line. A segment which isn't shorter than `max_chars` on its own is reported as a
+/// [`PolylineError::ChunkLengthError`] at the index of the coordinate ending it.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+/// use geo_types::line_string;
+///
+/// let coords = line_string![(x: -120.2, y: 38.5), (x: -120.95, y: 40.7), (x: -126.453, y: 43.252)];
+/// let chunks = polyline::chunk_by_encoded_len(coords, 5, 20).unwrap();
+/// assert_eq!(chunks, ["_p~iF~ps|U_ulLnnqC", "_flwFn`faV_mqNvxq`@"]);
+/// ```
+pub fn chunk_by_encoded_len<C>(
+    coordinates: C,
+    precision: u32,
+    max_chars: usize,
+) -> Result<Vec<String>, PolylineError>
+where
+    C: IntoIterator<Item = Coord<f64>>,
+{
+    let factor = factor(precision);
+    let mut chunks = vec![];
+    let mut output = vec![];
+    let mut previous = Coord { x: 0, y: 0 };
+    let mut count = 0;
+    for (idx, coord) in coordinates.into_iter().enumerate() {
+        let scaled = scale_coord(idx, coord, factor)?;
+        let start = output.len();
+        encode(scaled.y - previous.y, &mut output);
+        encode(scaled.x - previous.x, &mut output);
+        if output.len() >= max_chars {
+            // a chunk with fewer coordinates already held this segment on its own
+            if count < 2 {
+                return Err(PolylineError::ChunkLengthError { idx });
+            }
+            output.truncate(start);
+            // SAFETY: encoded values are ASCII
+            chunks.push(unsafe { String::from_utf8_unchecked(output) });
+
+            output = Vec::with_capacity(max_chars);
+            encode(previous.y, &mut output);
+            encode(previous.x, &mut output);
+            encode(scaled.y - previous.y, &mut output);
+            encode(scaled.x - previous.x, &mut output);
+            if output.len() >= max_chars {
+                return Err(PolylineError::ChunkLengthError { idx });
+            }
+            count = 1;
+        }
+        previous = scaled;
+        count += 1;
+    }
+    if count > 0 {
+        // SAFETY: encoded values are ASCII
+        chunks.push(unsafe { String::from_utf8_unchecked(output) });
+    }
+    Ok(chunks)
+}
+
+/// Re-encodes a polyline at a different precision, without converting its coordinates to
+/// floats.
+///
+/// Coordinates are rounded half away from zero when reducing the precision, just as when
+/// encoding. Out-of-bounds coordinates are reported as they would be when decoding at `from`,
+/// and coordinates which can't be represented at `to` as encoding errors.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let polyline6 = polyline::convert_precision("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5, 6).unwrap();
+/// assert_eq!(polyline6, "_izlhA~rlgdF_{geC~ywl@_kwzCn`{nI");
+/// ```
+pub fn convert_precision(polyline: &str, from: u32, to: u32) -> Result<String, PolylineError> {
+    let bytes = polyline.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut previous = Coord { x: 0, y: 0 };
+    for (i, span) in Spans::new(bytes, 0).enumerate() {
+        let span = span?;
+        check_bounds(&span, from)?;
+        let (Some(x), Some(y)) = (
+            rescale(span.scaled.x, from, to),
+            rescale(span.scaled.y, from, to),
+        ) else {
+            return Err(PolylineError::CoordEncodingError {
+                coord: unscale(span.scaled, from),
+                idx: i,
+            });
+        };
+        encode(y - previous.y, &mut output);
+        encode(x - previous.x, &mut output);
+        previous = Coord { x, y };
+    }
+    // SAFETY: encoded values are ASCII
+    Ok(unsafe { String::from_utf8_unchecked(output) })
+}
+
+/// Re-encodes a polyline in its canonical form, without converting its coordinates to floats.
+///
+/// The canonical form is exactly what encoding the polyline's coordinates produces, with every
+/// value terminated and encoded in as few characters as possible. Polylines holding the same
+/// coordinates have the same canonical form, so once canonicalized they can be compared or
+/// deduplicated as strings. Canonicalizing a canonical polyline returns it unchanged.
+///
+/// Out-of-bounds coordinates are reported as they would be when decoding.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// // a redundant chunk in the last value
+/// let canonical = polyline::canonicalize("_p~iF~ps|U_ulLnnqC_mqNvxq``?", 5).unwrap();
+/// assert_eq!(canonical, "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+/// assert_eq!(polyline::canonicalize(&canonical, 5).unwrap(), canonical);
+/// ```
+pub fn canonicalize(polyline: &str, precision: u32) -> Result<String, PolylineError> {
+    convert_precision(polyline, precision, precision)
+}
+
+/// Decodes a polyline as [`decode_polyline`](crate::decode_polyline) does, but only if it's
+/// canonically encoded, for checking polylines from other sources before storing them.
+///
+/// Values encoded in more characters than needed, such as with redundant continuation chunks or
+/// an over-long zero, are reported as a [`PolylineError::NonCanonicalError`] at their index, as
+/// are characters above the printable range and an unterminated last value, which the lenient
+/// decoder accepts.
+///
+/// # Examples
+///
+/// ```
+/// use polyline::{self, errors::PolylineError};
+///
+/// assert!(polyline::decode_polyline_strict("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).is_ok());
+/// assert_eq!(
+///     polyline::decode_polyline_strict("_p~iF~ps|U_ulLnnqC_mqNvxq``?", 5).unwrap_err(),
+///     PolylineError::NonCanonicalError { idx: 22 }
+/// );
+/// ```
+pub fn decode_polyline_strict(
+    polyline: &str,
+    precision: u32,
+) -> Result<LineString<f64>, PolylineError> {
+    let bytes = polyline.as_bytes();
+    // the lenient decoder reads the low bits of characters above the printable range
+    if let Some(idx) = bytes
+        .iter()
+        .position(|&byte| (0x40..INVALID_CHUNK).contains(&DECODING_TABLE[byte as usize]))
+    {
+        return Err(PolylineError::NonCanonicalError { idx });
+    }
+    let mut coords = vec![];
+    let mut previous_y = 0;
+    for span in Spans::new(bytes, 0) {
+        let span = span?;
+        let lat_len = encoded_value_len(zigzag(span.scaled.y - previous_y));
+        if span.lon_start - span.start != lat_len {
+            return Err(PolylineError::NonCanonicalError { idx: span.start });
+        }
+        if span.end - span.lon_start != encoded_value_len(zigzag(span.lon_delta)) {
+            return Err(PolylineError::NonCanonicalError {
+                idx: span.lon_start,
+            });
+        }
+        check_bounds(&span, precision)?;
+        coords.push(unscale(span.scaled, precision));
+        previous_y = span.scaled.y;
+    }
+    if !is_terminated(bytes) {
+        return Err(PolylineError::NonCanonicalError {
+            idx: bytes.len() - 1,
+        });
+    }
+    Ok(LineString::new(coords))
+}
+
+/// Converts a scaled value to another precision, rounding half away from zero.
+fn rescale(scaled: i64, from: u32, to: u32) -> Option<i64> {
+    match 10i64.checked_pow(from.abs_diff(to)) {
+        Some(scale) if to >= from => scaled.checked_mul(scale),
+        Some(scale) => Some(div_round(scaled, scale)),
+        // reducing the precision this much rounds everything to zero
+        None if to < from => Some(0),
+        None => None,
+    }
+}
+
+fn unscale(scaled: Coord<i64>, precision: u32) -> Coord<f64> {
+    let factor = factor(precision);
+    Coord {
+        x: scaled.x as f64 / factor,
+        y: scaled.y as f64 / factor,
+    }
+}
+
+/// Checks the bounds of a coordinate, reporting errors as the decoder does.
+fn check_bounds(span: &Span, precision: u32) -> Result<(), PolylineError> {
+    let coord = unscale(span.scaled, precision);
+    if !(MIN_LATITUDE..=MAX_LATITUDE).contains(&coord.y) {
+        return Err(PolylineError::LatitudeCoordError {
+            coord: coord.y,
+            idx: span.start,
+        });
+    }
+    if !(MIN_LONGITUDE..=MAX_LONGITUDE).contains(&coord.x) {
+        return Err(PolylineError::LongitudeCoordError {
+            coord: coord.x,
+            idx: span.lon_start,
+        });
+    }
+    Ok(())
+}
+
+/// Divides, rounding half away from zero.
+fn div_round(n: i64, d: i64) -> i64 {
+    let quotient = n / d;
+    let remainder = n % d;
+    if remainder.unsigned_abs() * 2 >= d.unsigned_abs() {
+        quotient + n.signum()
+    } else {
+        quotient
+    }
+}
+
+/// Computes the bounding rectangle of an encoded polyline's coordinates, without collecting
+/// them.
+///
+/// Returns `None` for an empty polyline.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+/// use geo_types::coord;
+///
+/// let bounds = polyline::decoded_bounds("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap().unwrap();
+/// assert_eq!(bounds.min(), coord! { x: -126.453, y: 38.5 });
+/// assert_eq!(bounds.max(), coord! { x: -120.2, y: 43.252 });
+/// ```
+pub fn decoded_bounds(polyline: &str, precision: u32) -> Result<Option<Rect<f64>>, PolylineError> {
+    let mut coords = Decoder::new(polyline.as_bytes(), precision);
+    let Some(first) = coords.next().transpose()? else {
+        return Ok(None);
+    };
+    let (min, max) = coords.try_fold((first, first), |(min, max), coord| {
+        let coord = coord?;
+        Ok::<_, PolylineError>((
+            Coord {
+                x: min.x.min(coord.x),
+                y: min.y.min(coord.y),
+            },
+            Coord {
+                x: max.x.max(coord.x),
+                y: max.y.max(coord.y),
+            },
+        ))
+    })?;
+    Ok(Some(Rect::new(min, max)))
+}
+
+/// Counts the coordinates of an encoded polyline, checking its structure without decoding any
+/// values.
+///
+/// Invalid characters, overlong values and a missing longitude are reported as when decoding,
+/// but coordinates aren't checked against their bounds. See [`crate::estimate_point_count`]
+/// for a faster estimate which doesn't check anything.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// assert_eq!(polyline::point_count("_p~iF~ps|U_ulLnnqC_mqNvxq`@").unwrap(), 3);
+/// assert!(polyline::point_count("_p~iF~ps|U_ulLnnqC_mqN").is_err());
+/// ```
+pub fn point_count(polyline: &str) -> Result<usize, PolylineError> {
+    let mut values = 0;
+    let mut shift = 0;
+    let mut lat_start = 0;
+    for (idx, &byte) in polyline.as_bytes().iter().enumerate() {
+        if shift == 0 && values % 2 == 0 {
+            lat_start = idx;
+        }
+        let chunk = DECODING_TABLE[byte as usize];
+        if chunk == INVALID_CHUNK || shift > MAX_SHIFT {
+            return Err(PolylineError::DecodeError { idx });
+        }
+        shift += 5;
+        if chunk < 0x20 {
+            values += 1;
+            shift = 0;
+        }
+    }
+    // the decoder accepts an unterminated last value
+    if shift > 0 {
+        values += 1;
+    }
+    if values % 2 == 1 {
+        return Err(PolylineError::NoLongError { idx: lat_start });
+    }
+    Ok(values / 2)
+}
+
+/// Statistics about an encoded polyline, computed by [`analyze`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct PolylineStats {
+    /// The number of coordinates
+    pub point_count: usize,
+    /// The bounding rectangle of the coordinates, or `None` if there are none
+    pub bounds: Option<Rect<f64>>,
+    /// The average number of characters encoding each coordinate, or `0.0` if there are none
+    pub bytes_per_point: f64,
+    /// The smallest change in latitude or longitude between consecutive coordinates, in degrees
+    pub min_delta: Option<f64>,
+    /// The largest change in latitude or longitude between consecutive coordinates, in degrees
+    pub max_delta: Option<f64>,
+    /// Whether the polyline is exactly what encoding its coordinates would produce, with every
+    /// value terminated and encoded in as few characters as possible
+    pub canonical: bool,
+}
+
+/// Computes statistics about an encoded polyline, such as how compactly its coordinates are
+/// encoded, without collecting them.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let stats = polyline::analyze("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap();
+/// assert_eq!(stats.point_count, 3);
+/// assert_eq!(stats.bytes_per_point, 9.0);
+/// assert_eq!(stats.max_delta, Some(5.503));
+/// assert!(stats.canonical);
+///
+/// // the same coordinates, with a redundant chunk in the last value
+/// let stats = polyline::analyze("_p~iF~ps|U_ulLnnqC_mqNvxq``?", 5).unwrap();
+/// assert!(!stats.canonical);
+/// ```
+pub fn analyze(polyline: &str, precision: u32) -> Result<PolylineStats, PolylineError> {
+    let bytes = polyline.as_bytes();
+    let factor = factor(precision);
+    let mut point_count = 0;
+    let mut canonical = is_terminated(bytes);
+    let mut previous: Option<Coord<i64>> = None;
+    let mut min = Coord {
+        x: i64::MAX,
+        y: i64::MAX,
+    };
+    let mut max = Coord {
+        x: i64::MIN,
+        y: i64::MIN,
+    };
+    let mut deltas: Option<(u64, u64)> = None;
+    for span in Spans::new(bytes, 0) {
+        let span = span?;
+        check_bounds(&span, precision)?;
+        point_count += 1;
+
+        let lat_delta = span.scaled.y - previous.map_or(0, |previous| previous.y);
+        canonical &= span.lon_start - span.start == encoded_value_len(zigzag(lat_delta))
+            && span.end - span.lon_start == encoded_value_len(zigzag(span.lon_delta));
+        if previous.is_some() {
+            let (smallest, largest) = [lat_delta.unsigned_abs(), span.lon_delta.unsigned_abs()]
+                .into_iter()
+                .fold(
+                    deltas.unwrap_or((u64::MAX, 0)),
+                    |(smallest, largest), delta| (smallest.min(delta), largest.max(delta)),
+                );
+            deltas = Some((smallest, largest));
+        }
+        previous = Some(span.scaled);
+
+        min = Coord {
+            x: min.x.min(span.scaled.x),
+            y: min.y.min(span.scaled.y),
+        };
+        max = Coord {
+            x: max.x.max(span.scaled.x),
+            y: max.y.max(span.scaled.y),
+        };
+    }
+
+    Ok(PolylineStats {
+        point_count,
+        bounds: previous.map(|_| Rect::new(unscale(min, precision), unscale(max, precision))),
+        bytes_per_point: if point_count == 0 {
+            0.0
+        } else {
+            bytes.len() as f64 / point_count as f64
+        },
+        min_delta: deltas.map(|(smallest, _)| smallest as f64 / factor),
+        max_delta: deltas.map(|(_, largest)| largest as f64 / factor),
+        canonical,
+    })
+}
+
+/// Compares two polylines encoded at `precision` coordinate by coordinate, treating them as equal
+/// if each pair of latitudes and longitudes differs by at most `tolerance` units in the last
+/// place.
+///
+/// The comparison is done on the scaled integers, so differences in rounding can be tolerated
+/// exactly. Polylines with different numbers of coordinates are never equal, and errors are
+/// reported as when decoding, up to the end of the shorter polyline.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+/// use geo_types::line_string;
+///
+/// let a = line_string![(x: 2.0, y: 1.0), (x: 3.0, y: 4.0)];
+/// let a = polyline::encode_coordinates(a, 5).unwrap();
+/// let b = line_string![(x: 2.00001, y: 1.0), (x: 3.0, y: 3.99999)];
+/// let b = polyline::encode_coordinates(b, 5).unwrap();
+/// assert!(!polyline::approx_eq(&a, &b, 5, 0).unwrap());
+/// assert!(polyline::approx_eq(&a, &b, 5, 1).unwrap());
+/// ```
+pub fn approx_eq(a: &str, b: &str, precision: u32, tolerance: u64) -> Result<bool, PolylineError> {
+    let mut a = Spans::new(a.as_bytes(), 0);
+    let mut b = Spans::new(b.as_bytes(), 0);
+    loop {
+        let (a, b) = match (a.next().transpose()?, b.next().transpose()?) {
+            (Some(a), Some(b)) => (a, b),
+            (None, None) => return Ok(true),
+            _ => return Ok(false),
+        };
+        check_bounds(&a, precision)?;
+        check_bounds(&b, precision)?;
+        if a.scaled.x.abs_diff(b.scaled.x) > tolerance
+            || a.scaled.y.abs_diff(b.scaled.y) > tolerance
+        {
+            return Ok(false);
+        }
+    }
+}
+
+/// The difference between two encoded polylines, computed by [`diff`] and applied by
+/// [`apply_patch`].
+///
+/// Only the coordinates which changed are kept, so a patch to a long polyline which changed
+/// near its end is small enough to send to clients instead of the whole polyline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolylinePatch {
+    /// The index of the first coordinate which changed
+    pub start: usize,
+    /// The number of coordinates of the old polyline which were replaced, from `start`
+    pub removed: usize,
+    /// The coordinates which replaced them, encoded relative to the coordinate before `start`
+    pub inserted: String,
+}
+
+/// Computes the patch which turns the `old` polyline into the `new` one, without converting
+/// their coordinates to floats.
+///
+/// The coordinates the polylines share at their start and end are left out of the patch, and
+/// the rest of `new` is copied into it as it is, apart from its first coordinate.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let old = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+/// let new = "_p~iF~ps|U_ulLnnqC_mqNvxq`@_seK_seK";
+/// let patch = polyline::diff(old, new).unwrap();
+/// assert_eq!((patch.start, patch.removed, patch.inserted.as_str()), (3, 0, "_seK_seK"));
+/// assert_eq!(polyline::apply_patch(old, &patch).unwrap(), new);
+/// ```
+pub fn diff(old: &str, new: &str) -> Result<PolylinePatch, PolylineError> {
+    let old = Spans::new(old.as_bytes(), 0).collect::<Result<Vec<_>, _>>()?;
+    let bytes = new.as_bytes();
+    let new = Spans::new(bytes, 0).collect::<Result<Vec<_>, _>>()?;
+
+    let prefix = old
+        .iter()
+        .zip(&new)
+        .take_while(|(old, new)| old.scaled == new.scaled)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old.scaled == new.scaled)
+        .count();
+
+    let mut inserted = Vec::new();
+    let changed = &new[prefix..new.len() - suffix];
+    if let (Some(first), Some(last)) = (changed.first(), changed.last()) {
+        let previous = previous_scaled(&new, prefix);
+        encode(first.scaled.y - previous.y, &mut inserted);
+        encode(first.scaled.x - previous.x, &mut inserted);
+        copy_rest(bytes, first, last, &mut inserted);
+    }
+    Ok(PolylinePatch {
+        start: prefix,
+        removed: old.len() - prefix - suffix,
+        // SAFETY: see `concat_polylines`
+        inserted: unsafe { String::from_utf8_unchecked(inserted) },
+    })
+}
+
+/// Applies a patch computed by [`diff`] to the polyline it was computed from, without converting
+/// its coordinates to floats.
+///
+/// The replaced coordinates are clamped to those the polyline has. Errors in the inserted
+/// coordinates are reported at their index in [`PolylinePatch::inserted`].
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let old = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+/// let new = "_p~iF~ps|U_ulLnnqC_seK_seK";
+/// let patch = polyline::diff(old, new).unwrap();
+/// assert_eq!((patch.start, patch.removed), (2, 1));
+/// assert_eq!(polyline::apply_patch(old, &patch).unwrap(), new);
+/// ```
+pub fn apply_patch(old: &str, patch: &PolylinePatch) -> Result<String, PolylineError> {
+    let bytes = old.as_bytes();
+    let spans = Spans::new(bytes, 0).collect::<Result<Vec<_>, _>>()?;
+    let start = patch.start.min(spans.len());
+    let end = start.saturating_add(patch.removed).min(spans.len());
+    let mut output = Vec::with_capacity(bytes.len() + patch.inserted.len());
+
+    if let Some(last) = start.checked_sub(1).map(|i| &spans[i]) {
+        copy_spans(bytes, &spans[0], last, &mut output);
+    }
+    let mut previous = previous_scaled(&spans, start);
+    let inserted = patch.inserted.as_bytes();
+    let mut inserted_spans = Spans::new(inserted, 0);
+    if let Some(first) = inserted_spans.next().transpose()? {
+        let last = inserted_spans.try_fold(first, |_, span| span)?;
+        copy_spans(inserted, &first, &last, &mut output);
+        previous = previous + last.scaled;
+    }
+    if let (Some(first), Some(last)) = (spans.get(end), spans.last()) {
+        encode(first.scaled.y - previous.y, &mut output);
+        encode(first.scaled.x - previous.x, &mut output);
+        copy_rest(bytes, first, last, &mut output);
+    }
+    // SAFETY: see `concat_polylines`
+    Ok(unsafe { String::from_utf8_unchecked(output) })
+}
+
+/// The scaled position of the coordinate before `idx`, or the origin for the first coordinate.
+fn previous_scaled(spans: &[Span], idx: usize) -> Coord<i64> {
+    idx.checked_sub(1)
+        .map_or(Coord { x: 0, y: 0 }, |i| spans[i].scaled)
+}
+
+/// Copies the values of the coordinates from `first` up to and including `last`, terminating
+/// the last value if it isn't.
+fn copy_spans(bytes: &[u8], first: &Span, last: &Span, output: &mut Vec<u8>) {
+    if last.end < bytes.len() || is_terminated(bytes) {
+        output.extend_from_slice(&bytes[first.start..last.end]);
+    } else {
+        output.extend_from_slice(&bytes[first.start..last.lon_start]);
+        encode(last.lon_delta, output);
+    }
+}
+
+/// The mean radius of the Earth, in meters
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// The great-circle distance between two coordinates in meters, on a spherical Earth.
+fn haversine_distance(a: Coord<f64>, b: Coord<f64>) -> f64 {
+    let (lat_a, lat_b) = (a.y.to_radians(), b.y.to_radians());
+    let half_lat = (lat_b - lat_a) / 2.0;
+    let half_lon = (b.x - a.x).to_radians() / 2.0;
+    let h = half_lat.sin().powi(2) + lat_a.cos() * lat_b.cos() * half_lon.sin().powi(2);
+    2.0 * EARTH_RADIUS * h.sqrt().min(1.0).asin()
+}
+
+/// Finds the point `distance` meters along an encoded polyline, decoding it only as far as the
+/// point.
+///
+/// Distances are measured along great circles, while the point is interpolated linearly between
+/// the coordinates on either side of it, which is indistinguishable for segments of the lengths
+/// found in routes. Distances beyond either end are clamped to it, and `None` is returned for
+/// an empty polyline. Only the coordinates up to the point are checked for errors.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// // from the equator to 1° north
+/// let point = polyline::point_at_distance("??_ibE?", 5, 55_597.5).unwrap().unwrap();
+/// assert!((point.y - 0.5).abs() < 1e-6);
+/// ```
+pub fn point_at_distance(
+    polyline: &str,
+    precision: u32,
+    distance: f64,
+) -> Result<Option<Coord<f64>>, PolylineError> {
+    let mut coords = Decoder::new(polyline.as_bytes(), precision);
+    let Some(first) = coords.next().transpose()? else {
+        return Ok(None);
+    };
+    point_along(first, segments(first, coords), distance).map(Some)
+}
+
+/// Finds the point a `fraction` of the way along an encoded polyline, from `0.0` at its start to
+/// `1.0` at its end.
+///
+/// The polyline is scanned twice without allocating, once to measure its length and again up to
+/// the point, which is found as [`point_at_distance`] finds it.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let point = polyline::point_at_fraction("??_ibE?_ibE?", 5, 0.75).unwrap().unwrap();
+/// assert!((point.y - 1.5).abs() < 1e-6);
+/// ```
+pub fn point_at_fraction(
+    polyline: &str,
+    precision: u32,
+    fraction: f64,
+) -> Result<Option<Coord<f64>>, PolylineError> {
+    let mut coords = span_coords(polyline, precision);
+    let Some(first) = coords.next().transpose()? else {
+        return Ok(None);
+    };
+    let length = segments(first, coords).try_fold(0.0, |length, segment| {
+        Ok::<_, PolylineError>(length + segment?.1)
+    })?;
+    let distance = length * fraction.clamp(0.0, 1.0);
+    let coords = span_coords(polyline, precision).skip(1);
+    point_along(first, segments(first, coords), distance).map(Some)
+}
+
+/// The coordinates of a polyline, unscaled from its spans.
+fn span_coords(
+    polyline: &str,
+    precision: u32,
+) -> impl Iterator<Item = Result<Coord<f64>, PolylineError>> + '_ {
+    Spans::new(polyline.as_bytes(), 0)
+        .checked(precision)
+        .map(move |span| span.map(|span| unscale(span.scaled, precision)))
+}
+
+/// Pairs each coordinate after `first` with the length of the segment ending at it.
+fn segments(
+    first: Coord<f64>,
+    coords: impl Iterator<Item = Result<Coord<f64>, PolylineError>>,
+) -> impl Iterator<Item = Result<(Coord<f64>, f64), PolylineError>> {
+    let mut previous = first;
+    coords.map(move |coord| {
+        let coord = coord?;
+        let length = haversine_distance(previous, coord);
+        previous = coord;
+        Ok((coord, length))
+    })
+}
+
+/// Finds the point `distance` meters along the segments from `first`, clamped to the ends.
+fn point_along(
+    first: Coord<f64>,
+    segments: impl Iterator<Item = Result<(Coord<f64>, f64), PolylineError>>,
+    distance: f64,
+) -> Result<Coord<f64>, PolylineError> {
+    let mut previous = first;
+    let mut travelled = 0.0;
+    for segment in segments {
+        let (coord, length) = segment?;
+        if travelled + length > distance {
+            let t = ((distance - travelled) / length).max(0.0);
+            return Ok(previous + (coord - previous) * t);
+        }
+        travelled += length;
+        previous = coord;
+    }
+    Ok(previous)
+}
+
+/// The error encoding coordinates at a precision introduces, computed by
+/// [`quantization_report`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct QuantizationReport {
+    /// The largest distance between a coordinate and its encoded position, in degrees
+    pub max_error_degrees: f64,
+    /// The mean distance between the coordinates and their encoded positions, in degrees
+    pub mean_error_degrees: f64,
+    /// The largest distance between a coordinate and its encoded position, in meters
+    pub max_error_meters: f64,
+    /// The mean distance between the coordinates and their encoded positions, in meters
+    pub mean_error_meters: f64,
+}
+
+/// Measures how far encoding coordinates at `precision` moves them, without encoding them.
+///
+/// Each coordinate is compared with the position it decodes to. Distances in degrees treat
+/// latitude and longitude as planar, while distances in meters are along great circles of a
+/// spherical Earth. The errors are `0.0` if there are no coordinates.
+///
+/// # Examples
+///
+/// Finding the lowest precision which keeps every coordinate within a meter:
+///
+/// ```
+/// use polyline;
+/// use geo_types::line_string;
+///
+/// let coords = line_string![(x: -120.2041, y: 38.5032), (x: -120.95217, y: 40.7411)];
+/// let precision = (0..=10)
+///     .find(|&precision| {
+///         let report = polyline::quantization_report(coords.clone(), precision).unwrap();
+///         report.max_error_meters <= 1.0
+///     })
+///     .unwrap();
+/// assert_eq!(precision, 5);
+/// ```
+pub fn quantization_report<C>(
+    coordinates: C,
+    precision: u32,
+) -> Result<QuantizationReport, PolylineError>
+where
+    C: IntoIterator<Item = Coord<f64>>,
+{
+    let factor = factor(precision);
+    let mut count = 0;
+    let mut report = QuantizationReport {
+        max_error_degrees: 0.0,
+        mean_error_degrees: 0.0,
+        max_error_meters: 0.0,
+        mean_error_meters: 0.0,
+    };
+    for (i, coord) in coordinates.into_iter().enumerate() {
+        let encoded = unscale(scale_coord(i, coord, factor)?, precision);
+        let degrees = (encoded.x - coord.x).hypot(encoded.y - coord.y);
+        let meters = haversine_distance(coord, encoded);
+        report.max_error_degrees = report.max_error_degrees.max(degrees);
+        report.max_error_meters = report.max_error_meters.max(meters);
+        report.mean_error_degrees += degrees;
+        report.mean_error_meters += meters;
+        count += 1;
+    }
+    if count > 0 {
+        report.mean_error_degrees /= count as f64;
+        report.mean_error_meters /= count as f64;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        analyze, append_coordinate, apply_patch, approx_eq, canonicalize, chunk_by_encoded_len,
+        concat_polylines, convert_precision, decode_polyline_strict, decoded_bounds, diff,
+        haversine_distance, join_segments, point_at_distance, point_at_fraction, point_count,
+        quantization_report, slice_points, split_at_point, truncate_points, PolylinePatch,
+    };
+    use crate::errors::PolylineError;
+    use crate::{decode_polyline, encode_coordinates};
+    use geo_types::{Coord, LineString};
+
+    #[test]
+    fn concat() {
+        let first = LineString::from(vec![[-120.2, 38.5], [-120.95, 40.7]]);
+        let second = LineString::from(vec![[-126.453, 43.252]]);
+        let third = LineString::from(vec![[2.0, 1.0], [4.0, 3.0], [4.0, 3.0]]);
+        let pieces: Vec<_> = [&first, &second, &third]
+            .iter()
+            .map(|line| encode_coordinates(line.coords().copied(), 5).unwrap())
+            .collect();
+        let joined: Vec<_> = [first, second, third]
+            .into_iter()
+            .flat_map(|line| line.0)
+            .collect();
+
+        let concatenated = concat_polylines(&[&pieces[0], "", &pieces[1], &pieces[2]], 5).unwrap();
+        assert_eq!(concatenated, encode_coordinates(joined, 5).unwrap());
+        assert_eq!(concat_polylines(&[], 5).unwrap(), "");
+    }
+
+    #[test]
+    fn unterminated() {
+        // the last longitude is missing its terminating character
+        let unterminated = "_p~iF~ps|U_ulLnnqC_mqNvxq`";
+        let expected = [
+            decode_polyline(unterminated, 5).unwrap().0,
+            decode_polyline("_ibE_seK", 5).unwrap().0,
+        ]
+        .concat();
+        let concatenated = concat_polylines(&[unterminated, "_ibE_seK"], 5).unwrap();
+        assert_eq!(decode_polyline(&concatenated, 5).unwrap().0, expected);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            concat_polylines(&["_ibE_seK", "_ibE_seK_seK"], 5).unwrap_err(),
+            PolylineError::NoLongError { idx: 16 }
+        );
+        assert_eq!(
+            concat_polylines(&["_ibE_seK", "_ibE_s!K"], 5).unwrap_err(),
+            PolylineError::DecodeError { idx: 14 }
+        );
+
+        // coordinates are checked against the bounds of the precision
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let err = decode_polyline(polyline, 4).unwrap_err();
+        assert!(matches!(
+            err,
+            PolylineError::LatitudeCoordError { idx: 0, .. }
+        ));
+        assert_eq!(truncate_points(polyline, 4, 1).unwrap_err(), err);
+        assert_eq!(split_at_point(polyline, 4, 2).unwrap_err(), err);
+        assert_eq!(slice_points(polyline, 4, 1..).unwrap_err(), err);
+        assert!(matches!(
+            concat_polylines(&["_ibE_seK", polyline], 4).unwrap_err(),
+            PolylineError::LatitudeCoordError { idx: 8, .. }
+        ));
+
+        // the latitudes of values of maximum length overflow when summed
+        let overflowing = "~~~~~~~~~~~^".repeat(64);
+        assert!(matches!(
+            concat_polylines(&["_ibE_seK", &overflowing], 5).unwrap_err(),
+            PolylineError::LatitudeCoordError { idx: 8, .. }
+        ));
+        assert!(truncate_points(&overflowing, 5, 32).is_err());
+        assert!(diff(&overflowing, "").is_err());
+    }
+
+    #[test]
+    fn append() {
+        let coords = LineString::from(vec![[-120.2, 38.5], [-120.95, 40.7], [-126.453, 43.252]]);
+        let mut encoded = String::new();
+        for &coord in &coords.0 {
+            append_coordinate(&mut encoded, coord, 5).unwrap();
+        }
+        assert_eq!(encoded, encode_coordinates(coords.clone(), 5).unwrap());
+
+        let mut unterminated = String::from("_p~iF~ps|U_ulLnnqC_mqNvxq`");
+        let mut expected = decode_polyline(&unterminated, 5).unwrap();
+        append_coordinate(&mut unterminated, coords[0], 5).unwrap();
+        expected.0.push(coords[0]);
+        assert_eq!(decode_polyline(&unterminated, 5).unwrap(), expected);
+
+        let mut encoded = String::from("_ibE_seK");
+        let out_of_bounds = geo_types::coord! { x: 200.0, y: 0.0 };
+        assert_eq!(
+            append_coordinate(&mut encoded, out_of_bounds, 5).unwrap_err(),
+            PolylineError::LongitudeCoordError {
+                coord: 200.0,
+                idx: 1
+            }
+        );
+        assert_eq!(encoded, "_ibE_seK");
+    }
+
+    #[test]
+    fn truncate() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let coords = decode_polyline(polyline, 5).unwrap().0;
+        for n in 0..=coords.len() {
+            let truncated = truncate_points(polyline, 5, n).unwrap();
+            assert_eq!(decode_polyline(truncated, 5).unwrap().0, coords[..n]);
+        }
+        assert_eq!(truncate_points(polyline, 5, 10).unwrap(), polyline);
+        // errors after the first n coordinates aren't found
+        assert_eq!(truncate_points("_ibE_seK_seK", 5, 1).unwrap(), "_ibE_seK");
+        assert_eq!(
+            truncate_points("_ibE_seK_seK", 5, 2).unwrap_err(),
+            PolylineError::NoLongError { idx: 8 }
+        );
+    }
+
+    #[test]
+    fn split() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let coords = decode_polyline(polyline, 5).unwrap().0;
+        for idx in 0..=coords.len() + 1 {
+            let (first, second) = split_at_point(polyline, 5, idx).unwrap();
+            let idx = idx.min(coords.len());
+            assert_eq!(decode_polyline(&first, 5).unwrap().0, coords[..idx]);
+            assert_eq!(decode_polyline(&second, 5).unwrap().0, coords[idx..]);
+        }
+        assert_eq!(
+            split_at_point("_ibE_seK_seK", 5, 1).unwrap_err(),
+            PolylineError::NoLongError { idx: 8 }
+        );
+    }
+
+    #[test]
+    fn slice() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let coords = decode_polyline(polyline, 5).unwrap().0;
+        for start in 0..=coords.len() {
+            for end in start..=coords.len() {
+                let sliced = slice_points(polyline, 5, start..end).unwrap();
+                assert_eq!(decode_polyline(&sliced, 5).unwrap().0, coords[start..end]);
+            }
+        }
+        assert_eq!(slice_points(polyline, 5, ..).unwrap(), polyline);
+        assert_eq!(
+            slice_points(polyline, 5, 2..10).unwrap(),
+            slice_points(polyline, 5, 2..).unwrap()
+        );
+        assert_eq!(slice_points(polyline, 5, 5..).unwrap(), "");
+        // errors after the end of the range aren't found
+        assert_eq!(slice_points("_ibE_seK_seK", 5, ..1).unwrap(), "_ibE_seK");
+    }
+
+    #[test]
+    fn precision() {
+        let polyline5 = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let polyline6 = "_izlhA~rlgdF_{geC~ywl@_kwzCn`{nI";
+        assert_eq!(convert_precision(polyline5, 5, 6).unwrap(), polyline6);
+        assert_eq!(convert_precision(polyline6, 6, 5).unwrap(), polyline5);
+        assert_eq!(convert_precision(polyline5, 5, 5).unwrap(), polyline5);
+
+        // rounds the absolute coordinates half away from zero
+        let coords = LineString::from(vec![[0.000015, -0.000015], [0.000024, -0.000025]]);
+        let converted = convert_precision(&encode_coordinates(coords, 6).unwrap(), 6, 5).unwrap();
+        assert_eq!(
+            decode_polyline(&converted, 5).unwrap(),
+            LineString::from(vec![[0.00002, -0.00002], [0.00002, -0.00003]])
+        );
+
+        assert_eq!(
+            convert_precision("_ibE_seK", 5, 30).unwrap_err(),
+            PolylineError::CoordEncodingError {
+                coord: geo_types::coord! { x: 2.0, y: 1.0 },
+                idx: 0
+            }
+        );
+        assert_eq!(convert_precision("_ibE_seK", 5, 0).unwrap(), "AC");
+        // bounds are checked at the original precision
+        assert_eq!(
+            convert_precision(polyline5, 4, 5).unwrap_err(),
+            PolylineError::LatitudeCoordError {
+                coord: 385.0,
+                idx: 0
+            }
+        );
+    }
+
+    #[test]
+    fn canonical() {
+        for polyline in [
+            "_p~iF~ps|U_ulLnnqC_mqNvxq`@",
+            // redundant chunks
+            "_p~iF~ps|U_ulLnnqC_mqNvxq``?",
+            "_?_?",
+            // an unterminated last value
+            "_p~iF~ps|U_ulLnnqC_mqNvxq`",
+            "",
+        ] {
+            let canonical = canonicalize(polyline, 5).unwrap();
+            let coords = decode_polyline(polyline, 5).unwrap();
+            assert_eq!(canonical, encode_coordinates(coords, 5).unwrap());
+            assert!(analyze(&canonical, 5).unwrap().canonical);
+            assert_eq!(canonicalize(&canonical, 5).unwrap(), canonical);
+        }
+        assert_eq!(canonicalize("_?_?", 5).unwrap(), "??");
+
+        // encoding what's been decoded gives back the same polyline
+        let coords = LineString::from(vec![
+            [0.1 + 0.2, -0.3],
+            [-120.95, 40.7],
+            [0.000015, -0.000015],
+            [179.999995, -89.999995],
+            [1e-12, -1e-12],
+        ]);
+        for precision in [0, 5, 6, 10] {
+            let encoded = encode_coordinates(coords.clone(), precision).unwrap();
+            let decoded = decode_polyline(&encoded, precision).unwrap();
+            assert_eq!(encode_coordinates(decoded, precision).unwrap(), encoded);
+            assert_eq!(canonicalize(&encoded, precision).unwrap(), encoded);
+        }
+
+        assert_eq!(
+            canonicalize("_cidP?", 4).unwrap_err(),
+            PolylineError::LatitudeCoordError {
+                coord: 900.0,
+                idx: 0
+            }
+        );
+    }
+
+    #[test]
+    fn strict() {
+        for polyline in ["_p~iF~ps|U_ulLnnqC_mqNvxq`@", "", "??", "_ibE_seK????"] {
+            assert_eq!(
+                decode_polyline_strict(polyline, 5).unwrap(),
+                decode_polyline(polyline, 5).unwrap()
+            );
+        }
+        for (polyline, idx) in [
+            // redundant continuation chunks
+            ("_p~iF~ps|U_ulLnnqC_mqNvxq``?", 22),
+            ("_ibE_se{?", 4),
+            // an over-long zero
+            ("_?_ibE", 0),
+            // an unterminated last value
+            ("_p~iF~ps|U_ulLnnqC_mqNvxq`", 25),
+            // above the printable range
+            ("??\x7f??", 2),
+        ] {
+            assert!(decode_polyline(polyline, 5).is_ok(), "{}", polyline);
+            assert_eq!(
+                decode_polyline_strict(polyline, 5).unwrap_err(),
+                PolylineError::NonCanonicalError { idx },
+                "{}",
+                polyline
+            );
+        }
+        assert_eq!(
+            decode_polyline_strict("_ibE!", 5).unwrap_err(),
+            PolylineError::DecodeError { idx: 4 }
+        );
+        assert_eq!(
+            decode_polyline_strict("_cidP?", 4).unwrap_err(),
+            PolylineError::LatitudeCoordError {
+                coord: 900.0,
+                idx: 0
+            }
+        );
+    }
+
+    #[test]
+    fn bounds() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        assert_eq!(
+            decoded_bounds(polyline, 5).unwrap(),
+            Some(geo_types::Rect::new(
+                geo_types::coord! { x: -120.2, y: 38.5 },
+                geo_types::coord! { x: -126.453, y: 43.252 },
+            ))
+        );
+        assert_eq!(decoded_bounds("", 5).unwrap(), None);
+        assert_eq!(
+            decoded_bounds("_ibE_seK_seK", 5).unwrap_err(),
+            PolylineError::NoLongError { idx: 8 }
+        );
+    }
+
+    #[test]
+    fn count() {
+        for polyline in [
+            "",
+            "_p~iF~ps|U_ulLnnqC_mqNvxq`@",
+            "_p~iF~ps|U_ulLnnqC_mqNvxq`",
+            "_ibE_seK_seK",
+            "_ibE_se!K",
+            "_________________",
+        ] {
+            let decoded: Result<Vec<_>, _> = crate::Decoder::new(polyline.as_bytes(), 5).collect();
+            assert_eq!(point_count(polyline), decoded.map(|coords| coords.len()));
+        }
+        // bounds aren't checked
+        let mut out_of_bounds = vec![];
+        crate::encode(9_100_000, &mut out_of_bounds);
+        crate::encode(0, &mut out_of_bounds);
+        let out_of_bounds = String::from_utf8(out_of_bounds).unwrap();
+        assert!(crate::decode_polyline(&out_of_bounds, 5).is_err());
+        assert_eq!(point_count(&out_of_bounds), Ok(1));
+    }
+
+    #[test]
+    fn stats() {
+        let stats = analyze("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap();
+        assert_eq!(stats.point_count, 3);
+        assert_eq!(
+            stats.bounds,
+            decoded_bounds("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap()
+        );
+        assert_eq!(stats.bytes_per_point, 9.0);
+        assert_eq!(stats.min_delta, Some(0.75));
+        assert_eq!(stats.max_delta, Some(5.503));
+        assert!(stats.canonical);
+
+        let stats = analyze("", 5).unwrap();
+        assert_eq!(stats.point_count, 0);
+        assert_eq!(stats.bounds, None);
+        assert_eq!(stats.bytes_per_point, 0.0);
+        assert_eq!(stats.min_delta, None);
+        assert!(stats.canonical);
+
+        let stats = analyze("_ibE_seK", 5).unwrap();
+        assert_eq!((stats.min_delta, stats.max_delta), (None, None));
+        assert!(stats.canonical);
+
+        // an unterminated last value, and a redundant chunk
+        assert!(!analyze("_p~iF~ps|U_ulLnnqC_mqNvxq`", 5).unwrap().canonical);
+        assert!(
+            !analyze("_p~iF~ps|U_ulLnnqC_mqNvxq``?", 5)
+                .unwrap()
+                .canonical
+        );
+        assert!(!analyze("_??", 5).unwrap().canonical);
+
+        assert_eq!(
+            analyze("_ibE_seK_seK", 5).unwrap_err(),
+            PolylineError::NoLongError { idx: 8 }
+        );
+    }
+
+    #[test]
+    fn approximately_equal() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        assert!(approx_eq(polyline, polyline, 5, 0).unwrap());
+        assert!(approx_eq("", "", 5, 0).unwrap());
+
+        // the same coordinates, with a redundant chunk
+        assert!(approx_eq(polyline, "_p~iF~ps|U_ulLnnqC_mqNvxq``?", 5, 0).unwrap());
+
+        // the latitude of the second coordinate is 2e-5 degrees further north
+        let nudged: LineString<f64> =
+            vec![[-120.2, 38.5], [-120.95, 40.70002], [-126.453, 43.252]].into();
+        let nudged = &encode_coordinates(nudged, 5).unwrap();
+        assert!(!approx_eq(polyline, nudged, 5, 1).unwrap());
+        assert!(approx_eq(polyline, nudged, 5, 2).unwrap());
+
+        assert!(!approx_eq(polyline, "_p~iF~ps|U_ulLnnqC", 5, 100).unwrap());
+        assert!(!approx_eq("", polyline, 5, 100).unwrap());
+        assert_eq!(
+            approx_eq("_ibE_seK_seK", "_ibE_seK_seK_seK", 5, 0).unwrap_err(),
+            PolylineError::NoLongError { idx: 8 }
+        );
+    }
+
+    #[test]
+    fn patch() {
+        let polylines = [
+            "",
+            "_ibE_seK",
+            "_p~iF~ps|U_ulLnnqC_mqNvxq`@",
+            "_p~iF~ps|U_ulLnnqC_mqNvxq`",
+            "_p~iF~ps|U_ulLnnqC_mqNvxq`@_seK_seK",
+            "_seK_seK_p~iF~ps|U_ulLnnqC_mqNvxq`@",
+            "_p~iF~ps|U_seK_seK_mqNvxq`@",
+            "_p~iF~ps|U_mqNvxq`@",
+            "_p~iF~ps|",
+        ];
+        for old in polylines {
+            for new in polylines {
+                let patch = diff(old, new).unwrap();
+                let patched = apply_patch(old, &patch).unwrap();
+                assert_eq!(
+                    decode_polyline(&patched, 5),
+                    decode_polyline(new, 5),
+                    "{old} -> {new}"
+                );
+            }
+        }
+
+        let old = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        assert_eq!(
+            diff(old, old).unwrap(),
+            PolylinePatch {
+                start: 3,
+                removed: 0,
+                inserted: String::new(),
+            }
+        );
+        // only the changed coordinate is kept
+        let new: LineString<f64> = vec![[-120.2, 38.5], [-121.0, 41.0], [-126.453, 43.252]].into();
+        let patch = diff(old, &encode_coordinates(new, 5).unwrap()).unwrap();
+        assert_eq!((patch.start, patch.removed), (1, 1));
+        assert_eq!(
+            patch.inserted,
+            encode_coordinates([geo_types::coord! { x: -0.8, y: 2.5 }], 5).unwrap()
+        );
+
+        assert_eq!(
+            diff(old, "_ibE_seK_seK").unwrap_err(),
+            PolylineError::NoLongError { idx: 8 }
+        );
+        let patch = PolylinePatch {
+            start: 1,
+            removed: 1,
+            inserted: "_seK_se!K".to_string(),
+        };
+        assert_eq!(
+            apply_patch(old, &patch).unwrap_err(),
+            PolylineError::DecodeError { idx: 7 }
+        );
+    }
+
+    #[test]
+    fn distances() {
+        // a degree along the equator and along a meridian
+        let equator = haversine_distance(Coord { x: 0.0, y: 0.0 }, Coord { x: 1.0, y: 0.0 });
+        assert!((equator - 111_195.08).abs() < 0.01);
+        let meridian = haversine_distance(Coord { x: 10.0, y: 0.0 }, Coord { x: 10.0, y: 1.0 });
+        assert!((meridian - equator).abs() < 1e-6);
+
+        // north 1°, then east 1°
+        let polyline = "??_ibE?".to_string() + "?_ibE";
+        for (distance, expected) in [
+            (-1.0, Coord { x: 0.0, y: 0.0 }),
+            (0.0, Coord { x: 0.0, y: 0.0 }),
+            (equator / 4.0, Coord { x: 0.0, y: 0.25 }),
+            (equator, Coord { x: 0.0, y: 1.0 }),
+            (equator * 1.5, Coord { x: 0.5, y: 1.0 }),
+            (equator * 3.0, Coord { x: 1.0, y: 1.0 }),
+        ] {
+            let point = point_at_distance(&polyline, 5, distance).unwrap().unwrap();
+            assert!((point.x - expected.x).abs() < 1e-4, "{distance}: {point:?}");
+            assert!((point.y - expected.y).abs() < 1e-9, "{distance}: {point:?}");
+        }
+        let point = point_at_fraction(&polyline, 5, 0.25).unwrap().unwrap();
+        assert!((point.y - 0.5).abs() < 1e-4);
+        assert_eq!(
+            point_at_fraction(&polyline, 5, 2.0).unwrap(),
+            Some(Coord { x: 1.0, y: 1.0 })
+        );
+
+        assert_eq!(point_at_distance("", 5, 10.0).unwrap(), None);
+        assert_eq!(
+            point_at_fraction("_ibE_seK", 5, 0.5).unwrap(),
+            Some(Coord { x: 2.0, y: 1.0 })
+        );
+        // the end isn't reached, so the error isn't either
+        assert!(point_at_distance("??_ibE?_ibE", 5, 10.0).is_ok());
+        assert_eq!(
+            point_at_fraction("??_ibE?_ibE", 5, 0.5).unwrap_err(),
+            PolylineError::NoLongError { idx: 7 }
+        );
+    }
+
+    #[test]
+    fn quantization() {
+        let coords = [Coord { x: 0.0, y: 0.0 }, Coord { x: 0.4, y: 0.3 }];
+        let report = quantization_report(coords, 0).unwrap();
+        assert!((report.max_error_degrees - 0.5).abs() < 1e-12);
+        assert!((report.mean_error_degrees - 0.25).abs() < 1e-12);
+        let meters = haversine_distance(coords[1], Coord { x: 0.0, y: 0.0 });
+        assert_eq!(report.max_error_meters, meters);
+        assert_eq!(report.mean_error_meters, meters / 2.0);
+
+        let report = quantization_report(coords, 5).unwrap();
+        assert!(report.max_error_meters < 1e-6);
+        assert_eq!(quantization_report([], 5).unwrap().mean_error_meters, 0.0);
+        assert_eq!(
+            quantization_report([Coord { x: 0.0, y: 91.0 }], 5).unwrap_err(),
+            PolylineError::LatitudeCoordError {
+                coord: 91.0,
+                idx: 0
+            }
+        );
+    }
+
+    #[test]
+    fn chunks() {
+        let coords: Vec<_> = (0..100)
+            .map(|i| Coord {
+                x: i as f64 * 0.37,
+                y: (i % 7) as f64 * -1.3,
+            })
+            .collect();
+        let chunks = chunk_by_encoded_len(coords.clone(), 5, 40).unwrap();
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|chunk| chunk.len() < 40));
+
+        // the chunks overlap at their ends, and join up into the whole line
+        let mut joined = vec![];
+        for chunk in &chunks {
+            let decoded = decode_polyline(chunk, 5).unwrap().0;
+            if let Some(last) = joined.pop() {
+                assert_eq!(last, decoded[0]);
+            }
+            joined.extend(decoded);
+        }
+        let whole = encode_coordinates(coords.clone(), 5).unwrap();
+        assert_eq!(joined, decode_polyline(&whole, 5).unwrap().0);
+        assert_eq!(
+            chunk_by_encoded_len(coords.clone(), 5, whole.len() + 1).unwrap(),
+            [whole.as_str()]
+        );
+        // chunks are shorter than the limit, not as long as it
+        let chunks = chunk_by_encoded_len(coords.clone(), 5, whole.len()).unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().all(|chunk| chunk.len() < whole.len()));
+        assert_eq!(
+            chunk_by_encoded_len(vec![Coord { x: 0.0, y: 0.0 }], 5, 2).unwrap_err(),
+            PolylineError::ChunkLengthError { idx: 0 }
+        );
+        assert_eq!(
+            chunk_by_encoded_len(vec![Coord { x: 0.0, y: 0.0 }], 5, 3).unwrap(),
+            ["??"]
+        );
+
+        assert_eq!(
+            chunk_by_encoded_len(vec![], 5, 0).unwrap(),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            chunk_by_encoded_len(coords.clone(), 5, 1).unwrap_err(),
+            PolylineError::ChunkLengthError { idx: 0 }
+        );
+        // the first point fits, but not the segment after it
+        assert_eq!(
+            chunk_by_encoded_len(coords, 5, 6).unwrap_err(),
+            PolylineError::ChunkLengthError { idx: 1 }
+        );
+        assert_eq!(
+            chunk_by_encoded_len(vec![Coord { x: 181.0, y: 0.0 }], 5, 40).unwrap_err(),
+            PolylineError::LongitudeCoordError {
+                coord: 181.0,
+                idx: 0
+            }
+        );
+    }
+
+    #[test]
+    fn joins_segments() {
+        let legs = ["_p~iF~ps|U_ulLnnqC", "_flwFn`faV_mqNvxq`@"];
+        let joined = join_segments(&legs, 5).unwrap();
+        assert_eq!(joined, "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+        // legs which don't share an end are concatenated
+        assert_eq!(
+            join_segments(&["_p~iF~ps|U", "_flwFn`faV"], 5).unwrap(),
+            "_p~iF~ps|U_ulLnnqC"
+        );
+        // a leg of a single shared point adds nothing
+        assert_eq!(
+            join_segments(
+                &[
+                    "_p~iF~ps|U_ulLnnqC",
+                    "_flwFn`faV",
+                    "",
+                    "_flwFn`faV_mqNvxq`@"
+                ],
+                5
+            )
+            .unwrap(),
+            joined
+        );
+        // duplicates within a leg are kept
+        assert_eq!(join_segments(&["_ibE_seK??"], 5).unwrap(), "_ibE_seK??");
+        assert_eq!(join_segments(&[], 5).unwrap(), "");
+
+        assert_eq!(
+            join_segments(&["_p~iF~ps|U", "_ibE_seK_seK"], 5).unwrap_err(),
+            PolylineError::NoLongError { idx: 18 }
+        );
+        // out of bounds at precision 4
+        assert!(matches!(
+            join_segments(&["_p~iF~ps|U", "_p~iF~ps|U"], 4),
+            Err(PolylineError::LatitudeCoordError { idx: 0, .. })
+        ));
+        assert!(matches!(
+            join_segments(&["_ibE_seK", "_p~iF~ps|U"], 4),
+            Err(PolylineError::LatitudeCoordError { idx: 8, .. })
+        ));
+    }
+}
@@ -0,0 +1,69 @@
+//! Parallel batch encoding and decoding, enabled with the `rayon` feature
+
+use crate::errors::PolylineError;
+use crate::{decode_polyline, encode_coordinates};
+use geo_types::LineString;
+use rayon::prelude::*;
+
+/// Decodes many Google Encoded Polylines in parallel.
+///
+/// The results are in the same order as `polylines`; a polyline which fails to decode does not
+/// affect the others.
+///
+/// # Examples
+///
+/// ```
+/// let decoded = polyline::par_decode_polylines(&["_ibE_seK_seK_seK", "ugh"], 5);
+/// assert!(decoded[0].is_ok());
+/// assert!(decoded[1].is_err());
+/// ```
+pub fn par_decode_polylines(
+    polylines: &[&str],
+    precision: u32,
+) -> Vec<Result<LineString<f64>, PolylineError>> {
+    polylines
+        .par_iter()
+        .map(|polyline| decode_polyline(polyline, precision))
+        .collect()
+}
+
+/// Encodes many `LineString`s in parallel.
+///
+/// The results are in the same order as `line_strings`; a `LineString` which fails to encode
+/// does not affect the others.
+///
+/// # Examples
+///
+/// ```
+/// use geo_types::line_string;
+///
+/// let encoded = polyline::par_encode(&[line_string![(x: 2.0, y: 1.0), (x: 4.0, y: 3.0)]], 5);
+/// assert_eq!(encoded[0].as_deref(), Ok("_ibE_seK_seK_seK"));
+/// ```
+pub fn par_encode(
+    line_strings: &[LineString<f64>],
+    precision: u32,
+) -> Vec<Result<String, PolylineError>> {
+    line_strings
+        .par_iter()
+        .map(|line_string| encode_coordinates(line_string.coords().copied(), precision))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preserves_order_and_errors() {
+        let polylines = ["_ibE_seK_seK_seK", "ugh_ugh", "_p~iF~ps|U_ulLnnqC_mqNvxq`@"];
+        let decoded = par_decode_polylines(&polylines, 5);
+        assert_eq!(decoded.len(), 3);
+        assert!(decoded[1].is_err());
+
+        let line_strings: Vec<_> = decoded.into_iter().flatten().collect();
+        let encoded = par_encode(&line_strings, 5);
+        assert_eq!(encoded[0].as_deref(), Ok(polylines[0]));
+        assert_eq!(encoded[1].as_deref(), Ok(polylines[2]));
+    }
+}
@@ -0,0 +1,225 @@
+//! Encoding and decoding Polars columns of polylines, enabled with the `polars` feature.
+//!
+//! [`encode`] and [`decode`] are expressions, so they run inside lazy queries like any other
+//! Polars operation, while [`encode_series`] and [`decode_series`] convert eager `Series`.
+//! Lines are `List(List(Float64))` columns, each coordinate being a `[longitude, latitude]`
+//! list, and polylines are `String` columns; nulls in either are preserved.
+//!
+//! # Examples
+//!
+//! ```
+//! use polars::prelude::*;
+//! use polyline::polars_io;
+//!
+//! let df = df!("route" => ["_p~iF~ps|U_ulLnnqC", "_ibE_seK"]).unwrap();
+//! let decoded = df
+//!     .lazy()
+//!     .select([polars_io::decode(col("route"), 5).alias("line")])
+//!     .with_column(polars_io::encode(col("line"), 5).alias("route"))
+//!     .collect()
+//!     .unwrap();
+//! assert_eq!(decoded["route"].str().unwrap().get(0), Some("_p~iF~ps|U_ulLnnqC"));
+//! ```
+
+use crate::errors::PolylineError;
+use crate::{encode_coord, factor, Decoder};
+use geo_types::Coord;
+use polars::prelude::*;
+use polars_arrow::array::{Array, ListArray, PrimitiveArray};
+use polars_arrow::bitmap::MutableBitmap;
+use polars_arrow::datatypes::ArrowDataType;
+
+/// An expression encoding a column of lines as polylines at `precision`.
+///
+/// Columns of other numeric types are cast to `List(List(Float64))` first.
+pub fn encode(expr: Expr, precision: u32) -> Expr {
+    expr.map_with_fmt_str(
+        move |column| encode_series(column.as_materialized_series(), precision).map(Column::from),
+        |_, field| Ok(Field::new(field.name().clone(), DataType::String)),
+        "polyline.encode",
+    )
+}
+
+/// An expression decoding a column of polylines encoded at `precision` as lines.
+pub fn decode(expr: Expr, precision: u32) -> Expr {
+    expr.map_with_fmt_str(
+        move |column| decode_series(column.as_materialized_series(), precision).map(Column::from),
+        |_, field| Ok(Field::new(field.name().clone(), line_type())),
+        "polyline.decode",
+    )
+}
+
+fn line_type() -> DataType {
+    DataType::List(Box::new(DataType::List(Box::new(DataType::Float64))))
+}
+
+/// Encodes a `Series` of lines as polylines at `precision`.
+///
+/// The first line which fails to encode is reported as a `ComputeError` naming its row.
+pub fn encode_series(lines: &Series, precision: u32) -> PolarsResult<Series> {
+    let lines = lines.cast(&line_type())?;
+    let lines = lines.list()?;
+    let factor = factor(precision);
+    let mut builder = StringChunkedBuilder::new(lines.name().clone(), lines.len());
+    let mut output = vec![];
+    let mut row = 0;
+    for chunk in lines.downcast_iter() {
+        let coords = chunk
+            .values()
+            .as_any()
+            .downcast_ref::<ListArray<i64>>()
+            .expect("cast to a list of lists");
+        let values = coords
+            .values()
+            .as_any()
+            .downcast_ref::<PrimitiveArray<f64>>()
+            .expect("cast to a list of lists of Float64");
+        for i in 0..chunk.len() {
+            if chunk.is_null(i) {
+                builder.append_null();
+            } else {
+                output.clear();
+                let mut previous = Coord { x: 0, y: 0 };
+                let (start, end) = chunk.offsets().start_end(i);
+                for (idx, position) in (start..end).enumerate() {
+                    let (first, last) = coords.offsets().start_end(position);
+                    if coords.is_null(position)
+                        || last - first != 2
+                        || values.is_null(first)
+                        || values.is_null(first + 1)
+                    {
+                        polars_bail!(
+                            ComputeError: "row {}: coordinate {} isn't a [longitude, latitude] pair",
+                            row, idx
+                        );
+                    }
+                    let coord = Coord {
+                        x: values.value(first),
+                        y: values.value(first + 1),
+                    };
+                    encode_coord(idx, coord, factor, &mut previous, &mut output)
+                        .map_err(|err| compute_error(row, err))?;
+                }
+                // SAFETY: encoded values are ASCII
+                builder.append_value(unsafe { std::str::from_utf8_unchecked(&output) });
+            }
+            row += 1;
+        }
+    }
+    Ok(builder.finish().into_series())
+}
+
+/// Decodes a `Series` of polylines encoded at `precision` as lines.
+///
+/// Every coordinate is decoded into a single buffer, so the first polyline which fails to
+/// decode is reported as a `ComputeError` naming its row.
+pub fn decode_series(polylines: &Series, precision: u32) -> PolarsResult<Series> {
+    let polylines = polylines.str()?;
+    let mut values = vec![];
+    let mut line_offsets = Vec::with_capacity(polylines.len() + 1);
+    line_offsets.push(0);
+    let mut validity = MutableBitmap::with_capacity(polylines.len());
+    for (row, polyline) in polylines.iter().enumerate() {
+        if let Some(polyline) = polyline {
+            for coord in Decoder::new(polyline.as_bytes(), precision) {
+                let coord = coord.map_err(|err| compute_error(row, err))?;
+                values.push(coord.x);
+                values.push(coord.y);
+            }
+        }
+        line_offsets.push(values.len() as i64 / 2);
+        validity.push(polyline.is_some());
+    }
+
+    // every coordinate is a list of two values
+    let coord_offsets = (0..=values.len() as i64).step_by(2).collect::<Vec<_>>();
+    let coords = ListArray::<i64>::new(
+        ListArray::<i64>::default_datatype(ArrowDataType::Float64),
+        coord_offsets.try_into()?,
+        PrimitiveArray::from_vec(values).boxed(),
+        None,
+    );
+    let lines = ListArray::<i64>::new(
+        ListArray::<i64>::default_datatype(coords.dtype().clone()),
+        line_offsets.try_into()?,
+        coords.boxed(),
+        (polylines.null_count() > 0).then(|| validity.into()),
+    );
+    Ok(ListChunked::with_chunk(polylines.name().clone(), lines).into_series())
+}
+
+fn compute_error(row: usize, err: PolylineError) -> PolarsError {
+    PolarsError::ComputeError(format!("row {}: {}", row, err).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let polylines = Series::new(
+            "route".into(),
+            [
+                Some("_p~iF~ps|U_ulLnnqC_mqNvxq`@"),
+                None,
+                Some(""),
+                Some("_ibE_seK"),
+            ],
+        );
+        let lines = decode_series(&polylines, 5).unwrap();
+        assert_eq!(lines.dtype(), &line_type());
+        assert_eq!(lines.name(), "route");
+        assert_eq!(lines.null_count(), 1);
+        let last = lines.list().unwrap().get_as_series(3).unwrap();
+        let coord = last.list().unwrap().get_as_series(0).unwrap();
+        assert_eq!(coord.f64().unwrap().to_vec(), [Some(2.0), Some(1.0)]);
+
+        assert!(encode_series(&lines, 5).unwrap().equals_missing(&polylines));
+        // several chunks
+        let mut chunked = lines.slice(0, 2);
+        chunked.append(&lines.slice(2, 2)).unwrap();
+        assert!(encode_series(&chunked, 5)
+            .unwrap()
+            .equals_missing(&polylines));
+    }
+
+    #[test]
+    fn evaluates_expressions() {
+        let df = df!("line" => [Series::new("".into(), [Series::new("".into(), [2i32, 1])])])
+            .unwrap()
+            .lazy()
+            .select([encode(col("line"), 5)])
+            .with_column(decode(col("line"), 5).alias("decoded"))
+            .collect()
+            .unwrap();
+        assert_eq!(df["line"].str().unwrap().get(0), Some("_ibE_seK"));
+        assert_eq!(df["decoded"].dtype(), &line_type());
+    }
+
+    #[test]
+    fn reports_errors() {
+        let polylines = Series::new("".into(), ["_ibE_seK", "_ibE_seK_seK"]);
+        let err = decode_series(&polylines, 5).unwrap_err();
+        assert!(err.to_string().contains("row 1"));
+
+        let lines = Series::new(
+            "".into(),
+            [Series::new(
+                "".into(),
+                [Series::new("".into(), [1.0, 2.0, 3.0])],
+            )],
+        );
+        assert!(encode_series(&lines, 5).is_err());
+        let lines = Series::new(
+            "".into(),
+            [Series::new(
+                "".into(),
+                [Series::new("".into(), [1.0, 91.0])],
+            )],
+        );
+        let err = encode_series(&lines, 5).unwrap_err();
+        assert!(err.to_string().contains("latitude out of bounds"));
+        assert!(encode_series(&polylines, 5).is_err());
+    }
+}
@@ -0,0 +1,464 @@
+//! Google Maps Platform, whose polylines are encoded at precision 5
+
+#[cfg(feature = "serde_json")]
+use super::json_error;
+#[cfg(feature = "serde_json")]
+use crate::decode_polyline;
+use crate::errors::PolylineError;
+use crate::{encode_coord, factor};
+#[cfg(feature = "geo")]
+use geo::Simplify;
+use geo_types::{Coord, LineString};
+#[cfg(feature = "serde_json")]
+use serde_json::Value;
+
+/// The precision of Google's polylines
+pub const PRECISION: u32 = 5;
+
+/// The geometry of a route returned by the Directions API
+#[cfg(feature = "serde_json")]
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct DirectionsRoute {
+    /// The route's `overview_polyline`, a smoothed approximation suitable for display
+    pub overview: LineString<f64>,
+    /// The polylines of every step of every leg, joined into a single line in which
+    /// consecutive duplicate coordinates, such as those shared by adjacent steps, only appear
+    /// once. This is the overview when the response has no steps.
+    pub path: LineString<f64>,
+}
+
+/// Decodes the geometry of every route in a Directions API JSON response.
+///
+/// A response whose `status` isn't `OK` or `ZERO_RESULTS`, or which lacks the expected
+/// fields, is reported as a [`PolylineError::JsonError`]. A polyline which fails to decode is
+/// reported as it would be by [`decode_polyline`], with an index into that polyline.
+///
+/// # Examples
+///
+/// ```
+/// use geo_types::line_string;
+/// use polyline::providers::google;
+/// use serde_json::json;
+///
+/// let response = json!({
+///     "status": "OK",
+///     "routes": [{
+///         "overview_polyline": { "points": "_p~iF~ps|U_ulLnnqC" },
+///         "legs": [{
+///             "steps": [
+///                 { "polyline": { "points": "_p~iF~ps|U_ulLnnqC" } },
+///                 { "polyline": { "points": "_flwFn`faV_mqNvxq`@" } }
+///             ]
+///         }]
+///     }]
+/// });
+/// let routes = google::decode_directions(&response).unwrap();
+/// assert_eq!(
+///     routes[0].path,
+///     line_string![(x: -120.2, y: 38.5), (x: -120.95, y: 40.7), (x: -126.453, y: 43.252)]
+/// );
+/// ```
+#[cfg(feature = "serde_json")]
+pub fn decode_directions(response: &Value) -> Result<Vec<DirectionsRoute>, PolylineError> {
+    match response["status"].as_str() {
+        None | Some("OK") | Some("ZERO_RESULTS") => {}
+        Some(status) => {
+            return Err(json_error(format!(
+                "the Directions API returned status `{}`",
+                status
+            )))
+        }
+    }
+    let routes = response["routes"]
+        .as_array()
+        .ok_or_else(|| json_error("expected a `routes` array".to_string()))?;
+    routes.iter().map(decode_route).collect()
+}
+
+#[cfg(feature = "serde_json")]
+fn decode_route(route: &Value) -> Result<DirectionsRoute, PolylineError> {
+    let overview = decode_points(&route["overview_polyline"], "overview_polyline")?;
+    let mut path = vec![];
+    let legs = route["legs"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    for leg in legs {
+        let steps = leg["steps"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        for step in steps {
+            path.extend(decode_points(&step["polyline"], "polyline")?);
+        }
+    }
+    path.dedup();
+    let path = if path.is_empty() {
+        overview.clone()
+    } else {
+        LineString::new(path)
+    };
+    Ok(DirectionsRoute { overview, path })
+}
+
+/// Decodes the `points` of a Directions API polyline object
+#[cfg(feature = "serde_json")]
+fn decode_points(polyline: &Value, field: &str) -> Result<LineString<f64>, PolylineError> {
+    let points = polyline["points"]
+        .as_str()
+        .ok_or_else(|| json_error(format!("expected `{}.points` to be a string", field)))?;
+    decode_polyline(points, PRECISION)
+}
+
+/// The default limit on the length of Static Maps API URLs
+pub const STATIC_MAP_URL_LIMIT: usize = 8192;
+
+/// Builds Static Maps API URLs drawing a line as an encoded `path=enc:` parameter, keeping
+/// each URL within a length limit.
+///
+/// A line which doesn't fit in a single URL is split into several paths, each in a URL of its
+/// own and starting where the previous one ended. With the `geo` feature, the line can instead
+/// be simplified until it fits, and is only split if it still doesn't once it's simplified as
+/// far as allowed.
+///
+/// # Examples
+///
+/// ```
+/// use geo_types::line_string;
+/// use polyline::providers::google::StaticMapPath;
+///
+/// let line = line_string![(x: -120.2, y: 38.5), (x: -120.95, y: 40.7)];
+/// let urls = StaticMapPath::new("https://maps.googleapis.com/maps/api/staticmap?size=600x400")
+///     .style("color:0xff0000ff|weight:4")
+///     .urls(line)
+///     .unwrap();
+/// assert_eq!(
+///     urls,
+///     ["https://maps.googleapis.com/maps/api/staticmap?size=600x400\
+///       &path=color:0xff0000ff%7Cweight:4%7Cenc:_p~iF~ps%7CU_ulLnnqC"]
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaticMapPath {
+    base_url: String,
+    style: Option<String>,
+    max_url_len: usize,
+    #[cfg(feature = "geo")]
+    max_epsilon: Option<f64>,
+}
+
+impl StaticMapPath {
+    /// Creates a builder for URLs starting with `base_url`, which holds the rest of the map's
+    /// parameters, such as its `size` and `key`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        StaticMapPath {
+            base_url: base_url.into(),
+            style: None,
+            max_url_len: STATIC_MAP_URL_LIMIT,
+            #[cfg(feature = "geo")]
+            max_epsilon: None,
+        }
+    }
+
+    /// Sets the path's style, such as `color:0xff0000ff|weight:4`, which is drawn the same in
+    /// every URL.
+    pub fn style(mut self, style: impl Into<String>) -> Self {
+        self.style = Some(style.into());
+        self
+    }
+
+    /// Sets the longest URL to build, which is [`STATIC_MAP_URL_LIMIT`] by default.
+    pub fn max_url_len(mut self, max_url_len: usize) -> Self {
+        self.max_url_len = max_url_len;
+        self
+    }
+
+    /// Allows the line to be simplified, by up to `max_epsilon` degrees, so it fits in a single
+    /// URL. The line is simplified as little as possible.
+    #[cfg(feature = "geo")]
+    pub fn simplify(mut self, max_epsilon: f64) -> Self {
+        self.max_epsilon = Some(max_epsilon);
+        self
+    }
+
+    /// Returns the URLs drawing the line, in order.
+    ///
+    /// A URL is only longer than the limit if the base URL doesn't leave room for a single
+    /// segment of the line. An empty line draws no URLs.
+    pub fn urls<C>(&self, coordinates: C) -> Result<Vec<String>, PolylineError>
+    where
+        C: IntoIterator<Item = Coord<f64>>,
+    {
+        let separator = match self.base_url.chars().last() {
+            Some('?' | '&') => "",
+            _ if self.base_url.contains('?') => "&",
+            _ => "?",
+        };
+        let paths = self.paths(coordinates, self.base_url.len() + separator.len())?;
+        Ok(paths
+            .into_iter()
+            .map(|path| format!("{}{}{}", self.base_url, separator, path))
+            .collect())
+    }
+
+    /// Returns the `path=enc:` parameters drawing the line, in order, each of which fits in a
+    /// URL alongside `base_url`.
+    pub fn params<C>(&self, coordinates: C) -> Result<Vec<String>, PolylineError>
+    where
+        C: IntoIterator<Item = Coord<f64>>,
+    {
+        self.paths(coordinates, self.base_url.len() + 1)
+    }
+
+    fn paths<C>(&self, coordinates: C, base_len: usize) -> Result<Vec<String>, PolylineError>
+    where
+        C: IntoIterator<Item = Coord<f64>>,
+    {
+        let line: LineString<f64> = coordinates.into_iter().collect();
+        let budget = self.max_url_len.saturating_sub(base_len);
+        #[cfg(feature = "geo")]
+        if let Some(max_epsilon) = self.max_epsilon {
+            // start at about a tenth of a meter, doubling until the line fits
+            let mut epsilon = 0.0;
+            loop {
+                let paths = self.split(&line.simplify(epsilon), budget)?;
+                if paths.len() <= 1 || epsilon >= max_epsilon {
+                    return Ok(paths);
+                }
+                epsilon = if epsilon == 0.0 { 1e-6 } else { epsilon * 2.0 };
+                epsilon = epsilon.min(max_epsilon);
+            }
+        }
+        self.split(&line, budget)
+    }
+
+    /// Splits a line into as few paths as fit in `budget` bytes each
+    fn split(&self, line: &LineString<f64>, budget: usize) -> Result<Vec<String>, PolylineError> {
+        let mut prefix = String::from("path=");
+        if let Some(style) = &self.style {
+            escape(style.as_bytes(), &mut prefix);
+            escape(b"|", &mut prefix);
+        }
+        prefix.push_str("enc:");
+
+        let factor = factor(PRECISION);
+        let mut paths = vec![];
+        let mut output = vec![];
+        let mut previous = Coord { x: 0, y: 0 };
+        let mut len = prefix.len();
+        let mut count = 0;
+        for (idx, &coord) in line.0.iter().enumerate() {
+            let start = output.len();
+            encode_coord(idx, coord, factor, &mut previous, &mut output)?;
+            let added = escaped_len(&output[start..]);
+            // every path has at least one segment
+            if len + added <= budget || count < 2 {
+                len += added;
+                count += 1;
+                continue;
+            }
+            output.truncate(start);
+            paths.push(path(&prefix, &output));
+            // start the next path where this one ended
+            output.clear();
+            previous = Coord { x: 0, y: 0 };
+            encode_coord(idx - 1, line.0[idx - 1], factor, &mut previous, &mut output)?;
+            encode_coord(idx, coord, factor, &mut previous, &mut output)?;
+            len = prefix.len() + escaped_len(&output);
+            count = 2;
+        }
+        if count > 0 {
+            paths.push(path(&prefix, &output));
+        }
+        Ok(paths)
+    }
+}
+
+fn path(prefix: &str, encoded: &[u8]) -> String {
+    let mut path = prefix.to_string();
+    escape(encoded, &mut path);
+    path
+}
+
+/// Whether a byte can appear in a URL's query without being percent-encoded
+fn is_unescaped(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"-._~:,".contains(&byte)
+}
+
+fn escaped_len(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .map(|&byte| if is_unescaped(byte) { 1 } else { 3 })
+        .sum()
+}
+
+fn escape(bytes: &[u8], output: &mut String) {
+    for &byte in bytes {
+        if is_unescaped(byte) {
+            output.push(byte as char);
+        } else {
+            output.push_str(&format!("%{:02X}", byte));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_polyline, encode_coordinates};
+    #[cfg(feature = "serde_json")]
+    use serde_json::json;
+
+    #[test]
+    #[cfg(feature = "serde_json")]
+    fn decodes_directions() {
+        let response = json!({
+            "status": "OK",
+            "routes": [
+                {
+                    "overview_polyline": { "points": "_p~iF~ps|U_ulLnnqC" },
+                    "legs": [
+                        { "steps": [{ "polyline": { "points": "_p~iF~ps|U_ulLnnqC" } }] },
+                        { "steps": [{ "polyline": { "points": "_flwFn`faV_mqNvxq`@" } }] }
+                    ]
+                },
+                { "overview_polyline": { "points": "_ibE_seK" }, "legs": [] }
+            ]
+        });
+        let routes = decode_directions(&response).unwrap();
+        assert_eq!(routes.len(), 2);
+        assert_eq!(
+            routes[0].path,
+            decode_polyline("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap()
+        );
+        assert_eq!(routes[0].overview.0.len(), 2);
+        assert_eq!(routes[1].path, routes[1].overview);
+
+        let empty = json!({ "status": "ZERO_RESULTS", "routes": [] });
+        assert_eq!(decode_directions(&empty).unwrap(), []);
+    }
+
+    #[test]
+    #[cfg(feature = "serde_json")]
+    fn reports_errors() {
+        let denied = json!({ "status": "REQUEST_DENIED", "error_message": "no key" });
+        assert!(matches!(
+            decode_directions(&denied),
+            Err(PolylineError::JsonError { .. })
+        ));
+        assert!(matches!(
+            decode_directions(&json!({ "routes": [{}] })),
+            Err(PolylineError::JsonError { .. })
+        ));
+        let invalid = json!({ "routes": [{ "overview_polyline": { "points": "_ibE_seK_seK" } }] });
+        assert_eq!(
+            decode_directions(&invalid).unwrap_err(),
+            PolylineError::NoLongError { idx: 8 }
+        );
+    }
+
+    /// A zigzag line of `n` coordinates, which can't be simplified much
+    fn zigzag(n: usize) -> LineString<f64> {
+        (0..n)
+            .map(|i| Coord {
+                x: i as f64 * 0.01,
+                y: if i % 2 == 0 { 0.0 } else { 0.5 },
+            })
+            .collect()
+    }
+
+    /// Decodes the polyline of a `path=enc:` parameter
+    fn decode_param(param: &str) -> LineString<f64> {
+        let escaped = param.split("enc:").nth(1).unwrap().as_bytes();
+        let mut encoded = vec![];
+        let mut i = 0;
+        while i < escaped.len() {
+            if escaped[i] == b'%' {
+                let hex = std::str::from_utf8(&escaped[i + 1..i + 3]).unwrap();
+                encoded.push(u8::from_str_radix(hex, 16).unwrap());
+                i += 3;
+            } else {
+                encoded.push(escaped[i]);
+                i += 1;
+            }
+        }
+        decode_polyline(std::str::from_utf8(&encoded).unwrap(), PRECISION).unwrap()
+    }
+
+    #[test]
+    fn splits_long_lines() {
+        let line = zigzag(500);
+        let base = "https://maps.googleapis.com/maps/api/staticmap?size=400x400";
+        let builder = StaticMapPath::new(base).style("weight:2").max_url_len(1000);
+        let urls = builder.urls(line.clone()).unwrap();
+        assert!(urls.len() > 1);
+        assert!(urls.iter().all(|url| url.len() <= 1000));
+
+        // the paths join up into the original line
+        let mut joined: Vec<Coord<f64>> = vec![];
+        for param in builder.params(line.clone()).unwrap() {
+            let path = decode_param(&param);
+            if let Some(last) = joined.last() {
+                assert_eq!(last, &path.0[0]);
+                joined.pop();
+            }
+            joined.extend(path);
+        }
+        let encoded = encode_coordinates(line.clone(), PRECISION).unwrap();
+        assert_eq!(
+            LineString::new(joined),
+            decode_polyline(&encoded, PRECISION).unwrap()
+        );
+
+        let short = builder.urls(zigzag(3)).unwrap();
+        assert_eq!(short.len(), 1);
+        assert!(short[0].starts_with(&format!("{}&path=weight:2%7Cenc:", base)));
+        assert_eq!(builder.urls(zigzag(0)).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn joins_parameters() {
+        let line = zigzag(2);
+        let url = |base| StaticMapPath::new(base).urls(line.clone()).unwrap()[0].clone();
+        assert!(url("https://example.com/map").starts_with("https://example.com/map?path="));
+        assert!(url("https://example.com/map?").starts_with("https://example.com/map?path="));
+        assert!(
+            url("https://example.com/map?a=b&").starts_with("https://example.com/map?a=b&path=")
+        );
+    }
+
+    #[test]
+    fn always_makes_progress() {
+        let urls = StaticMapPath::new("https://example.com/map")
+            .max_url_len(10)
+            .urls(zigzag(4))
+            .unwrap();
+        assert_eq!(urls.len(), 3);
+
+        assert!(StaticMapPath::new("")
+            .urls(vec![Coord { x: 181.0, y: 0.0 }])
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "geo")]
+    fn simplifies_before_splitting() {
+        // collinear points simplify to the ends of the line
+        let line: LineString<f64> = (0..2000)
+            .map(|i| Coord {
+                x: i as f64 * 0.001,
+                y: 0.0,
+            })
+            .collect();
+        let builder = StaticMapPath::new("https://example.com/map").max_url_len(200);
+        assert!(builder.urls(line.clone()).unwrap().len() > 1);
+        let urls = builder.clone().simplify(0.001).urls(line).unwrap();
+        assert_eq!(urls.len(), 1);
+        assert_eq!(decode_param(&urls[0]).0.len(), 2);
+
+        // a zigzag can't be simplified within the limit
+        let urls = builder.simplify(0.001).urls(zigzag(500)).unwrap();
+        assert!(urls.len() > 1);
+    }
+}
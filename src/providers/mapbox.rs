@@ -0,0 +1,105 @@
+//! The Mapbox Directions API, whose `polyline6` geometries are encoded at precision 6
+//!
+//! Mapbox defaults to `geometries=polyline`, encoded at precision 5, which decodes at precision
+//! 6 to coordinates a tenth of their real size, or out of bounds the other way around. Routes
+//! should be requested with `geometries=polyline6` for these helpers.
+
+use crate::errors::PolylineError;
+use crate::{decode_polyline, encode_coordinates};
+use geo_types::{Coord, LineString};
+use std::fmt;
+
+/// The precision of Mapbox's `polyline6` geometries
+pub const PRECISION: u32 = 6;
+
+/// An error decoding a Mapbox geometry, whose message reminds the reader that geometries have
+/// to be requested with `geometries=polyline6`
+#[derive(Debug, PartialEq)]
+pub struct GeometryError(pub PolylineError);
+
+impl fmt::Display for GeometryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}; was the route requested with `geometries=polyline6`?",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for GeometryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+impl From<GeometryError> for PolylineError {
+    fn from(err: GeometryError) -> Self {
+        err.0
+    }
+}
+
+/// Decodes a Mapbox Directions API `polyline6` geometry, such as a route's `geometry`.
+///
+/// # Examples
+///
+/// ```
+/// use polyline::providers::mapbox;
+///
+/// let geometry = mapbox::decode_geometry("_izlhA~rlgdF_{geC~ywl@").unwrap();
+/// assert_eq!(geometry, polyline::decode_polyline("_p~iF~ps|U_ulLnnqC", 5).unwrap());
+///
+/// // a GeoJSON geometry
+/// let err = mapbox::decode_geometry(r#"{"type":"LineString"}"#).unwrap_err();
+/// assert!(err.to_string().contains("geometries=polyline6"));
+/// ```
+pub fn decode_geometry(geometry: &str) -> Result<LineString<f64>, GeometryError> {
+    decode_polyline(geometry, PRECISION).map_err(GeometryError)
+}
+
+/// Encodes coordinates as a Mapbox `polyline6` geometry, such as the path of a Static Images
+/// API overlay.
+///
+/// # Examples
+///
+/// ```
+/// use geo_types::line_string;
+/// use polyline::providers::mapbox;
+///
+/// let coords = line_string![(x: -120.2, y: 38.5), (x: -120.95, y: 40.7)];
+/// assert_eq!(mapbox::encode_geometry(coords).unwrap(), "_izlhA~rlgdF_{geC~ywl@");
+/// ```
+pub fn encode_geometry<C>(coordinates: C) -> Result<String, PolylineError>
+where
+    C: IntoIterator<Item = Coord<f64>>,
+{
+    encode_coordinates(coordinates, PRECISION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let geometry = "_izlhA~rlgdF_{geC~ywl@_kwzCn`{nI";
+        let coords = decode_geometry(geometry).unwrap();
+        assert_eq!(coords.0.len(), 3);
+        assert_eq!(encode_geometry(coords).unwrap(), geometry);
+    }
+
+    #[test]
+    fn mentions_polyline6() {
+        let err = decode_geometry("_ibE_seK_seK").unwrap_err();
+        assert_eq!(err, GeometryError(PolylineError::NoLongError { idx: 8 }));
+        assert_eq!(
+            err.to_string(),
+            "no longitude to go with latitude at index: 8; \
+             was the route requested with `geometries=polyline6`?"
+        );
+        assert_eq!(
+            PolylineError::from(err),
+            PolylineError::NoLongError { idx: 8 }
+        );
+    }
+}
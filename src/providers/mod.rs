@@ -0,0 +1,20 @@
+//! Helpers for the polylines returned by routing services.
+//!
+//! Each service documents its own conventions for the precision of its polylines, and where
+//! they're found in its responses; these modules apply them, so the polylines can be decoded
+//! without rediscovering them.
+//!
+//! Helpers which read JSON responses need the `serde_json` feature.
+
+#[cfg(feature = "serde_json")]
+use crate::errors::PolylineError;
+
+pub mod google;
+pub mod mapbox;
+pub mod osrm;
+pub mod valhalla;
+
+#[cfg(feature = "serde_json")]
+fn json_error(message: String) -> PolylineError {
+    PolylineError::JsonError { message }
+}
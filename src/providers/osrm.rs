@@ -0,0 +1,229 @@
+//! OSRM, whose `polyline6` geometries are encoded at precision 6
+//!
+//! OSRM returns GeoJSON geometries by default, and can also return polylines encoded at
+//! precision 5, so routes should be requested with `geometries=polyline6` for these helpers,
+//! and `overview=full` if their annotations are used.
+
+#[cfg(feature = "serde_json")]
+use super::json_error;
+#[cfg(feature = "serde_json")]
+use crate::decode_polyline;
+#[cfg(feature = "serde_json")]
+use crate::errors::PolylineError;
+#[cfg(feature = "serde_json")]
+use geo_types::LineString;
+#[cfg(feature = "serde_json")]
+use serde_json::Value;
+
+/// The precision of OSRM's `polyline6` geometries
+pub const PRECISION: u32 = 6;
+
+/// The geometry of a route returned by OSRM's route service
+#[cfg(feature = "serde_json")]
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Route {
+    /// The route's overview `geometry`
+    pub geometry: LineString<f64>,
+    /// The geometry of each leg, joined from its steps' geometries in the same way as
+    /// [`google::DirectionsRoute::path`](super::google::DirectionsRoute::path). Legs are empty
+    /// unless the route was requested with `steps=true`.
+    pub legs: Vec<LineString<f64>>,
+}
+
+/// Decodes the geometry of every route in an OSRM route service JSON response.
+///
+/// Where every leg has an `annotation`, its arrays are checked against the route's geometry:
+/// annotations describe each segment between consecutive coordinates of the full geometry, so
+/// they don't line up with a simplified overview. A response whose `code` isn't `Ok`, which
+/// lacks the expected fields, has GeoJSON geometries, or whose annotations don't line up with
+/// its geometry is reported as a [`PolylineError::JsonError`].
+///
+/// # Examples
+///
+/// ```
+/// use polyline::providers::osrm;
+/// use serde_json::json;
+///
+/// let response = json!({
+///     "code": "Ok",
+///     "routes": [{
+///         "geometry": "_izlhA~rlgdF_{geC~ywl@_kwzCn`{nI",
+///         "legs": [{ "annotation": { "distance": [251.5, 342.7], "nodes": [1, 2, 3] } }]
+///     }]
+/// });
+/// let routes = osrm::decode_routes(&response).unwrap();
+/// assert_eq!(routes[0].geometry.0.len(), 3);
+/// ```
+#[cfg(feature = "serde_json")]
+pub fn decode_routes(response: &Value) -> Result<Vec<Route>, PolylineError> {
+    match response["code"].as_str() {
+        None | Some("Ok") => {}
+        Some(code) => {
+            let message = response["message"].as_str().unwrap_or_default();
+            return Err(json_error(format!(
+                "OSRM returned code `{}`: {}",
+                code, message
+            )));
+        }
+    }
+    let routes = response["routes"]
+        .as_array()
+        .ok_or_else(|| json_error("expected a `routes` array".to_string()))?;
+    routes.iter().map(decode_route).collect()
+}
+
+#[cfg(feature = "serde_json")]
+fn decode_route(route: &Value) -> Result<Route, PolylineError> {
+    let geometry = decode_geometry(&route["geometry"])?;
+    let legs = route["legs"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let mut annotated = 0;
+    let mut segments = 0;
+    let mut decoded = Vec::with_capacity(legs.len());
+    for (i, leg) in legs.iter().enumerate() {
+        if let Some(count) = annotation_segments(&leg["annotation"], i)? {
+            annotated += 1;
+            segments += count;
+        }
+        let mut coords = vec![];
+        let steps = leg["steps"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        for step in steps {
+            coords.extend(decode_geometry(&step["geometry"])?);
+        }
+        coords.dedup();
+        decoded.push(LineString::new(coords));
+    }
+    if annotated > 0 && annotated == legs.len() && segments + 1 != geometry.0.len() {
+        return Err(json_error(format!(
+            "annotations describe {} segments, but the geometry has {} coordinates; \
+             request the route with `overview=full`",
+            segments,
+            geometry.0.len()
+        )));
+    }
+    Ok(Route {
+        geometry,
+        legs: decoded,
+    })
+}
+
+/// Decodes a `polyline6` geometry
+#[cfg(feature = "serde_json")]
+fn decode_geometry(geometry: &Value) -> Result<LineString<f64>, PolylineError> {
+    let polyline = geometry.as_str().ok_or_else(|| {
+        json_error("expected a polyline geometry; request `geometries=polyline6`".to_string())
+    })?;
+    decode_polyline(polyline, PRECISION)
+}
+
+/// Returns the number of segments described by a leg's annotation, checking that its arrays
+/// agree with one another
+#[cfg(feature = "serde_json")]
+fn annotation_segments(annotation: &Value, leg: usize) -> Result<Option<usize>, PolylineError> {
+    let Some(annotation) = annotation.as_object() else {
+        return Ok(None);
+    };
+    let mut segments = None;
+    for (name, values) in annotation {
+        let Some(values) = values.as_array() else {
+            // such as `metadata`
+            continue;
+        };
+        // there's a node for each coordinate, rather than each segment
+        let count = if name == "nodes" {
+            values.len().saturating_sub(1)
+        } else {
+            values.len()
+        };
+        match segments {
+            Some(segments) if segments != count => {
+                return Err(json_error(format!(
+                    "the `{}` annotation of leg {} describes {} segments rather than {}",
+                    name, leg, count, segments
+                )))
+            }
+            _ => segments = Some(count),
+        }
+    }
+    Ok(segments)
+}
+
+#[cfg(all(test, feature = "serde_json"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn decodes_routes() {
+        let response = json!({
+            "code": "Ok",
+            "routes": [{
+                "geometry": "_izlhA~rlgdF_{geC~ywl@_kwzCn`{nI",
+                "legs": [
+                    {
+                        "steps": [{ "geometry": "_izlhA~rlgdF_{geC~ywl@" }],
+                        "annotation": { "duration": [10.0], "nodes": [1, 2] }
+                    },
+                    {
+                        "steps": [
+                            { "geometry": "_ecslA~meueF_kwzCn`{nI" },
+                            { "geometry": "_q{nqAnoaepF" }
+                        ],
+                        "annotation": { "duration": [12.0], "metadata": {} }
+                    }
+                ]
+            }]
+        });
+        let routes = decode_routes(&response).unwrap();
+        let route = &routes[0];
+        assert_eq!(
+            route.geometry,
+            decode_polyline("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap()
+        );
+        assert_eq!(route.legs.len(), 2);
+        assert_eq!(route.legs[0].0.len(), 2);
+        // the arrival step's point is a duplicate
+        assert_eq!(route.legs[1].0.len(), 2);
+
+        // legs without annotations aren't checked
+        let response = json!({ "routes": [{ "geometry": "_izlhA~rlgdF", "legs": [{}] }] });
+        assert_eq!(decode_routes(&response).unwrap()[0].legs[0].0.len(), 0);
+    }
+
+    #[test]
+    fn reports_errors() {
+        let error = json!({ "code": "NoRoute", "message": "Impossible route" });
+        assert!(matches!(
+            decode_routes(&error),
+            Err(PolylineError::JsonError { .. })
+        ));
+
+        let geojson = json!({ "routes": [{ "geometry": { "type": "LineString" } }] });
+        let err = decode_routes(&geojson).unwrap_err();
+        assert!(err.to_string().contains("geometries=polyline6"));
+
+        // a simplified overview
+        let simplified = json!({ "routes": [{
+            "geometry": "_izlhA~rlgdF_g`aHn{s|J",
+            "legs": [{ "annotation": { "distance": [1.0, 2.0] } }]
+        }] });
+        let err = decode_routes(&simplified).unwrap_err();
+        assert!(err.to_string().contains("overview=full"));
+
+        let misaligned = json!({ "routes": [{
+            "geometry": "_izlhA~rlgdF_{geC~ywl@_kwzCn`{nI",
+            "legs": [{ "annotation": { "distance": [1.0, 2.0], "nodes": [1, 2] } }]
+        }] });
+        assert!(matches!(
+            decode_routes(&misaligned),
+            Err(PolylineError::JsonError { .. })
+        ));
+    }
+}
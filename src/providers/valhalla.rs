@@ -0,0 +1,180 @@
+//! Valhalla, whose shapes are encoded at precision 6
+//!
+//! Valhalla's elevation service returns a `height` for each point of a shape, in meters, which
+//! [`decode_shape_with_heights`] pairs with the shape's coordinates. Heights are `null` where
+//! Valhalla has no elevation data.
+
+#[cfg(feature = "serde_json")]
+use super::json_error;
+use crate::errors::PolylineError;
+use crate::{decode_polyline, encode_coordinates};
+use geo_types::{Coord, LineString};
+#[cfg(feature = "serde_json")]
+use serde_json::Value;
+
+/// The precision of Valhalla's shapes
+pub const PRECISION: u32 = 6;
+
+/// A coordinate of a shape, along with its height in meters, if known
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ElevatedCoord {
+    /// The longitude (`x`) and latitude (`y`) of the point
+    pub coord: Coord<f64>,
+    /// The height of the point, or `None` where there's no elevation data
+    pub height: Option<f64>,
+}
+
+/// Decodes a Valhalla shape, such as a trip leg's `shape`.
+///
+/// # Examples
+///
+/// ```
+/// use polyline::providers::valhalla;
+///
+/// let shape = valhalla::decode_shape("_izlhA~rlgdF_{geC~ywl@").unwrap();
+/// assert_eq!(shape, polyline::decode_polyline("_p~iF~ps|U_ulLnnqC", 5).unwrap());
+/// ```
+pub fn decode_shape(shape: &str) -> Result<LineString<f64>, PolylineError> {
+    decode_polyline(shape, PRECISION)
+}
+
+/// Encodes coordinates as a Valhalla shape, such as the `encoded_polyline` of an elevation
+/// request.
+pub fn encode_shape<C>(coordinates: C) -> Result<String, PolylineError>
+where
+    C: IntoIterator<Item = Coord<f64>>,
+{
+    encode_coordinates(coordinates, PRECISION)
+}
+
+/// Decodes a Valhalla shape, pairing each of its coordinates with the height at the same
+/// position in `heights`, as returned by the elevation service.
+///
+/// There must be exactly one height for each coordinate, or a
+/// [`PolylineError::HeightCountError`] is returned; shapes resampled by the elevation service
+/// have to be taken from its response, rather than the request.
+///
+/// # Examples
+///
+/// ```
+/// use polyline::providers::valhalla;
+///
+/// let elevated =
+///     valhalla::decode_shape_with_heights("_izlhA~rlgdF_{geC~ywl@", &[Some(12.0), None]).unwrap();
+/// assert_eq!(elevated[0].height, Some(12.0));
+/// assert_eq!(elevated[1].coord.y, 40.7);
+/// ```
+pub fn decode_shape_with_heights(
+    shape: &str,
+    heights: &[Option<f64>],
+) -> Result<Vec<ElevatedCoord>, PolylineError> {
+    let coords = decode_shape(shape)?;
+    if coords.0.len() != heights.len() {
+        return Err(PolylineError::HeightCountError {
+            points: coords.0.len(),
+            heights: heights.len(),
+        });
+    }
+    Ok(coords
+        .into_iter()
+        .zip(heights)
+        .map(|(coord, &height)| ElevatedCoord { coord, height })
+        .collect())
+}
+
+/// Decodes the shape of an elevation service JSON response, pairing it with the response's
+/// heights as [`decode_shape_with_heights`] does.
+///
+/// The response must include its `encoded_polyline`, and either a `height` array, or the
+/// `range_height` array of `[distance, height]` pairs returned for requests with
+/// `range: true`. A response lacking these is reported as a [`PolylineError::JsonError`].
+///
+/// # Examples
+///
+/// ```
+/// use polyline::providers::valhalla;
+/// use serde_json::json;
+///
+/// let response = json!({
+///     "encoded_polyline": "_izlhA~rlgdF_{geC~ywl@",
+///     "range_height": [[0, 303], [251, null]]
+/// });
+/// let elevated = valhalla::decode_height_response(&response).unwrap();
+/// assert_eq!(elevated[0].height, Some(303.0));
+/// assert_eq!(elevated[1].height, None);
+/// ```
+#[cfg(feature = "serde_json")]
+pub fn decode_height_response(response: &Value) -> Result<Vec<ElevatedCoord>, PolylineError> {
+    let shape = response["encoded_polyline"]
+        .as_str()
+        .ok_or_else(|| json_error("expected an `encoded_polyline` string".to_string()))?;
+    let heights: Vec<Option<f64>> = if let Some(heights) = response["height"].as_array() {
+        heights.iter().map(Value::as_f64).collect()
+    } else if let Some(ranges) = response["range_height"].as_array() {
+        ranges.iter().map(|range| range[1].as_f64()).collect()
+    } else {
+        return Err(json_error(
+            "expected a `height` or `range_height` array".to_string(),
+        ));
+    };
+    decode_shape_with_heights(shape, &heights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let shape = "_izlhA~rlgdF_{geC~ywl@_kwzCn`{nI";
+        let coords = decode_shape(shape).unwrap();
+        assert_eq!(
+            coords,
+            decode_polyline("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap()
+        );
+        assert_eq!(encode_shape(coords).unwrap(), shape);
+    }
+
+    #[test]
+    fn pairs_heights() {
+        let shape = "_izlhA~rlgdF_{geC~ywl@_kwzCn`{nI";
+        let elevated = decode_shape_with_heights(shape, &[Some(1.0), None, Some(-3.5)]).unwrap();
+        let coords = decode_shape(shape).unwrap();
+        assert_eq!(elevated.len(), 3);
+        assert_eq!(elevated[2].coord, coords.0[2]);
+        assert_eq!(elevated[2].height, Some(-3.5));
+
+        assert_eq!(
+            decode_shape_with_heights(shape, &[Some(1.0)]).unwrap_err(),
+            PolylineError::HeightCountError {
+                points: 3,
+                heights: 1
+            }
+        );
+        assert_eq!(decode_shape_with_heights("", &[]).unwrap(), []);
+    }
+
+    #[test]
+    #[cfg(feature = "serde_json")]
+    fn decodes_height_responses() {
+        use serde_json::json;
+
+        let response = json!({
+            "encoded_polyline": "_izlhA~rlgdF_{geC~ywl@",
+            "height": [303, 215.5]
+        });
+        let elevated = decode_height_response(&response).unwrap();
+        assert_eq!(elevated[1].height, Some(215.5));
+
+        let shape_only = json!({ "encoded_polyline": "_izlhA~rlgdF" });
+        assert!(matches!(
+            decode_height_response(&shape_only),
+            Err(PolylineError::JsonError { .. })
+        ));
+        let resampled = json!({ "encoded_polyline": "_izlhA~rlgdF", "height": [1, 2] });
+        assert!(matches!(
+            decode_height_response(&resampled),
+            Err(PolylineError::HeightCountError { .. })
+        ));
+    }
+}
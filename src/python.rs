@@ -0,0 +1,165 @@
+//! Python bindings, enabled with the `python` feature
+//!
+//! Build the extension module with [maturin](https://www.maturin.rs), e.g.
+//! `maturin build --features python`. Coordinates are passed as `(longitude, latitude)` tuples,
+//! matching the `(x, y)` order used by the rest of this crate.
+//!
+//! With the `numpy` feature, `encode_array` and `decode_array` additionally accept and return
+//! NumPy `(N, 2)` `float64` arrays of `longitude, latitude` rows, avoiding Python-level list
+//! conversion.
+//!
+//! Failures raise `ValueError(message, index)`, where `index` is the position in the input at
+//! which the error occurred, or `None` if it cannot be attributed to a position.
+
+use crate::errors::PolylineError;
+use crate::{decode_polyline, encode_coordinates};
+#[cfg(feature = "numpy")]
+use crate::{estimate_point_count, Decoder};
+use geo_types::Coord;
+#[cfg(feature = "numpy")]
+use numpy::ndarray::{Array2, ArrayView2};
+#[cfg(feature = "numpy")]
+use numpy::{IntoPyArray, PyArray2, PyReadonlyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+fn value_error(err: PolylineError) -> PyErr {
+    PyValueError::new_err((err.to_string(), error_index(&err)))
+}
+
+/// The position in the input at which the error occurred, if it can be attributed to one.
+fn error_index(err: &PolylineError) -> Option<usize> {
+    match *err {
+        PolylineError::LongitudeCoordError { idx, .. }
+        | PolylineError::LatitudeCoordError { idx, .. }
+        | PolylineError::NoLongError { idx }
+        | PolylineError::DecodeError { idx }
+        | PolylineError::CoordEncodingError { idx, .. } => Some(idx),
+        _ => None,
+    }
+}
+
+/// Encodes a list of `(longitude, latitude)` tuples as a polyline.
+#[pyfunction]
+pub fn encode(coordinates: Vec<(f64, f64)>, precision: u32) -> PyResult<String> {
+    let coordinates = coordinates.into_iter().map(|(x, y)| Coord { x, y });
+    encode_coordinates(coordinates, precision).map_err(value_error)
+}
+
+/// Decodes a polyline into a list of `(longitude, latitude)` tuples.
+#[pyfunction]
+pub fn decode(polyline: &str, precision: u32) -> PyResult<Vec<(f64, f64)>> {
+    let line_string = decode_polyline(polyline, precision).map_err(value_error)?;
+    Ok(line_string.coords().map(|c| (c.x, c.y)).collect())
+}
+
+/// Encodes a NumPy `(N, 2)` array of `longitude, latitude` rows as a polyline.
+#[cfg(feature = "numpy")]
+#[pyfunction]
+pub fn encode_array(coordinates: PyReadonlyArray2<'_, f64>, precision: u32) -> PyResult<String> {
+    encode_rows(coordinates.as_array(), precision)
+}
+
+#[cfg(feature = "numpy")]
+fn encode_rows(coordinates: ArrayView2<'_, f64>, precision: u32) -> PyResult<String> {
+    if coordinates.ncols() != 2 {
+        return Err(PyValueError::new_err((
+            "coordinates must have shape (N, 2)",
+            None::<usize>,
+        )));
+    }
+    let coordinates = coordinates.rows().into_iter().map(|row| Coord {
+        x: row[0],
+        y: row[1],
+    });
+    encode_coordinates(coordinates, precision).map_err(value_error)
+}
+
+/// Decodes a polyline into a NumPy `(N, 2)` array of `longitude, latitude` rows.
+#[cfg(feature = "numpy")]
+#[pyfunction]
+pub fn decode_array<'py>(
+    py: Python<'py>,
+    polyline: &str,
+    precision: u32,
+) -> PyResult<Bound<'py, PyArray2<f64>>> {
+    let array = decode_rows(polyline, precision).map_err(value_error)?;
+    Ok(array.into_pyarray(py))
+}
+
+#[cfg(feature = "numpy")]
+fn decode_rows(polyline: &str, precision: u32) -> Result<Array2<f64>, PolylineError> {
+    let mut values = Vec::with_capacity(estimate_point_count(polyline) * 2);
+    for coord in Decoder::new(polyline.as_bytes(), precision) {
+        let coord = coord?;
+        values.push(coord.x);
+        values.push(coord.y);
+    }
+    let rows = values.len() / 2;
+    Ok(Array2::from_shape_vec((rows, 2), values)
+        .expect("values are pushed in longitude, latitude pairs"))
+}
+
+#[pymodule]
+fn polyline(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(encode, m)?)?;
+    m.add_function(wrap_pyfunction!(decode, m)?)?;
+    #[cfg(feature = "numpy")]
+    {
+        m.add_function(wrap_pyfunction!(encode_array, m)?)?;
+        m.add_function(wrap_pyfunction!(decode_array, m)?)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let coordinates = vec![(-120.2, 38.5), (-120.95, 40.7), (-126.453, 43.252)];
+        let encoded = encode(coordinates.clone(), 5).unwrap();
+        assert_eq!(encoded, "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+        assert_eq!(decode(&encoded, 5).unwrap(), coordinates);
+
+        assert!(encode(vec![(0.0, 91.0)], 5).is_err());
+        assert!(decode("_p~iF~ps|U_ulL", 5).is_err());
+    }
+
+    #[test]
+    fn reports_indices() {
+        assert_eq!(
+            error_index(&decode_polyline("_p~iF~ps|U_ulL", 5).unwrap_err()),
+            Some(10)
+        );
+        assert_eq!(
+            error_index(&decode_polyline("_p~iF~ps|U", 4).unwrap_err()),
+            Some(0)
+        );
+        assert_eq!(
+            error_index(&PolylineError::ContainerError {
+                message: String::new()
+            }),
+            None
+        );
+    }
+
+    #[cfg(feature = "numpy")]
+    #[test]
+    fn arrays() {
+        let rows = numpy::ndarray::array![[-120.2, 38.5], [-120.95, 40.7], [-126.453, 43.252]];
+        assert_eq!(
+            encode_rows(rows.view(), 5).unwrap(),
+            "_p~iF~ps|U_ulLnnqC_mqNvxq`@"
+        );
+        assert_eq!(decode_rows("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap(), rows);
+        assert_eq!(decode_rows("", 5).unwrap().dim(), (0, 2));
+
+        assert!(encode_rows(Array2::zeros((3, 3)).view(), 5).is_err());
+        assert_eq!(
+            decode_rows("_p~iF~ps|U_ulL", 5).unwrap_err(),
+            PolylineError::NoLongError { idx: 10 }
+        );
+    }
+}
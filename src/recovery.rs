@@ -0,0 +1,392 @@
+//! Decoding and repairing damaged polylines
+
+use crate::errors::PolylineError;
+use crate::{
+    decode_next, encode, factor, DECODING_TABLE, INVALID_CHUNK, MAX_LATITUDE, MAX_LONGITUDE,
+    MIN_LATITUDE, MIN_LONGITUDE,
+};
+use geo_types::{Coord, LineString, MultiLineString};
+use std::ops::Range;
+
+/// Decodes as much of a damaged polyline as possible, for finding where it was damaged.
+///
+/// Instead of stopping at the first error, coordinates which can't be decoded, are out of
+/// bounds or are missing their longitude become `None`. After a character which can't be
+/// decoded, decoding resumes with the next character as the start of a new coordinate. The
+/// changes lost with the character are lost for good, so the coordinates after it may be offset
+/// from their true positions, while those before it are unaffected.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+/// use geo_types::coord;
+///
+/// // the second coordinate is damaged
+/// let coords = polyline::decode_polyline_lossy("_p~iF~ps|U_ul!LnnqC_mqNvxq`@", 5);
+/// assert_eq!(coords[0], Some(coord! { x: -120.2, y: 38.5 }));
+/// assert_eq!(coords[1], None);
+/// ```
+pub fn decode_polyline_lossy(polyline: &str, precision: u32) -> Vec<Option<Coord<f64>>> {
+    let bytes = polyline.as_bytes();
+    let factor = factor(precision);
+    let mut coords = vec![];
+    let mut scaled: Coord<i64> = Coord { x: 0, y: 0 };
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let Ok(latitude_change) = decode_next(bytes, &mut pos) else {
+            coords.push(None);
+            continue;
+        };
+        if pos == bytes.len() {
+            // no longitude
+            coords.push(None);
+            break;
+        }
+        let Ok(longitude_change) = decode_next(bytes, &mut pos) else {
+            coords.push(None);
+            continue;
+        };
+        // damaged values can be large enough to overflow, in which case the changes are dropped
+        // and the coordinates after are decoded relative to the one before
+        let (Some(lat), Some(lon)) = (
+            scaled.y.checked_add(latitude_change),
+            scaled.x.checked_add(longitude_change),
+        ) else {
+            coords.push(None);
+            continue;
+        };
+        scaled = Coord { x: lon, y: lat };
+        coords.push(unscale_checked(scaled, factor));
+    }
+    coords
+}
+
+/// A damaged polyline decoded by [`decode_polyline_resync`]
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct RecoveredPolyline {
+    /// The runs of coordinates decoded between the damaged parts of the polyline
+    pub lines: MultiLineString<f64>,
+    /// The ranges of the polyline which were skipped over
+    pub skipped: Vec<Range<usize>>,
+}
+
+/// Decodes a damaged polyline, skipping over the parts which can't be decoded.
+///
+/// When a coordinate can't be decoded, is out of bounds or is missing its longitude, decoding
+/// skips forward to the next value boundary where a coordinate could start. As the changes in
+/// the skipped part are lost, the first coordinate after it is treated as an absolute position,
+/// and a boundary is only plausible if that position is within the bounds. Each run of
+/// coordinates decoded between skipped parts becomes a separate line.
+///
+/// Finding the boundary is a heuristic: the coordinates after a skipped part are only right if
+/// it resumed at the start of a coordinate which was encoded absolutely, such as the start of a
+/// polyline concatenated to the damaged one.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+/// use geo_types::line_string;
+///
+/// // two polylines joined by a damaged character
+/// let recovered = polyline::decode_polyline_resync("_ibE_seK_ibE_ibE!_seK_ibE", 5);
+/// assert_eq!(recovered.skipped, [16..17]);
+/// assert_eq!(recovered.lines.0[0], line_string![(x: 2.0, y: 1.0), (x: 3.0, y: 2.0)]);
+/// assert_eq!(recovered.lines.0[1], line_string![(x: 1.0, y: 2.0)]);
+/// ```
+pub fn decode_polyline_resync(polyline: &str, precision: u32) -> RecoveredPolyline {
+    let bytes = polyline.as_bytes();
+    let factor = factor(precision);
+    let origin = Coord { x: 0, y: 0 };
+    let mut lines = vec![];
+    let mut line = vec![];
+    let mut skipped = vec![];
+    let mut scaled = origin;
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let start = pos;
+        if let Some((next, coord)) = next_coord(bytes, &mut pos, scaled, factor) {
+            scaled = next;
+            line.push(coord);
+            continue;
+        }
+
+        if !line.is_empty() {
+            lines.push(LineString::new(std::mem::take(&mut line)));
+        }
+        // `pos` is where decoding failed, so resume at the next plausible boundary
+        while pos < bytes.len() {
+            if is_boundary(bytes, pos)
+                && next_coord(bytes, &mut pos.clone(), origin, factor).is_some()
+            {
+                break;
+            }
+            pos += 1;
+        }
+        skipped.push(start..pos);
+        scaled = origin;
+    }
+    if !line.is_empty() {
+        lines.push(LineString::new(line));
+    }
+    RecoveredPolyline {
+        lines: MultiLineString::new(lines),
+        skipped,
+    }
+}
+
+/// A repair made by [`repair`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Repair {
+    /// Curly quotes or apostrophes were replaced with the backticks they had been substituted
+    /// for, such as by a word processor
+    ReplacedQuotes,
+    /// Doubled backslashes were collapsed, as they had been escaped for JSON or a string literal
+    CollapsedBackslashes,
+    /// The last value was terminated, as its final chunk had been stripped
+    TerminatedLastValue,
+}
+
+/// A polyline repaired by [`repair`], and the repairs made to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Repaired {
+    pub polyline: String,
+    pub repairs: Vec<Repair>,
+}
+
+/// Repairs the damage polylines commonly suffer in transport, before they're decoded.
+///
+/// Polylines never contain quotes or apostrophes, so these are replaced with backticks. Runs of
+/// backslashes are halved if they're all of even length, as they are once escaped; a polyline
+/// with a backslash on its own hasn't been escaped. A last value missing its final chunk is
+/// terminated, keeping the bits which remain.
+///
+/// Polylines which still can't be decoded are reported as a [`PolylineError::DecodeError`] or
+/// [`PolylineError::NoLongError`], at their index in the repaired polyline. The coordinates
+/// aren't checked against the bounds, as they depend on the precision.
+///
+/// # Examples
+///
+/// ```
+/// use polyline::{self, Repair};
+///
+/// let repaired = polyline::repair("_p~iF~ps|U_ulLnnqC_mqNvxq\u{2019}@").unwrap();
+/// assert_eq!(repaired.polyline, "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+/// assert_eq!(repaired.repairs, [Repair::ReplacedQuotes]);
+/// ```
+pub fn repair(polyline: &str) -> Result<Repaired, PolylineError> {
+    const QUOTES: [char; 3] = ['\'', '\u{2018}', '\u{2019}'];
+
+    let mut repairs = vec![];
+    let mut repaired = polyline.to_string();
+    if repaired.contains(QUOTES) {
+        repaired = repaired.replace(QUOTES, "`");
+        repairs.push(Repair::ReplacedQuotes);
+    }
+    if repaired.contains('\\')
+        && repaired
+            .split(|c| c != '\\')
+            .all(|backslashes| backslashes.len() % 2 == 0)
+    {
+        repaired = repaired.replace("\\\\", "\\");
+        repairs.push(Repair::CollapsedBackslashes);
+    }
+
+    let bytes = repaired.as_bytes();
+    let mut pos = 0;
+    let mut values = 0;
+    let mut last_start = 0;
+    while pos < bytes.len() {
+        last_start = pos;
+        decode_next(bytes, &mut pos)?;
+        values += 1;
+    }
+    if values % 2 == 1 {
+        return Err(PolylineError::NoLongError { idx: last_start });
+    }
+    if bytes
+        .last()
+        .is_some_and(|&byte| DECODING_TABLE[byte as usize] >= 0x20)
+    {
+        let value = decode_next(bytes, &mut last_start.clone())?;
+        // the last value starts after a terminating chunk, which is ASCII
+        repaired.truncate(last_start);
+        let mut terminated = vec![];
+        encode(value, &mut terminated);
+        // SAFETY: encoded values are ASCII
+        repaired.push_str(unsafe { std::str::from_utf8_unchecked(&terminated) });
+        repairs.push(Repair::TerminatedLastValue);
+    }
+    Ok(Repaired {
+        polyline: repaired,
+        repairs,
+    })
+}
+
+/// Decodes the coordinate at `pos` relative to `scaled`, if it can be decoded and is within the
+/// bounds, returning its scaled and unscaled positions.
+///
+/// If it can't be, `pos` is left where decoding failed.
+fn next_coord(
+    bytes: &[u8],
+    pos: &mut usize,
+    scaled: Coord<i64>,
+    factor: f64,
+) -> Option<(Coord<i64>, Coord<f64>)> {
+    let latitude_change = decode_next(bytes, pos).ok()?;
+    if *pos == bytes.len() {
+        return None;
+    }
+    let longitude_change = decode_next(bytes, pos).ok()?;
+    let scaled = Coord {
+        x: scaled.x.checked_add(longitude_change)?,
+        y: scaled.y.checked_add(latitude_change)?,
+    };
+    unscale_checked(scaled, factor).map(|coord| (scaled, coord))
+}
+
+/// Whether a value could start at `pos`, which isn't just after a continuation chunk.
+fn is_boundary(bytes: &[u8], pos: usize) -> bool {
+    pos == 0 || !(0x20..INVALID_CHUNK).contains(&DECODING_TABLE[bytes[pos - 1] as usize])
+}
+
+/// Unscales a coordinate, if it's within the bounds.
+fn unscale_checked(scaled: Coord<i64>, factor: f64) -> Option<Coord<f64>> {
+    let coord = Coord {
+        x: scaled.x as f64 / factor,
+        y: scaled.y as f64 / factor,
+    };
+    let in_bounds = (MIN_LATITUDE..=MAX_LATITUDE).contains(&coord.y)
+        && (MIN_LONGITUDE..=MAX_LONGITUDE).contains(&coord.x);
+    in_bounds.then_some(coord)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_polyline;
+
+    #[test]
+    fn decodes_lossily() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let expected: Vec<_> = decode_polyline(polyline, 5)
+            .unwrap()
+            .0
+            .into_iter()
+            .map(Some)
+            .collect();
+        assert_eq!(decode_polyline_lossy(polyline, 5), expected);
+        assert_eq!(decode_polyline_lossy("", 5), []);
+
+        // a damaged longitude, and a damaged last value
+        assert_eq!(
+            decode_polyline_lossy("_ibE!_seK_ibE_ibE_ibE", 5),
+            [
+                None,
+                Some(Coord { x: 1.0, y: 2.0 }),
+                Some(Coord { x: 2.0, y: 3.0 }),
+            ]
+        );
+        assert_eq!(
+            decode_polyline_lossy("_ibE_seK_ib!", 5),
+            [Some(Coord { x: 2.0, y: 1.0 }), None]
+        );
+
+        // out of bounds, then back within them
+        assert_eq!(
+            decode_polyline_lossy("_cidP?~bidP?", 4),
+            [None, Some(Coord { x: 0.0, y: 0.0 })]
+        );
+
+        // a latitude without a longitude
+        assert_eq!(
+            decode_polyline_lossy("??_ibE", 5),
+            [Some(Coord { x: 0.0, y: 0.0 }), None]
+        );
+
+        // the latitudes overflow after the sixteenth coordinate
+        let coords = decode_polyline_lossy(&"~~~~~~~~~~~^".repeat(64), 19);
+        assert_eq!(coords.len(), 32);
+        assert!(coords[..16].iter().all(Option::is_some));
+        assert!(coords[16..].iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn resynchronizes() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let recovered = decode_polyline_resync(polyline, 5);
+        assert_eq!(
+            recovered.lines,
+            MultiLineString::new(vec![decode_polyline(polyline, 5).unwrap()])
+        );
+        assert!(recovered.skipped.is_empty());
+
+        // an extra character, and a latitude without a longitude
+        let recovered = decode_polyline_resync("_ibE_seK!_ibE_seK_ibE", 5);
+        assert_eq!(recovered.skipped, [8..9, 17..21]);
+        assert_eq!(
+            recovered.lines.0,
+            [
+                LineString::from(vec![[2.0, 1.0]]),
+                LineString::from(vec![[2.0, 1.0]]),
+            ]
+        );
+
+        // out of bounds
+        let recovered = decode_polyline_resync("_cidP?_ibE_seK", 4);
+        assert_eq!(recovered.skipped.len(), 1);
+        assert_eq!(recovered.skipped[0], 0..6);
+        assert_eq!(recovered.lines.0, [LineString::from(vec![[20.0, 10.0]])]);
+
+        let recovered = decode_polyline_resync("!!!", 5);
+        assert_eq!(recovered.skipped.len(), 1);
+        assert_eq!(recovered.skipped[0], 0..3);
+        assert!(recovered.lines.0.is_empty());
+    }
+
+    #[test]
+    fn repairs() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        assert_eq!(
+            repair(polyline).unwrap(),
+            Repaired {
+                polyline: polyline.to_string(),
+                repairs: vec![],
+            }
+        );
+
+        let damaged = "_p~iF~ps|U_ulLnnqC_mqNvxq\u{2018}";
+        let repaired = repair(damaged).unwrap();
+        assert_eq!(
+            repaired.repairs,
+            [Repair::ReplacedQuotes, Repair::TerminatedLastValue]
+        );
+        assert_eq!(
+            repaired.polyline,
+            crate::canonicalize("_p~iF~ps|U_ulLnnqC_mqNvxq`", 5).unwrap()
+        );
+
+        // escaped backslashes
+        let polyline = "??\\??\\\\?";
+        let escaped = polyline.replace('\\', "\\\\");
+        let repaired = repair(&escaped).unwrap();
+        assert_eq!(repaired.polyline, polyline);
+        assert_eq!(repaired.repairs, [Repair::CollapsedBackslashes]);
+        // a backslash on its own means the polyline isn't escaped
+        let polyline = "??\\??\\\\?";
+        assert_eq!(repair(polyline).unwrap().polyline, polyline);
+
+        assert_eq!(
+            repair("_ibE!").unwrap_err(),
+            PolylineError::DecodeError { idx: 4 }
+        );
+        assert_eq!(
+            repair("_ibE_seK_ibE").unwrap_err(),
+            PolylineError::NoLongError { idx: 8 }
+        );
+    }
+}
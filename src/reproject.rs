@@ -0,0 +1,172 @@
+//! Reprojection while encoding or decoding, enabled with the `proj` feature
+
+use crate::errors::PolylineError;
+use crate::{estimate_point_count, Decoder, Encoder};
+use geo_types::{Coord, LineString};
+use proj4rs::transform::transform;
+use proj4rs::Proj;
+
+/// Polylines hold WGS84 longitudes and latitudes.
+fn wgs84() -> Proj {
+    Proj::from_proj_string("+proj=longlat +datum=WGS84 +no_defs")
+        .expect("the WGS84 definition is valid")
+}
+
+fn reproject(src: &Proj, dst: &Proj, coord: Coord<f64>) -> Result<Coord<f64>, PolylineError> {
+    let mut point = (coord.x, coord.y, 0.0);
+    transform(src, dst, &mut point).map_err(|err| PolylineError::ProjError {
+        message: err.to_string(),
+    })?;
+    Ok(Coord {
+        x: point.0,
+        y: point.1,
+    })
+}
+
+/// Decodes a Google Encoded Polyline, reprojecting each coordinate from WGS84 into `proj` in
+/// the same pass.
+///
+/// Like PROJ, `proj4rs` measures angles in radians, so a geographic `proj` yields radians
+/// rather than degrees. Coordinates are checked for errors before they're reprojected.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+/// use proj4rs::Proj;
+///
+/// // EPSG:3857
+/// let mercator = Proj::from_proj_string(
+///     "+proj=merc +a=6378137 +b=6378137 +lat_ts=0 +lon_0=0 +x_0=0 +y_0=0 +k=1 +units=m +nadgrids=@null +no_defs",
+/// )
+/// .unwrap();
+/// let decoded = polyline::decode_polyline_reproject("??_ibE_seK", 5, &mercator).unwrap();
+/// assert_eq!(decoded.0[0].x, 0.0);
+/// assert!((decoded.0[1].x - 222_638.98).abs() < 0.01);
+/// assert!((decoded.0[1].y - 111_325.14).abs() < 0.01);
+/// ```
+pub fn decode_polyline_reproject(
+    polyline: &str,
+    precision: u32,
+    proj: &Proj,
+) -> Result<LineString<f64>, PolylineError> {
+    let wgs84 = wgs84();
+    let mut coordinates = Vec::with_capacity(estimate_point_count(polyline));
+    for coordinate in Decoder::new(polyline.as_bytes(), precision) {
+        let coordinate = coordinate?;
+        let radians = Coord {
+            x: coordinate.x.to_radians(),
+            y: coordinate.y.to_radians(),
+        };
+        coordinates.push(reproject(&wgs84, proj, radians)?);
+    }
+    Ok(LineString::new(coordinates))
+}
+
+/// Encodes coordinates in `proj` as a Google Encoded Polyline, reprojecting each into WGS84 in
+/// the same pass.
+///
+/// The reprojected coordinates are checked for errors, just as
+/// [`encode_coordinates`](crate::encode_coordinates) checks its input, and coordinates which
+/// `proj` can't reproject are reported as a [`PolylineError::ProjError`].
+///
+/// # Examples
+///
+/// ```
+/// use geo_types::line_string;
+/// use polyline;
+/// use proj4rs::Proj;
+///
+/// // EPSG:3857
+/// let mercator = Proj::from_proj_string(
+///     "+proj=merc +a=6378137 +b=6378137 +lat_ts=0 +lon_0=0 +x_0=0 +y_0=0 +k=1 +units=m +nadgrids=@null +no_defs",
+/// )
+/// .unwrap();
+/// let coords = line_string![(x: 0.0, y: 0.0), (x: 222_638.98, y: 111_325.14)];
+/// let encoded = polyline::encode_coordinates_reproject(coords, 5, &mercator).unwrap();
+/// assert_eq!(encoded, "??_ibE_seK");
+/// ```
+pub fn encode_coordinates_reproject<C>(
+    coordinates: C,
+    precision: u32,
+    proj: &Proj,
+) -> Result<String, PolylineError>
+where
+    C: IntoIterator<Item = Coord<f64>>,
+{
+    let wgs84 = wgs84();
+    let mut encoder = Encoder::new(precision);
+    for coordinate in coordinates {
+        let radians = reproject(proj, &wgs84, coordinate)?;
+        encoder.push(Coord {
+            x: radians.x.to_degrees(),
+            y: radians.y.to_degrees(),
+        })?;
+    }
+    encoder.finish().map(|polyline| polyline.into_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_polyline;
+
+    fn mercator() -> Proj {
+        Proj::from_proj_string(
+            "+proj=merc +a=6378137 +b=6378137 +lat_ts=0 +lon_0=0 +x_0=0 +y_0=0 +k=1 +units=m +nadgrids=@null +no_defs",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn round_trips() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let projected = decode_polyline_reproject(polyline, 5, &mercator()).unwrap();
+        for (projected, coord) in projected
+            .coords()
+            .zip(decode_polyline(polyline, 5).unwrap())
+        {
+            // spherical Web Mercator
+            let x = coord.x.to_radians() * 6_378_137.0;
+            let y = (std::f64::consts::FRAC_PI_4 + coord.y.to_radians() / 2.0)
+                .tan()
+                .ln()
+                * 6_378_137.0;
+            assert!((projected.x - x).abs() < 1e-6, "{} {}", projected.x, x);
+            assert!((projected.y - y).abs() < 1e-6, "{} {}", projected.y, y);
+        }
+        assert_eq!(
+            encode_coordinates_reproject(projected, 5, &mercator()).unwrap(),
+            polyline
+        );
+        assert_eq!(
+            decode_polyline_reproject("", 5, &mercator()).unwrap(),
+            LineString::new(vec![])
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!(
+            decode_polyline_reproject("_ibE_seK_seK", 5, &mercator()).unwrap_err(),
+            PolylineError::NoLongError { idx: 8 }
+        );
+        // the poles are infinitely far away in Web Mercator
+        assert!(matches!(
+            decode_polyline_reproject("_cidP?", 5, &mercator()),
+            Err(PolylineError::ProjError { .. })
+        ));
+        // reprojected coordinates are checked
+        let coords = [
+            Coord { x: 0.0, y: 0.0 },
+            Coord {
+                x: f64::INFINITY,
+                y: 0.0,
+            },
+        ];
+        assert!(matches!(
+            encode_coordinates_reproject(coords, 5, &mercator()),
+            Err(PolylineError::LongitudeCoordError { idx: 1, .. })
+        ));
+    }
+}
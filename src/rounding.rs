@@ -0,0 +1,142 @@
+//! Encoding coordinates with the rounding of other implementations
+
+use crate::errors::PolylineError;
+use crate::{check_coord, check_scaled, encode, encode_coordinates, factor};
+use geo_types::Coord;
+
+/// How coordinates are rounded to the precision they're encoded at.
+///
+/// Only coordinates lying exactly halfway between two values at the precision, such as
+/// `-0.000005` at precision 5, are rounded differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Rounding {
+    /// Halves are rounded away from zero, as Rust's [`f64::round`] rounds them
+    #[default]
+    HalfAwayFromZero,
+    /// Halves are rounded up, towards positive infinity, as JavaScript's `Math.round` rounds
+    /// them in Google's reference implementation.
+    ///
+    /// Negative coordinates ending in 5 are encoded as Google's encoder would encode them, so
+    /// polylines can be compared with ones it produced.
+    HalfUp,
+}
+
+/// Encodes coordinates as a polyline, rounding them to `precision` with `rounding`.
+///
+/// # Examples
+///
+/// ```
+/// use polyline::{self, Rounding};
+/// use geo_types::line_string;
+///
+/// let coords = line_string![(x: -120.000005, y: -38.500005)];
+/// let encoded = polyline::encode_coordinates_with_rounding(coords.clone(), 5, Rounding::HalfUp);
+/// assert_eq!(encoded.unwrap(), "~o~iF~nl{U");
+/// assert_eq!(polyline::encode_coordinates(coords, 5).unwrap(), "`p~iF`ol{U");
+/// ```
+pub fn encode_coordinates_with_rounding<C>(
+    coordinates: C,
+    precision: u32,
+    rounding: Rounding,
+) -> Result<String, PolylineError>
+where
+    C: IntoIterator<Item = Coord<f64>>,
+{
+    match rounding {
+        Rounding::HalfAwayFromZero => encode_coordinates(coordinates, precision),
+        Rounding::HalfUp => {
+            let factor = factor(precision);
+            let mut output = vec![];
+            let mut previous = Coord { x: 0, y: 0 };
+            for (i, next) in coordinates.into_iter().enumerate() {
+                check_coord(i, next)?;
+                let scaled = check_scaled(
+                    i,
+                    next,
+                    Coord {
+                        x: round_half_up(next.x * factor),
+                        y: round_half_up(next.y * factor),
+                    },
+                )?;
+                encode(scaled.y - previous.y, &mut output);
+                encode(scaled.x - previous.x, &mut output);
+                previous = scaled;
+            }
+            // SAFETY: encoded values are ASCII
+            Ok(unsafe { String::from_utf8_unchecked(output) })
+        }
+    }
+}
+
+/// Rounds as JavaScript's `Math.round` does.
+///
+/// Unlike `(n + 0.5).floor()`, this doesn't round values just below a half up.
+fn round_half_up(n: f64) -> i64 {
+    let floor = n.floor();
+    if n - floor >= 0.5 {
+        floor as i64 + 1
+    } else {
+        floor as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_halves_up() {
+        assert_eq!(round_half_up(-0.5), 0);
+        assert_eq!(round_half_up(-2.5), -2);
+        assert_eq!(round_half_up(-2.6), -3);
+        assert_eq!(round_half_up(2.5), 3);
+        assert_eq!(round_half_up(0.49999999999999994), 0);
+
+        let coords = [Coord {
+            x: -0.000025,
+            y: -0.000005,
+        }];
+        assert_eq!(
+            encode_coordinates_with_rounding(coords, 5, Rounding::HalfUp).unwrap(),
+            "?B"
+        );
+        assert_eq!(
+            encode_coordinates_with_rounding(coords, 5, Rounding::default()).unwrap(),
+            "@D"
+        );
+
+        // other coordinates are rounded the same way
+        let coords = [
+            Coord { x: -120.2, y: 38.5 },
+            Coord {
+                x: -120.95,
+                y: 40.7,
+            },
+            Coord {
+                x: 0.000025,
+                y: -0.000026,
+            },
+        ];
+        assert_eq!(
+            encode_coordinates_with_rounding(coords, 5, Rounding::HalfUp).unwrap(),
+            encode_coordinates(coords, 5).unwrap()
+        );
+    }
+
+    #[test]
+    fn checks_bounds() {
+        assert_eq!(
+            encode_coordinates_with_rounding(
+                [Coord { x: 0.0, y: 0.0 }, Coord { x: 181.0, y: 0.0 }],
+                5,
+                Rounding::HalfUp
+            )
+            .unwrap_err(),
+            PolylineError::LongitudeCoordError {
+                coord: 181.0,
+                idx: 1
+            }
+        );
+    }
+}
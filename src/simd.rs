@@ -0,0 +1,399 @@
+//! Vectorised encoding and decoding, enabled with the nightly-only `simd` feature
+//!
+//! The decode fast path validates and classifies 16 bytes at a time, locating the terminating
+//! chunk of every value from a bitmask instead of branching on each byte. The encode fast path
+//! bounds-checks, scales, delta-encodes and zigzag-encodes four coordinates at a time.
+//!
+//! Both only handle well-formed input: anything the scalar code would reject (or treat
+//! specially) makes them bail out, so that the scalar code can produce the exact same result or
+//! error.
+//!
+//! The fast paths are compiled for several instruction sets, and the best one supported by the
+//! running CPU (AVX2, SSE4.1 or NEON) is picked at runtime, so binaries built for the baseline
+//! target still benefit. On any other CPU, the scalar code is used.
+
+use crate::errors::PolylineError;
+use crate::{
+    emit, encode_coord, MAX_LATITUDE, MAX_LONGITUDE, MAX_SCALED, MIN_LATITUDE, MIN_LONGITUDE,
+};
+use geo_types::Coord;
+use std::simd::prelude::*;
+use std::simd::StdFloat;
+
+const LANES: usize = 16;
+/// The most chunks the scalar decoder accepts in a single value before reporting an overflow
+const MAX_CHUNKS: u32 = 12;
+
+/// Accumulates the 5-bit chunks of the value currently being decoded.
+#[derive(Default)]
+struct Value {
+    bits: u64,
+    chunks: u32,
+}
+
+impl Value {
+    /// Adds a chunk (already offset by 63), returning the finished value at a terminating chunk.
+    #[inline(always)]
+    fn push(&mut self, chunk: u8) -> Option<Option<i64>> {
+        if self.chunks == MAX_CHUNKS {
+            return None;
+        }
+        self.bits |= u64::from(chunk & 0x1f) << (5 * self.chunks);
+        self.chunks += 1;
+        if chunk & 0x20 != 0 {
+            return Some(None);
+        }
+        let bits = std::mem::take(self).bits;
+        Some(Some(if bits & 1 > 0 {
+            !(bits >> 1)
+        } else {
+            bits >> 1
+        } as i64))
+    }
+}
+
+/// The instruction set used by the vectorised code
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Level {
+    #[cfg(target_arch = "x86_64")]
+    Avx2,
+    #[cfg(target_arch = "x86_64")]
+    Sse41,
+    #[cfg(target_arch = "aarch64")]
+    Neon,
+    Scalar,
+}
+
+/// Detects the best instruction set supported by the running CPU.
+fn level() -> Level {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            return Level::Avx2;
+        }
+        if is_x86_feature_detected!("sse4.1") {
+            return Level::Sse41;
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            return Level::Neon;
+        }
+    }
+    Level::Scalar
+}
+
+/// Decodes the zigzag-encoded deltas of `bytes` into `out`.
+///
+/// Returns `None` if `bytes` contains anything but complete, in-range values.
+#[inline(always)]
+fn decode_deltas(bytes: &[u8], out: &mut Vec<i64>) -> Option<()> {
+    let mut value = Value::default();
+    let mut blocks = bytes.chunks_exact(LANES);
+    for block in &mut blocks {
+        let block = u8x16::from_slice(block);
+        if (block.simd_lt(u8x16::splat(63)) | block.simd_gt(u8x16::splat(126))).any() {
+            return None;
+        }
+        let chunks = (block - u8x16::splat(63)).to_array();
+        let terminators = (u8x16::from_array(chunks) & u8x16::splat(0x20))
+            .simd_eq(u8x16::splat(0))
+            .to_bitmask();
+        if terminators == 0 {
+            // A value this long always overflows
+            return None;
+        }
+        let mut start = 0;
+        let mut remaining = terminators;
+        while remaining != 0 {
+            let end = remaining.trailing_zeros() as usize;
+            remaining &= remaining - 1;
+            for &chunk in &chunks[start..end] {
+                value.push(chunk)?;
+            }
+            out.push(value.push(chunks[end])??);
+            start = end + 1;
+        }
+        for &chunk in &chunks[start..] {
+            value.push(chunk)?;
+        }
+    }
+    for &byte in blocks.remainder() {
+        if !(63..=126).contains(&byte) {
+            return None;
+        }
+        if let Some(delta) = value.push(byte - 63)? {
+            out.push(delta);
+        }
+    }
+    // An unterminated trailing value is left to the scalar decoder
+    (value.chunks == 0).then_some(())
+}
+
+/// Decodes a polyline whose values are all well-formed and in bounds.
+///
+/// Returns `None` if the scalar decoder has to be used instead, either to report an error, to
+/// handle unusual input, or because the CPU lacks a supported instruction set.
+pub(crate) fn decode(bytes: &[u8], factor: f64) -> Option<Vec<Coord<f64>>> {
+    // SAFETY: `level` only returns instruction sets supported by the running CPU
+    unsafe {
+        match level() {
+            #[cfg(target_arch = "x86_64")]
+            Level::Avx2 => decode_avx2(bytes, factor),
+            #[cfg(target_arch = "x86_64")]
+            Level::Sse41 => decode_sse41(bytes, factor),
+            #[cfg(target_arch = "aarch64")]
+            Level::Neon => decode_neon(bytes, factor),
+            Level::Scalar => None,
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn decode_avx2(bytes: &[u8], factor: f64) -> Option<Vec<Coord<f64>>> {
+    decode_vectorised(bytes, factor)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse4.1")]
+unsafe fn decode_sse41(bytes: &[u8], factor: f64) -> Option<Vec<Coord<f64>>> {
+    decode_vectorised(bytes, factor)
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn decode_neon(bytes: &[u8], factor: f64) -> Option<Vec<Coord<f64>>> {
+    decode_vectorised(bytes, factor)
+}
+
+#[inline(always)]
+fn decode_vectorised(bytes: &[u8], factor: f64) -> Option<Vec<Coord<f64>>> {
+    let mut deltas = Vec::with_capacity(bytes.len() / 2);
+    decode_deltas(bytes, &mut deltas)?;
+    if deltas.len() % 2 != 0 {
+        return None;
+    }
+    let mut coords = Vec::with_capacity(deltas.len() / 2);
+    let (mut scaled_lat, mut scaled_lon) = (0i64, 0i64);
+    for pair in deltas.chunks_exact(2) {
+        // the scalar decoder reports sums which overflow
+        scaled_lat = scaled_lat.checked_add(pair[0])?;
+        scaled_lon = scaled_lon.checked_add(pair[1])?;
+        let lat = scaled_lat as f64 / factor;
+        let lon = scaled_lon as f64 / factor;
+        if !(MIN_LATITUDE..=MAX_LATITUDE).contains(&lat)
+            || !(MIN_LONGITUDE..=MAX_LONGITUDE).contains(&lon)
+        {
+            return None;
+        }
+        coords.push(Coord { x: lon, y: lat });
+    }
+    Some(coords)
+}
+
+/// The number of coordinates encoded at a time
+const BATCH: usize = 4;
+
+/// Encodes a batch of coordinates, or returns `false` without writing anything if one of them
+/// is out of bounds.
+#[inline(always)]
+fn encode_batch(
+    coords: &[Coord<f64>; BATCH],
+    factor: f64,
+    previous: &mut Coord<i64>,
+    output: &mut Vec<u8>,
+) -> bool {
+    let lat = f64x4::from_array(coords.map(|c| c.y));
+    let lon = f64x4::from_array(coords.map(|c| c.x));
+    let valid = lat.simd_ge(f64x4::splat(MIN_LATITUDE))
+        & lat.simd_le(f64x4::splat(MAX_LATITUDE))
+        & lon.simd_ge(f64x4::splat(MIN_LONGITUDE))
+        & lon.simd_le(f64x4::splat(MAX_LONGITUDE));
+    if !valid.all() {
+        return false;
+    }
+    let (lat, lon) = (
+        (lat * f64x4::splat(factor)).round(),
+        (lon * f64x4::splat(factor)).round(),
+    );
+    let max_scaled = f64x4::splat(MAX_SCALED as f64);
+    if !(lat.abs().simd_lt(max_scaled) & lon.abs().simd_lt(max_scaled)).all() {
+        return false;
+    }
+    let lat: i64x4 = lat.cast();
+    let lon: i64x4 = lon.cast();
+    let prev_lat = simd_swizzle!(lat, i64x4::splat(previous.y), [4, 0, 1, 2]);
+    let prev_lon = simd_swizzle!(lon, i64x4::splat(previous.x), [4, 0, 1, 2]);
+    let (lat_delta, lon_delta) = (lat - prev_lat, lon - prev_lon);
+    let lat_zigzag = (lat_delta << 1) ^ (lat_delta >> 63);
+    let lon_zigzag = (lon_delta << 1) ^ (lon_delta >> 63);
+    let (first, second) = lat_zigzag.interleave(lon_zigzag);
+    for value in first.to_array().into_iter().chain(second.to_array()) {
+        emit(value as u64, output);
+    }
+    *previous = Coord {
+        x: lon[BATCH - 1],
+        y: lat[BATCH - 1],
+    };
+    true
+}
+
+/// Encodes `coordinates` in batches, falling back to the scalar encoder for a batch containing
+/// an invalid coordinate and for the final, partial batch, or for everything if the CPU lacks a
+/// supported instruction set.
+pub(crate) fn encode_batches(
+    coordinates: impl Iterator<Item = (usize, Coord<f64>)>,
+    factor: f64,
+    previous: &mut Coord<i64>,
+    output: &mut Vec<u8>,
+) -> Result<(), PolylineError> {
+    // SAFETY: `level` only returns instruction sets supported by the running CPU
+    unsafe {
+        match level() {
+            #[cfg(target_arch = "x86_64")]
+            Level::Avx2 => encode_batches_avx2(coordinates, factor, previous, output),
+            #[cfg(target_arch = "x86_64")]
+            Level::Sse41 => encode_batches_sse41(coordinates, factor, previous, output),
+            #[cfg(target_arch = "aarch64")]
+            Level::Neon => encode_batches_neon(coordinates, factor, previous, output),
+            Level::Scalar => {
+                for (i, next) in coordinates {
+                    encode_coord(i, next, factor, previous, output)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn encode_batches_avx2(
+    coordinates: impl Iterator<Item = (usize, Coord<f64>)>,
+    factor: f64,
+    previous: &mut Coord<i64>,
+    output: &mut Vec<u8>,
+) -> Result<(), PolylineError> {
+    encode_batches_vectorised(coordinates, factor, previous, output)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse4.1")]
+unsafe fn encode_batches_sse41(
+    coordinates: impl Iterator<Item = (usize, Coord<f64>)>,
+    factor: f64,
+    previous: &mut Coord<i64>,
+    output: &mut Vec<u8>,
+) -> Result<(), PolylineError> {
+    encode_batches_vectorised(coordinates, factor, previous, output)
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn encode_batches_neon(
+    coordinates: impl Iterator<Item = (usize, Coord<f64>)>,
+    factor: f64,
+    previous: &mut Coord<i64>,
+    output: &mut Vec<u8>,
+) -> Result<(), PolylineError> {
+    encode_batches_vectorised(coordinates, factor, previous, output)
+}
+
+#[inline(always)]
+fn encode_batches_vectorised(
+    mut coordinates: impl Iterator<Item = (usize, Coord<f64>)>,
+    factor: f64,
+    previous: &mut Coord<i64>,
+    output: &mut Vec<u8>,
+) -> Result<(), PolylineError> {
+    loop {
+        let mut batch = [(0, Coord::zero()); BATCH];
+        let mut len = 0;
+        for slot in batch.iter_mut() {
+            let Some(next) = coordinates.next() else {
+                break;
+            };
+            *slot = next;
+            len += 1;
+        }
+        if len == BATCH && encode_batch(&batch.map(|(_, c)| c), factor, previous, output) {
+            continue;
+        }
+        for &(i, next) in &batch[..len] {
+            encode_coord(i, next, factor, previous, output)?;
+        }
+        if len < BATCH {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_scalar() {
+        let polyline = include_str!("../resources/route-geometry-sweden-west-coast.polyline6");
+        let coords = decode(polyline.as_bytes(), 1e6).unwrap();
+        assert_eq!(
+            coords,
+            crate::Decoder::new(polyline.as_bytes(), 6)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn bails_out_on_invalid_input() {
+        assert_eq!(decode(b"_p~iF~ps|U_u\xf0lLnnqC_mqNvxq`@", 1e5), None);
+        assert_eq!(decode(b"_ibE_seK_seK", 1e5), None);
+        assert_eq!(decode(b"_ibE_seK_seK_se", 1e5), None);
+        assert_eq!(decode(b"ugh_ugh", 1e5), None);
+        assert_eq!(decode(&[b'_'; 40], 1e5), None);
+        let overflowing = "~~~~~~~~~~~^".repeat(64);
+        assert_eq!(decode(overflowing.as_bytes(), 1e19), None);
+        assert!(matches!(
+            crate::decode_polyline(&overflowing, 19).unwrap_err(),
+            PolylineError::LatitudeCoordError { idx: 384, .. }
+        ));
+    }
+
+    #[test]
+    fn encode_matches_scalar() {
+        let polyline = include_str!("../resources/route-geometry-sweden-west-coast.polyline6");
+        let coords = crate::decode_polyline(polyline, 6).unwrap();
+        assert_eq!(crate::encode_coordinates(coords, 6).unwrap(), polyline);
+    }
+
+    #[test]
+    fn encode_reports_scalar_errors() {
+        let mut coords = vec![Coord { x: 1.0, y: 1.0 }; 9];
+        coords[6].x = 181.0;
+        assert_eq!(
+            crate::encode_coordinates(coords, 5).unwrap_err(),
+            PolylineError::LongitudeCoordError {
+                coord: 181.0,
+                idx: 6
+            }
+        );
+    }
+
+    #[test]
+    fn every_supported_level_agrees() {
+        let polyline = include_str!("../resources/route-geometry-sweden-west-coast.polyline6");
+        let expected = decode_vectorised(polyline.as_bytes(), 1e6);
+        assert!(expected.is_some());
+        #[cfg(target_arch = "x86_64")]
+        unsafe {
+            if is_x86_feature_detected!("avx2") {
+                assert_eq!(decode_avx2(polyline.as_bytes(), 1e6), expected);
+            }
+            if is_x86_feature_detected!("sse4.1") {
+                assert_eq!(decode_sse41(polyline.as_bytes(), 1e6), expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! [`proptest`] strategies generating coordinates and polylines, for property-testing pipelines
+//! which use this crate.
+//!
+//! # Examples
+//!
+//! ```
+//! use polyline::strategies;
+//! use proptest::prelude::*;
+//!
+//! proptest!(|(coords in strategies::coordinates(5, 10))| {
+//!     let encoded = polyline::encode_coordinates(coords.clone(), 5).unwrap();
+//!     prop_assert_eq!(polyline::decode_polyline(&encoded, 5).unwrap(), coords);
+//! });
+//! ```
+
+use crate::{encode, encode_coordinates, factor, MAX_LATITUDE, MAX_LONGITUDE};
+use geo_types::{Coord, LineString};
+use proptest::prelude::*;
+
+/// The highest precision whose deltas the decoder can hold
+const MAX_PRECISION: u32 = 15;
+
+/// Generates up to `max_len` coordinates which round trip through polylines at `precision`.
+///
+/// Coordinates lie on the grid of the precision, anywhere within the bounds, so encoding and
+/// decoding them gives back the same coordinates.
+///
+/// # Panics
+///
+/// If `precision` is above 15, as polylines at higher precisions can't always be decoded.
+pub fn coordinates(precision: u32, max_len: usize) -> impl Strategy<Value = LineString<f64>> {
+    assert!(
+        precision <= MAX_PRECISION,
+        "precision {} is above {}",
+        precision,
+        MAX_PRECISION
+    );
+    let factor = factor(precision);
+    let max_x = (MAX_LONGITUDE * factor) as i64;
+    let max_y = (MAX_LATITUDE * factor) as i64;
+    prop::collection::vec((-max_x..=max_x, -max_y..=max_y), 0..=max_len).prop_map(move |coords| {
+        coords
+            .into_iter()
+            .map(|(x, y)| Coord {
+                x: x as f64 / factor,
+                y: y as f64 / factor,
+            })
+            .collect()
+    })
+}
+
+/// Generates polylines of up to `max_len` coordinates at `precision`, which decode without
+/// errors.
+///
+/// # Panics
+///
+/// If `precision` is above 15.
+pub fn valid_polyline(precision: u32, max_len: usize) -> impl Strategy<Value = String> {
+    coordinates(precision, max_len)
+        .prop_map(move |coords| encode_coordinates(coords, precision).unwrap())
+}
+
+/// Generates polylines of up to `max_len` coordinates at `precision`, which fail to decode.
+///
+/// These are valid polylines which have been corrupted, by inserting a character outside the
+/// polyline alphabet, appending a latitude without a longitude, or appending the first chunk of
+/// a value which is cut off before its end.
+///
+/// # Panics
+///
+/// If `precision` is above 15.
+pub fn invalid_polyline(precision: u32, max_len: usize) -> impl Strategy<Value = String> {
+    prop_oneof![
+        (
+            valid_polyline(precision, max_len),
+            any::<prop::sample::Index>(),
+            b' '..b'?'
+        )
+            .prop_map(|(mut polyline, idx, invalid)| {
+                polyline.insert(idx.index(polyline.len() + 1), char::from(invalid));
+                polyline
+            }),
+        (valid_polyline(precision, max_len), any::<i32>()).prop_map(|(polyline, latitude)| {
+            let mut polyline = polyline.into_bytes();
+            encode(i64::from(latitude), &mut polyline);
+            // SAFETY: encoded values are ASCII
+            unsafe { String::from_utf8_unchecked(polyline) }
+        }),
+        valid_polyline(precision, max_len).prop_map(|mut polyline| {
+            polyline.push('_');
+            polyline
+        }),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_polyline;
+
+    proptest! {
+        #[test]
+        fn coordinates_round_trip(coords in coordinates(6, 20)) {
+            let encoded = encode_coordinates(coords.clone(), 6).unwrap();
+            prop_assert_eq!(decode_polyline(&encoded, 6).unwrap(), coords);
+        }
+
+        #[test]
+        fn valid_polylines_decode(polyline in valid_polyline(15, 20)) {
+            prop_assert!(decode_polyline(&polyline, 15).is_ok());
+        }
+
+        #[test]
+        fn invalid_polylines_fail(polyline in invalid_polyline(5, 20)) {
+            prop_assert!(decode_polyline(&polyline, 5).is_err());
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Decoding of polylines which arrive as a [`Stream`] of chunks.
+//!
+//! [`CoordStream`] is itself a [`Stream`] of coordinates, which only pulls the next chunk once
+//! the coordinates of the previous ones have been consumed, so slow consumers apply
+//! backpressure to the source.
+
+use crate::errors::PolylineError;
+use crate::incremental::IncrementalDecoder;
+use futures_core::Stream;
+use geo_types::Coord;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A [`Stream`] of the coordinates decoded from a stream of polyline chunks.
+///
+/// Chunks may split the polyline anywhere, including in the middle of a coordinate. The stream
+/// ends after the first error.
+///
+/// # Examples
+///
+/// ```
+/// use futures::executor::block_on;
+/// use futures::stream::{self, StreamExt};
+/// use polyline::stream::CoordStream;
+///
+/// let chunks = stream::iter(["_p~iF~ps|U_ul", "LnnqC_mqNvxq`@"]);
+/// let coords: Vec<_> = block_on(CoordStream::new(chunks, 5).collect());
+/// assert_eq!(coords.len(), 3);
+/// ```
+#[derive(Debug)]
+pub struct CoordStream<S, B> {
+    chunks: S,
+    chunk: Option<B>,
+    pos: usize,
+    decoder: IncrementalDecoder,
+    done: bool,
+}
+
+impl<S, B> CoordStream<S, B>
+where
+    S: Stream<Item = B> + Unpin,
+    B: AsRef<[u8]> + Unpin,
+{
+    /// Creates a stream decoding the chunks of a polyline encoded at `precision`.
+    pub fn new(chunks: S, precision: u32) -> Self {
+        CoordStream {
+            chunks,
+            chunk: None,
+            pos: 0,
+            decoder: IncrementalDecoder::new(precision),
+            done: false,
+        }
+    }
+
+    /// Returns the underlying stream of chunks.
+    pub fn into_inner(self) -> S {
+        self.chunks
+    }
+}
+
+impl<S, B> Stream for CoordStream<S, B>
+where
+    S: Stream<Item = B> + Unpin,
+    B: AsRef<[u8]> + Unpin,
+{
+    type Item = Result<Coord<f64>, PolylineError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        while !this.done {
+            if let Some(chunk) = &this.chunk {
+                let bytes = chunk.as_ref();
+                while this.pos < bytes.len() {
+                    let byte = bytes[this.pos];
+                    this.pos += 1;
+                    match this.decoder.push(byte) {
+                        Ok(Some(coord)) => return Poll::Ready(Some(Ok(coord))),
+                        Ok(None) => {}
+                        Err(err) => {
+                            this.done = true;
+                            return Poll::Ready(Some(Err(err)));
+                        }
+                    }
+                }
+                this.chunk = None;
+            }
+
+            match Pin::new(&mut this.chunks).poll_next(cx) {
+                Poll::Ready(Some(chunk)) => {
+                    this.chunk = Some(chunk);
+                    this.pos = 0;
+                }
+                Poll::Ready(None) => {
+                    this.done = true;
+                    return Poll::Ready(this.decoder.finish().transpose());
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CoordStream;
+    use crate::errors::PolylineError;
+    use crate::Decoder;
+    use futures::executor::block_on;
+    use futures::stream::{self, StreamExt};
+
+    #[test]
+    fn decodes_across_chunks() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let expected: Vec<_> = Decoder::new(polyline.as_bytes(), 5).collect();
+        for split in 0..=polyline.len() {
+            let (start, end) = polyline.as_bytes().split_at(split);
+            let chunks = stream::iter([start, &[], end]);
+            let coords: Vec<_> = block_on(CoordStream::new(chunks, 5).collect());
+            assert_eq!(coords, expected);
+        }
+    }
+
+    #[test]
+    fn ends_after_an_error() {
+        let chunks = stream::iter(["_ibE_seK_se", "K"]);
+        let coords: Vec<_> = block_on(CoordStream::new(chunks, 5).collect());
+        assert_eq!(coords.len(), 2);
+        assert_eq!(coords[1], Err(PolylineError::NoLongError { idx: 8 }));
+    }
+}
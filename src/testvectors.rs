@@ -0,0 +1,233 @@
+//! Conformance test vectors, enabled with the `test-util` feature.
+//!
+//! These are the cases this crate's own codec is tested against: Google's canonical example,
+//! coordinates on the bounds, tiny deltas and values close to overflowing, along with invalid
+//! polylines and coordinates and the errors they should be reported with. Wrappers and other
+//! implementations can run the same suite to check they agree with this crate.
+//!
+//! # Examples
+//!
+//! ```
+//! use polyline::testvectors;
+//!
+//! for vector in testvectors::valid() {
+//!     let encoded = polyline::encode_coordinates(vector.coordinates.clone(), vector.precision);
+//!     assert_eq!(encoded.unwrap(), vector.encoded, "{}", vector.name);
+//! }
+//! ```
+
+use crate::errors::PolylineError;
+use geo_types::{line_string, LineString};
+
+/// Coordinates and the polyline they encode to
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct ValidVector {
+    pub name: &'static str,
+    pub coordinates: LineString<f64>,
+    pub precision: u32,
+    pub encoded: &'static str,
+}
+
+/// A polyline which can't be decoded, and the error decoding it reports
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub struct InvalidPolyline {
+    pub name: &'static str,
+    pub encoded: &'static str,
+    pub precision: u32,
+    pub error: PolylineError,
+}
+
+/// Coordinates which can't be encoded, and the error encoding them reports
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub struct InvalidCoordinates {
+    pub name: &'static str,
+    pub coordinates: LineString<f64>,
+    pub precision: u32,
+    pub error: PolylineError,
+}
+
+/// Coordinates which round trip through polylines.
+pub fn valid() -> Vec<ValidVector> {
+    vec![
+        ValidVector {
+            name: "Google's example",
+            coordinates: line_string![
+                (x: -120.2, y: 38.5),
+                (x: -120.95, y: 40.7),
+                (x: -126.453, y: 43.252),
+            ],
+            precision: 5,
+            encoded: "_p~iF~ps|U_ulLnnqC_mqNvxq`@",
+        },
+        ValidVector {
+            name: "Google's example at precision 6",
+            coordinates: line_string![
+                (x: -120.2, y: 38.5),
+                (x: -120.95, y: 40.7),
+                (x: -126.453, y: 43.252),
+            ],
+            precision: 6,
+            encoded: "_izlhA~rlgdF_{geC~ywl@_kwzCn`{nI",
+        },
+        ValidVector {
+            name: "no coordinates",
+            coordinates: line_string![],
+            precision: 5,
+            encoded: "",
+        },
+        ValidVector {
+            name: "the origin",
+            coordinates: line_string![(x: 0.0, y: 0.0)],
+            precision: 5,
+            encoded: "??",
+        },
+        ValidVector {
+            name: "repeated coordinates",
+            coordinates: line_string![(x: 2.0, y: 1.0), (x: 2.0, y: 1.0), (x: 2.0, y: 1.0)],
+            precision: 5,
+            encoded: "_ibE_seK????",
+        },
+        ValidVector {
+            name: "corners of the bounds",
+            coordinates: line_string![
+                (x: 180.0, y: 90.0),
+                (x: -180.0, y: -90.0),
+                (x: 180.0, y: -90.0),
+                (x: -180.0, y: 90.0),
+            ],
+            precision: 5,
+            encoded: "_cidP_gsia@~fsia@~ngtcA?_ogtcA_gsia@~ngtcA",
+        },
+        ValidVector {
+            name: "deltas of a single unit",
+            coordinates: line_string![
+                (x: 0.00001, y: 0.00001),
+                (x: 0.00002, y: 0.00001),
+                (x: 0.00002, y: 0.0),
+            ],
+            precision: 5,
+            encoded: "AA?A@?",
+        },
+        ValidVector {
+            name: "negative deltas of a single unit at precision 6",
+            coordinates: line_string![(x: -0.000001, y: 0.000001)],
+            precision: 6,
+            encoded: "A@",
+        },
+        ValidVector {
+            name: "deltas close to overflowing",
+            coordinates: line_string![(x: 180.0, y: 90.0), (x: -180.0, y: -90.0)],
+            precision: 15,
+            encoded: "___cxln`|z~C___gqz}ayv~H~~~fqz}ayv~H~~~ncv|dsn~R",
+        },
+    ]
+}
+
+/// Polylines which can't be decoded.
+pub fn invalid_polylines() -> Vec<InvalidPolyline> {
+    vec![
+        InvalidPolyline {
+            name: "a latitude without a longitude",
+            encoded: "_ibE_seK_seK",
+            precision: 5,
+            error: PolylineError::NoLongError { idx: 8 },
+        },
+        InvalidPolyline {
+            name: "a character outside the alphabet",
+            encoded: "_ibE!",
+            precision: 5,
+            error: PolylineError::DecodeError { idx: 4 },
+        },
+        InvalidPolyline {
+            name: "a value too long to fit in 64 bits",
+            encoded: "__________________________________",
+            precision: 5,
+            error: PolylineError::DecodeError { idx: 12 },
+        },
+        InvalidPolyline {
+            name: "a latitude out of bounds",
+            encoded: "_cidP?",
+            precision: 4,
+            error: PolylineError::LatitudeCoordError {
+                coord: 900.0,
+                idx: 0,
+            },
+        },
+        InvalidPolyline {
+            name: "a longitude out of bounds",
+            encoded: "?_gsia@",
+            precision: 4,
+            error: PolylineError::LongitudeCoordError {
+                coord: 1800.0,
+                idx: 1,
+            },
+        },
+    ]
+}
+
+/// Coordinates which can't be encoded.
+pub fn invalid_coordinates() -> Vec<InvalidCoordinates> {
+    vec![
+        InvalidCoordinates {
+            name: "a latitude out of bounds",
+            coordinates: line_string![(x: 0.0, y: 91.0)],
+            precision: 5,
+            error: PolylineError::LatitudeCoordError {
+                coord: 91.0,
+                idx: 0,
+            },
+        },
+        InvalidCoordinates {
+            name: "a longitude out of bounds",
+            coordinates: line_string![(x: 0.0, y: 0.0), (x: -180.5, y: 0.0)],
+            precision: 5,
+            error: PolylineError::LongitudeCoordError {
+                coord: -180.5,
+                idx: 1,
+            },
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_polyline, encode_coordinates};
+
+    #[test]
+    fn conforms() {
+        for vector in valid() {
+            assert_eq!(
+                encode_coordinates(vector.coordinates.clone(), vector.precision).unwrap(),
+                vector.encoded,
+                "{}",
+                vector.name
+            );
+            assert_eq!(
+                decode_polyline(vector.encoded, vector.precision).unwrap(),
+                vector.coordinates,
+                "{}",
+                vector.name
+            );
+        }
+        for vector in invalid_polylines() {
+            assert_eq!(
+                decode_polyline(vector.encoded, vector.precision).unwrap_err(),
+                vector.error,
+                "{}",
+                vector.name
+            );
+        }
+        for vector in invalid_coordinates() {
+            assert_eq!(
+                encode_coordinates(vector.coordinates, vector.precision).unwrap_err(),
+                vector.error,
+                "{}",
+                vector.name
+            );
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! Incremental decoding of polylines read from a Tokio [`AsyncRead`].
+//!
+//! [`AsyncDecoder`] yields coordinates as soon as the bytes encoding them have been read, so
+//! giant polylines, such as those in streaming HTTP responses, can be decoded in constant
+//! memory.
+
+use crate::errors::PolylineError;
+use crate::incremental::IncrementalDecoder;
+use geo_types::Coord;
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// The number of bytes read at a time
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// Decodes the coordinates of a polyline as it's read from an [`AsyncRead`].
+///
+/// Invalid polylines are reported as [`io::ErrorKind::InvalidData`] errors wrapping the
+/// [`PolylineError`], which can be recovered with [`io::Error::into_inner`].
+///
+/// # Examples
+///
+/// ```
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// use polyline::tokio_io::AsyncDecoder;
+///
+/// let body: &[u8] = b"_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+/// let mut decoder = AsyncDecoder::new(body, 5);
+/// while let Some(coord) = decoder.next_coord().await? {
+///     println!("{:?}", coord);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// # }).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct AsyncDecoder<R> {
+    reader: R,
+    decoder: IncrementalDecoder,
+    buffer: Box<[u8]>,
+    pos: usize,
+    len: usize,
+    done: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncDecoder<R> {
+    /// Creates a decoder reading a polyline encoded at `precision` from `reader`.
+    pub fn new(reader: R, precision: u32) -> Self {
+        AsyncDecoder {
+            reader,
+            decoder: IncrementalDecoder::new(precision),
+            buffer: vec![0; BUFFER_SIZE].into_boxed_slice(),
+            pos: 0,
+            len: 0,
+            done: false,
+        }
+    }
+
+    /// Decodes the next coordinate, reading more of the polyline as needed.
+    ///
+    /// Returns `Ok(None)` once the polyline has been decoded, or after an error.
+    pub async fn next_coord(&mut self) -> io::Result<Option<Coord<f64>>> {
+        while !self.done {
+            if self.pos == self.len {
+                self.pos = 0;
+                self.len = match self.reader.read(&mut self.buffer).await {
+                    Ok(len) => len,
+                    Err(err) => {
+                        self.done = true;
+                        return Err(err);
+                    }
+                };
+                if self.len == 0 {
+                    self.done = true;
+                    return self.decoder.finish().map_err(invalid_data);
+                }
+            }
+
+            while self.pos < self.len {
+                let byte = self.buffer[self.pos];
+                self.pos += 1;
+                match self.decoder.push(byte) {
+                    Ok(Some(coord)) => return Ok(Some(coord)),
+                    Ok(None) => {}
+                    Err(err) => {
+                        self.done = true;
+                        return Err(invalid_data(err));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+fn invalid_data(err: PolylineError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AsyncDecoder;
+    use crate::decode_polyline;
+    use crate::errors::PolylineError;
+    use tokio::io::{AsyncRead, AsyncReadExt};
+
+    fn decode_all(
+        reader: impl AsyncRead + Unpin,
+    ) -> (Vec<geo_types::Coord<f64>>, Option<PolylineError>) {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let mut decoder = AsyncDecoder::new(reader, 5);
+            let mut coords = vec![];
+            loop {
+                match decoder.next_coord().await {
+                    Ok(Some(coord)) => coords.push(coord),
+                    Ok(None) => return (coords, None),
+                    Err(err) => {
+                        let err = err
+                            .into_inner()
+                            .unwrap()
+                            .downcast::<PolylineError>()
+                            .unwrap();
+                        return (coords, Some(*err));
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn decodes_across_reads() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        // deliver the polyline in pieces which split coordinates
+        let (start, rest) = polyline.as_bytes().split_at(3);
+        let (middle, end) = rest.split_at(9);
+        let (coords, err) = decode_all(start.chain(middle).chain(end));
+        assert_eq!(err, None);
+        assert_eq!(coords, decode_polyline(polyline, 5).unwrap().0);
+    }
+
+    #[test]
+    fn reports_errors() {
+        let (coords, err) = decode_all("_ibE_seK_seK".as_bytes());
+        assert_eq!(coords.len(), 1);
+        assert_eq!(err, Some(PolylineError::NoLongError { idx: 8 }));
+    }
+}
@@ -0,0 +1,123 @@
+//! Swift and Kotlin bindings, enabled with the `uniffi` feature
+//!
+//! The foreign-language sources are generated from the compiled library by the
+//! `uniffi-bindgen` binary (feature `uniffi-bindgen`).
+
+use crate::errors::PolylineError;
+use crate::{decode_polyline, encode_coordinates};
+use geo_types::Coord;
+
+/// A coordinate in degrees
+#[derive(Debug, Clone, Copy, PartialEq, uniffi::Record)]
+pub struct Coordinate {
+    pub longitude: f64,
+    pub latitude: f64,
+}
+
+/// Errors raised to Swift and Kotlin callers
+#[derive(Debug, PartialEq, uniffi::Error)]
+pub enum CodecError {
+    /// A longitude outside the range `-180.0..180.0` at the given index
+    LongitudeOutOfBounds { coord: f64, idx: u64 },
+    /// A latitude outside the range `-90.0..90.0` at the given index
+    LatitudeOutOfBounds { coord: f64, idx: u64 },
+    /// A latitude at the given string index without a longitude following it
+    MissingLongitude { idx: u64 },
+    /// A character at the given string index which could not be decoded
+    InvalidCharacter { idx: u64 },
+    /// A coordinate at the given array index which could not be encoded
+    Encoding { idx: u64 },
+    /// Any other failure
+    Other { message: String },
+}
+
+impl std::error::Error for CodecError {}
+impl std::fmt::Display for CodecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CodecError::LongitudeOutOfBounds { coord, idx } => {
+                write!(f, "longitude out of bounds: {} at position {}", coord, idx)
+            }
+            CodecError::LatitudeOutOfBounds { coord, idx } => {
+                write!(f, "latitude out of bounds: {} at position {}", coord, idx)
+            }
+            CodecError::MissingLongitude { idx } => {
+                write!(f, "no longitude to go with latitude at index: {}", idx)
+            }
+            CodecError::InvalidCharacter { idx } => {
+                write!(f, "cannot decode character at index {}", idx)
+            }
+            CodecError::Encoding { idx } => {
+                write!(f, "the coordinate at index: {} could not be encoded", idx)
+            }
+            CodecError::Other { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<PolylineError> for CodecError {
+    fn from(err: PolylineError) -> Self {
+        match err {
+            PolylineError::LongitudeCoordError { coord, idx } => CodecError::LongitudeOutOfBounds {
+                coord,
+                idx: idx as u64,
+            },
+            PolylineError::LatitudeCoordError { coord, idx } => CodecError::LatitudeOutOfBounds {
+                coord,
+                idx: idx as u64,
+            },
+            PolylineError::NoLongError { idx } => CodecError::MissingLongitude { idx: idx as u64 },
+            PolylineError::DecodeError { idx } => CodecError::InvalidCharacter { idx: idx as u64 },
+            PolylineError::CoordEncodingError { idx, .. } => {
+                CodecError::Encoding { idx: idx as u64 }
+            }
+            err => CodecError::Other {
+                message: err.to_string(),
+            },
+        }
+    }
+}
+
+/// Encodes a list of coordinates as a polyline.
+#[uniffi::export]
+pub fn encode(coordinates: Vec<Coordinate>, precision: u32) -> Result<String, CodecError> {
+    let coordinates = coordinates.into_iter().map(|c| Coord {
+        x: c.longitude,
+        y: c.latitude,
+    });
+    Ok(encode_coordinates(coordinates, precision)?)
+}
+
+/// Decodes a polyline into a list of coordinates.
+#[uniffi::export]
+pub fn decode(polyline: String, precision: u32) -> Result<Vec<Coordinate>, CodecError> {
+    let line_string = decode_polyline(&polyline, precision)?;
+    Ok(line_string
+        .coords()
+        .map(|c| Coordinate {
+            longitude: c.x,
+            latitude: c.y,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_errors() {
+        assert_eq!(
+            decode("ugh_ugh".to_string(), 5),
+            Err(CodecError::LatitudeOutOfBounds {
+                coord: 49775.95019,
+                idx: 0
+            })
+        );
+        let coords = vec![Coordinate {
+            longitude: 2.0,
+            latitude: 1.0,
+        }];
+        assert_eq!(encode(coords, 5).unwrap(), "_ibE_seK");
+    }
+}
@@ -0,0 +1,392 @@
+//! Checking polylines for every problem they have
+
+use crate::{
+    decode_next, encoded_value_len, factor, zigzag, DECODING_TABLE, INVALID_CHUNK, MAX_LATITUDE,
+    MAX_LONGITUDE, MIN_LATITUDE, MIN_LONGITUDE,
+};
+use geo_types::Coord;
+use std::ops::RangeInclusive;
+
+/// How serious a [`Problem`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    /// The polyline decodes, but isn't what encoding its coordinates would produce
+    Warning,
+    /// The polyline can't be decoded
+    Error,
+}
+
+/// What's wrong with part of a polyline
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum ProblemKind {
+    /// A character outside the polyline alphabet
+    InvalidCharacter,
+    /// A value with too many chunks to fit in 64 bits
+    ValueTooLong,
+    /// A latitude at the end of the polyline, without a longitude
+    MissingLongitude,
+    LatitudeOutOfBounds {
+        /// The latitude outside the range `-90.0..90.0`
+        coord: f64,
+    },
+    LongitudeOutOfBounds {
+        /// The longitude outside the range `-180.0..180.0`
+        coord: f64,
+    },
+    /// A value encoded in more characters than needed, or with characters above the printable
+    /// range
+    NonCanonicalValue,
+    /// A last value without a final chunk
+    UnterminatedValue,
+}
+
+impl ProblemKind {
+    /// Returns how serious the problem is.
+    pub fn severity(&self) -> Severity {
+        match self {
+            ProblemKind::NonCanonicalValue | ProblemKind::UnterminatedValue => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+/// A problem found by [`validate`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct Problem {
+    /// The string index of the value or character with the problem
+    pub idx: usize,
+    pub kind: ProblemKind,
+    pub severity: Severity,
+}
+
+/// Every problem found in a polyline by [`validate`], in the order they appear
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct ValidationReport {
+    pub problems: Vec<Problem>,
+}
+
+impl ValidationReport {
+    /// Whether the polyline can be decoded, though it may have warnings.
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// Returns the problems which stop the polyline from being decoded.
+    pub fn errors(&self) -> impl Iterator<Item = &Problem> {
+        self.problems
+            .iter()
+            .filter(|problem| problem.severity == Severity::Error)
+    }
+
+    fn push(&mut self, idx: usize, kind: ProblemKind) {
+        self.problems.push(Problem {
+            idx,
+            kind,
+            severity: kind.severity(),
+        });
+    }
+}
+
+/// Checks a polyline encoded at `precision`, reporting every problem found rather than stopping
+/// at the first, for summarizing the quality of a dataset.
+///
+/// Errors are the problems which stop [`decode_polyline`](crate::decode_polyline) from decoding
+/// the polyline, and the first is at the index it reports. Warnings are problems it accepts, as
+/// [`decode_polyline_strict`](crate::decode_polyline_strict) wouldn't.
+///
+/// After a value which can't be decoded, checking resumes with the next character as the start
+/// of a new coordinate. The change lost with the value offsets the coordinates after it, so
+/// their bounds are checked relative to the coordinate before the problem.
+///
+/// # Examples
+///
+/// ```
+/// use polyline::{self, ProblemKind, Severity};
+///
+/// let report = polyline::validate("_ibE!_seK_ibE``??", 5);
+/// assert!(!report.is_valid());
+/// assert_eq!(report.problems.len(), 2);
+/// assert_eq!(report.problems[0].kind, ProblemKind::InvalidCharacter);
+/// assert_eq!(report.problems[0].idx, 4);
+/// assert_eq!(report.problems[1].kind, ProblemKind::NonCanonicalValue);
+/// assert_eq!(report.problems[1].severity, Severity::Warning);
+/// ```
+pub fn validate(polyline: &str, precision: u32) -> ValidationReport {
+    let bytes = polyline.as_bytes();
+    let factor = factor(precision);
+    let mut report = ValidationReport::default();
+    let mut scaled: Coord<i64> = Coord { x: 0, y: 0 };
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let lat_start = pos;
+        let Some(latitude_change) = check_value(bytes, &mut pos, &mut report) else {
+            continue;
+        };
+        // sums which overflow are out of bounds at any precision, as the decoder reports them
+        let lat = scaled.y.checked_add(latitude_change);
+        if let Some(coord) = out_of_bounds(scaled.y, latitude_change, lat, factor, LATITUDES) {
+            report.push(lat_start, ProblemKind::LatitudeOutOfBounds { coord });
+        }
+        if pos == bytes.len() {
+            report.push(lat_start, ProblemKind::MissingLongitude);
+            break;
+        }
+
+        let lon_start = pos;
+        let Some(longitude_change) = check_value(bytes, &mut pos, &mut report) else {
+            continue;
+        };
+        let lon = scaled.x.checked_add(longitude_change);
+        if let Some(coord) = out_of_bounds(scaled.x, longitude_change, lon, factor, LONGITUDES) {
+            report.push(lon_start, ProblemKind::LongitudeOutOfBounds { coord });
+        }
+        // the coordinates after an overflow are checked relative to the one before it
+        scaled = Coord {
+            x: lon.unwrap_or(scaled.x),
+            y: lat.unwrap_or(scaled.y),
+        };
+    }
+    report
+}
+
+/// Whether a polyline encoded at `precision` can be decoded, checking its structure and bounds
+/// without allocating or collecting its coordinates.
+///
+/// This is equivalent to `decode_polyline(polyline, precision).is_ok()`, for cheaply filtering
+/// out invalid polylines when ingesting large datasets.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// assert!(polyline::is_valid_polyline("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5));
+/// assert!(!polyline::is_valid_polyline("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 4));
+/// assert!(!polyline::is_valid_polyline("_p~iF~ps|U_ul!", 5));
+/// ```
+pub fn is_valid_polyline(polyline: &str, precision: u32) -> bool {
+    let bytes = polyline.as_bytes();
+    let factor = factor(precision);
+    let mut scaled: Coord<i64> = Coord { x: 0, y: 0 };
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let Ok(latitude_change) = decode_next(bytes, &mut pos) else {
+            return false;
+        };
+        if pos == bytes.len() {
+            return false;
+        }
+        let Ok(longitude_change) = decode_next(bytes, &mut pos) else {
+            return false;
+        };
+        // sums which overflow are out of bounds, as the decoder reports them
+        let (Some(lat), Some(lon)) = (
+            scaled.y.checked_add(latitude_change),
+            scaled.x.checked_add(longitude_change),
+        ) else {
+            return false;
+        };
+        scaled = Coord { x: lon, y: lat };
+        if !(MIN_LATITUDE..=MAX_LATITUDE).contains(&(scaled.y as f64 / factor))
+            || !(MIN_LONGITUDE..=MAX_LONGITUDE).contains(&(scaled.x as f64 / factor))
+        {
+            return false;
+        }
+    }
+    true
+}
+
+const LATITUDES: RangeInclusive<f64> = MIN_LATITUDE..=MAX_LATITUDE;
+const LONGITUDES: RangeInclusive<f64> = MIN_LONGITUDE..=MAX_LONGITUDE;
+
+/// Returns the coordinate of the `sum` of a scaled coordinate and a change, if it's outside
+/// `bounds` or the sum overflowed.
+fn out_of_bounds(
+    scaled: i64,
+    change: i64,
+    sum: Option<i64>,
+    factor: f64,
+    bounds: RangeInclusive<f64>,
+) -> Option<f64> {
+    match sum {
+        Some(sum) if bounds.contains(&(sum as f64 / factor)) => None,
+        Some(sum) => Some(sum as f64 / factor),
+        None => Some((scaled as f64 + change as f64) / factor),
+    }
+}
+
+/// Decodes the value at `pos`, reporting its problems.
+///
+/// Returns `None` if it can't be decoded, leaving `pos` after the character which failed.
+fn check_value(bytes: &[u8], pos: &mut usize, report: &mut ValidationReport) -> Option<i64> {
+    let start = *pos;
+    let value = match decode_next(bytes, pos) {
+        Ok(value) => value,
+        Err(_) => {
+            let idx = *pos - 1;
+            let kind = if DECODING_TABLE[bytes[idx] as usize] == INVALID_CHUNK {
+                ProblemKind::InvalidCharacter
+            } else {
+                ProblemKind::ValueTooLong
+            };
+            report.push(idx, kind);
+            return None;
+        }
+    };
+
+    let chunks = &bytes[start..*pos];
+    if chunks
+        .last()
+        .is_some_and(|&byte| DECODING_TABLE[byte as usize] >= 0x20)
+    {
+        report.push(start, ProblemKind::UnterminatedValue);
+    } else if chunks.len() != encoded_value_len(zigzag(value))
+        || chunks
+            .iter()
+            .any(|&byte| DECODING_TABLE[byte as usize] >= 0x40)
+    {
+        report.push(start, ProblemKind::NonCanonicalValue);
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::PolylineError;
+    use crate::{decode_polyline, decode_polyline_strict, testvectors};
+
+    #[test]
+    fn reports_every_problem() {
+        assert_eq!(
+            validate("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5),
+            ValidationReport::default()
+        );
+        assert!(validate("", 5).is_valid());
+
+        let report = validate("_cidP?!_ibE_seK", 4);
+        assert_eq!(
+            kinds(&report),
+            [
+                (0, ProblemKind::LatitudeOutOfBounds { coord: 900.0 }),
+                (6, ProblemKind::InvalidCharacter),
+                (7, ProblemKind::LatitudeOutOfBounds { coord: 910.0 }),
+            ]
+        );
+        assert_eq!(report.errors().count(), 3);
+
+        // sums which overflow are out of bounds
+        let report = validate(&"~~~~~~~~~~~^".repeat(64), 19);
+        assert!(matches!(
+            report.problems[0],
+            Problem {
+                idx: 384,
+                kind: ProblemKind::LatitudeOutOfBounds { .. },
+                severity: Severity::Error,
+            }
+        ));
+
+        // latitudes are checked even without a longitude
+        assert_eq!(
+            kinds(&validate("_cidP!", 4)),
+            [
+                (0, ProblemKind::LatitudeOutOfBounds { coord: 900.0 }),
+                (5, ProblemKind::InvalidCharacter),
+            ]
+        );
+        assert_eq!(
+            kinds(&validate("_cidP", 4)),
+            [
+                (0, ProblemKind::LatitudeOutOfBounds { coord: 900.0 }),
+                (0, ProblemKind::MissingLongitude),
+            ]
+        );
+
+        let report = validate("??``??_", 5);
+        assert_eq!(
+            kinds(&report),
+            [
+                (2, ProblemKind::NonCanonicalValue),
+                (6, ProblemKind::UnterminatedValue),
+                (6, ProblemKind::MissingLongitude),
+            ]
+        );
+        assert_eq!(report.problems[0].severity, Severity::Warning);
+        assert!(!report.is_valid());
+        assert_eq!(report.errors().count(), 1);
+        assert!(validate("??``??", 5).is_valid());
+    }
+
+    fn kinds(report: &ValidationReport) -> Vec<(usize, ProblemKind)> {
+        report
+            .problems
+            .iter()
+            .map(|problem| (problem.idx, problem.kind))
+            .collect()
+    }
+
+    #[test]
+    fn checks_validity() {
+        for vector in testvectors::valid() {
+            assert!(
+                is_valid_polyline(vector.encoded, vector.precision),
+                "{}",
+                vector.name
+            );
+        }
+        for vector in testvectors::invalid_polylines() {
+            assert!(
+                !is_valid_polyline(vector.encoded, vector.precision),
+                "{}",
+                vector.name
+            );
+        }
+    }
+
+    #[test]
+    fn agrees_with_decoders() {
+        for (polyline, precision) in [
+            ("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5),
+            ("_p~iF~ps|U_ulLnnqC_mqNvxq``?", 5),
+            ("_p~iF~ps|U_ulLnnqC_mqNvxq`", 5),
+            ("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 4),
+            ("_ibE_seK_seK", 5),
+            ("_ibE!", 5),
+            ("??\x7f??", 5),
+            ("__________________________________", 5),
+            ("_cidP?", 4),
+            ("_cidP!", 4),
+            ("_cidP", 4),
+            ("?_gsia@", 4),
+            (&"~~~~~~~~~~~^".repeat(64), 19),
+        ] {
+            let report = validate(polyline, precision);
+            let first_error = report.errors().next().map(|problem| problem.idx);
+            let decode_error = decode_polyline(polyline, precision)
+                .err()
+                .map(|err| match err {
+                    PolylineError::LatitudeCoordError { idx, .. }
+                    | PolylineError::LongitudeCoordError { idx, .. }
+                    | PolylineError::NoLongError { idx }
+                    | PolylineError::DecodeError { idx } => idx,
+                    err => panic!("{}", err),
+                });
+            assert_eq!(first_error, decode_error, "{}", polyline);
+            assert_eq!(
+                is_valid_polyline(polyline, precision),
+                decode_error.is_none(),
+                "{}",
+                polyline
+            );
+            assert_eq!(
+                report.problems.is_empty(),
+                decode_polyline_strict(polyline, precision).is_ok(),
+                "{}",
+                polyline
+            );
+        }
+    }
+}
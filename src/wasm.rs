@@ -0,0 +1,103 @@
+//! WebAssembly bindings, enabled with the `wasm` feature
+//!
+//! Coordinates cross the boundary in `(x, y)` / `(longitude, latitude)` order, matching the rest
+//! of this crate.
+
+use crate::errors::PolylineError;
+use crate::{encode_coordinates, estimate_point_count, Decoder};
+use geo_types::Coord;
+use js_sys::Float64Array;
+use wasm_bindgen::prelude::*;
+
+/// Encodes a flat array of interleaved `longitude, latitude` values as a polyline.
+#[wasm_bindgen]
+pub fn encode(coords: &[f64], precision: u32) -> Result<String, JsError> {
+    encode_interleaved(coords, precision).map_err(|message| JsError::new(&message))
+}
+
+fn encode_interleaved(coords: &[f64], precision: u32) -> Result<String, String> {
+    if !coords.len().is_multiple_of(2) {
+        return Err("coordinates must contain an even number of values".to_string());
+    }
+    let coords = coords.chunks_exact(2).map(|pair| Coord {
+        x: pair[0],
+        y: pair[1],
+    });
+    encode_coordinates(coords, precision).map_err(|e| e.to_string())
+}
+
+/// Decoded coordinates, stored as interleaved `longitude, latitude` values in WebAssembly memory
+#[wasm_bindgen]
+pub struct DecodedCoordinates {
+    values: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl DecodedCoordinates {
+    /// The number of decoded coordinates
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.values.len() / 2
+    }
+
+    /// Returns a `Float64Array` viewing the interleaved values without copying them.
+    ///
+    /// The view is only valid until this object is freed or WebAssembly memory grows (e.g. by
+    /// decoding another polyline); call `slice()` on it to keep a copy around.
+    pub fn view(&self) -> Float64Array {
+        // SAFETY: the returned view borrows `self.values`, which is not modified or freed
+        // before the JS side frees this object; this is documented on the method.
+        unsafe { Float64Array::view(&self.values) }
+    }
+}
+
+/// Decodes a polyline into interleaved `longitude, latitude` values.
+#[wasm_bindgen]
+pub fn decode(polyline: &str, precision: u32) -> Result<DecodedCoordinates, JsError> {
+    let values = decode_interleaved(polyline, precision)?;
+    Ok(DecodedCoordinates { values })
+}
+
+fn decode_interleaved(polyline: &str, precision: u32) -> Result<Vec<f64>, PolylineError> {
+    let mut values = Vec::with_capacity(estimate_point_count(polyline) * 2);
+    for coord in Decoder::new(polyline.as_bytes(), precision) {
+        let coord = coord?;
+        values.push(coord.x);
+        values.push(coord.y);
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interleaved() {
+        let values = [-120.2, 38.5, -120.95, 40.7, -126.453, 43.252];
+        assert_eq!(
+            encode_interleaved(&values, 5).unwrap(),
+            "_p~iF~ps|U_ulLnnqC_mqNvxq`@"
+        );
+        let decoded = decode_interleaved("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap();
+        assert_eq!(decoded, values);
+        assert_eq!(decoded.capacity(), values.len());
+
+        assert_eq!(
+            encode_interleaved(&values[..5], 5).unwrap_err(),
+            "coordinates must contain an even number of values"
+        );
+        assert_eq!(
+            encode_interleaved(&[0.0, 91.0], 5).unwrap_err(),
+            PolylineError::LatitudeCoordError {
+                coord: 91.0,
+                idx: 0
+            }
+            .to_string()
+        );
+        assert_eq!(
+            decode_interleaved("_p~iF~ps|U_ulL", 5).unwrap_err(),
+            PolylineError::NoLongError { idx: 10 }
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! Conversion between polylines and WKT, enabled with the `wkt` feature
+
+use crate::errors::PolylineError;
+use crate::{decode_polyline, encode_coordinates};
+use geo_types::LineString;
+use wkt::{ToWkt, TryFromWkt};
+
+/// Decodes a Google Encoded Polyline as a WKT `LINESTRING`.
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let wkt = polyline::polyline_to_wkt("_p~iF~ps|U_ulLnnqC", 5).unwrap();
+/// assert_eq!(wkt, "LINESTRING(-120.2 38.5,-120.95 40.7)");
+/// ```
+pub fn polyline_to_wkt(polyline: &str, precision: u32) -> Result<String, PolylineError> {
+    Ok(decode_polyline(polyline, precision)?.wkt_string())
+}
+
+/// Encodes a WKT `LINESTRING` as a Google Encoded Polyline.
+///
+/// WKT which can't be parsed, or holds any other kind of geometry, is reported as a
+/// [`PolylineError::WktError`].
+///
+/// # Examples
+///
+/// ```
+/// use polyline;
+///
+/// let polyline = polyline::wkt_to_polyline("LINESTRING(-120.2 38.5,-120.95 40.7)", 5).unwrap();
+/// assert_eq!(polyline, "_p~iF~ps|U_ulLnnqC");
+/// ```
+pub fn wkt_to_polyline(wkt: &str, precision: u32) -> Result<String, PolylineError> {
+    let line_string =
+        LineString::<f64>::try_from_wkt_str(wkt).map_err(|err| PolylineError::WktError {
+            message: err.to_string(),
+        })?;
+    encode_coordinates(line_string, precision)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        let wkt = polyline_to_wkt(polyline, 5).unwrap();
+        assert_eq!(wkt_to_polyline(&wkt, 5).unwrap(), polyline);
+        assert_eq!(polyline_to_wkt("", 5).unwrap(), "LINESTRING EMPTY");
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert!(matches!(
+            wkt_to_polyline("LINESTRING(1 2,", 5),
+            Err(PolylineError::WktError { .. })
+        ));
+        assert!(matches!(
+            wkt_to_polyline("POINT(1 2)", 5),
+            Err(PolylineError::WktError { .. })
+        ));
+        assert!(matches!(
+            wkt_to_polyline("LINESTRING(181 2,1 2)", 5),
+            Err(PolylineError::LongitudeCoordError { idx: 0, .. })
+        ));
+        assert_eq!(
+            polyline_to_wkt("_ibE_seK_seK", 5).unwrap_err(),
+            PolylineError::NoLongError { idx: 8 }
+        );
+    }
+}